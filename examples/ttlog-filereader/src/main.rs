@@ -10,7 +10,7 @@ use std::io::Read;
 
 // Copy of the snapshot structure from your lib
 #[derive(Serialize, Deserialize, Clone, Debug)]
-struct Snapshot {
+pub struct Snapshot {
   pub service: String,
   pub hostname: String,
   pub pid: u32,
@@ -20,7 +20,7 @@ struct Snapshot {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct Event {
+pub struct Event {
   pub timestamp: u64,
   pub level: String,
   pub message: String,
@@ -20,7 +20,7 @@ pub fn example_simple() -> Result<(), Box<dyn std::error::Error>> {
 
   for i in 0..10 {
     let _x = i;
-    trace!("Hello from thread {}", i = i);
+    trace!("Hello from thread {i}", i = i);
     std::thread::sleep(std::time::Duration::from_secs(1));
   }
 
@@ -12,7 +12,9 @@ fn main() {
   println!("=====================");
 
   // Run all examples
-  example_simple::example_simple();
+  if let Err(e) = example_simple::example_simple() {
+    eprintln!("example_simple failed: {}", e);
+  }
   // example_basic_logging();
   // example_structured_logging();
   // example_high_volume_logging();
@@ -189,7 +189,7 @@ impl ThroughputTester {
 
     let target_id = logger.interner.intern_target("bench::real_path");
     let file_id = logger.interner.intern_file(file!());
-    let message_id = std::num::NonZeroU16::new(logger.interner.intern_message("bench event"));
+    let message_id = std::num::NonZeroU32::new(logger.interner.intern_message("bench event"));
 
     let drain_stop = Arc::clone(&stop_flag);
     let drain_handle = thread::spawn(move || {
@@ -297,15 +297,9 @@ impl ThroughputTester {
             counter += 1;
 
             // Count pushes and track overwrite pressure explicitly.
-            match buffer.push(event) {
-              Ok(Some(_)) => {
-                local_ops += 1;
-                local_overwrites += 1;
-              },
-              Ok(None) => {
-                local_ops += 1;
-              },
-              Err(_) => {},
+            local_ops += 1;
+            if buffer.push(event).is_some() {
+              local_overwrites += 1;
             }
 
             // Pop occasionally to maintain flow
@@ -480,26 +474,18 @@ impl ConcurrencyTester {
 
       // Test operations on all buffers
       let mut operations = 0u64;
-      let mut all_successful = true;
 
       for (i, buffer) in buffers.iter().enumerate() {
         for j in 0..100 {
           let event = create_minimal_event((i * 100 + j) as u64);
-          if buffer.push(event).is_ok() {
-            operations += 1;
-          } else {
-            all_successful = false;
-            break;
-          }
-        }
-        if !all_successful {
-          break;
+          buffer.push_overwrite(event);
+          operations += 1;
         }
       }
 
       let test_duration = test_start.elapsed();
 
-      if all_successful && test_duration < Duration::from_secs(30) {
+      if test_duration < Duration::from_secs(30) {
         successful_buffers = *buffer_count;
         total_operations = operations;
       } else {
@@ -642,10 +628,9 @@ impl MemoryEfficiencyTester {
 
           while Instant::now() < end && !stop_flag.load(Ordering::Acquire) {
             let event = create_minimal_event(thread_id as u64 * 1_000_000 + counter);
-            if buffer.push(event).is_ok() {
-              local_bytes += event_size;
-              counter += 1;
-            }
+            buffer.push_overwrite(event);
+            local_bytes += event_size;
+            counter += 1;
 
             if counter.is_multiple_of(100) {
               let _ = buffer.pop();
@@ -791,10 +776,8 @@ impl BufferOperationsTester {
             let event = create_minimal_event(producer_id as u64 * 1000000 + event_counter);
             event_counter += 1;
 
-            // Count pushes explicitly, including overwrite-mode success.
-            if buffer.push(event).is_ok() {
-              local_ops += 1
-            }
+            buffer.push_overwrite(event);
+            local_ops += 1;
 
             if local_ops.is_multiple_of(10000) {
               thread::yield_now();
@@ -1086,17 +1069,9 @@ impl EndToEndTester {
             let ts_us = (Instant::now().duration_since(t0).as_micros() as u64) as u32;
             let mut ev = create_minimal_event(pid as u64 * 1_000_000 + ctr);
             ev.position = (ts_us, 0);
-            match buffer.push(ev) {
-              Ok(Some(_evicted)) => {
-                local_prod += 1;
-                local_drops += 1;
-              },
-              Ok(None) => {
-                local_prod += 1;
-              },
-              Err(_) => {
-                local_drops += 1;
-              },
+            local_prod += 1;
+            if buffer.push(ev).is_some() {
+              local_drops += 1;
             }
             ctr += 1;
             if (ctr & 0x3FF) == 0 {
@@ -1252,7 +1227,7 @@ fn create_minimal_event(counter: u64) -> LogEvent {
 
   // Intern common strings
   let target_id = interner.intern_target("bench");
-  let message_id = std::num::NonZeroU16::new(interner.intern_message("test message"));
+  let message_id = std::num::NonZeroU32::new(interner.intern_message("test message"));
   let kv_id = None;
   let file_id = interner.intern_file(file!());
 
@@ -1270,6 +1245,9 @@ fn create_minimal_event(counter: u64) -> LogEvent {
     message_id,
     file_id,
     position: (0, 0),
+    span_id: 0,
+    mono_ns: 0,
+    seq: counter,
   };
 
   // Optionally use counter to vary position for uniqueness
@@ -1289,7 +1267,7 @@ fn create_variable_size_event(counter: u64) -> LogEvent {
   };
 
   let target_id = interner.intern_target("bench");
-  let message_id = std::num::NonZeroU16::new(interner.intern_message(message));
+  let message_id = std::num::NonZeroU32::new(interner.intern_message(message));
   let file_id = interner.intern_file(file!());
   let kv_id = None;
 
@@ -1306,6 +1284,9 @@ fn create_variable_size_event(counter: u64) -> LogEvent {
     message_id,
     file_id,
     position: (0, 0),
+    span_id: 0,
+    mono_ns: 0,
+    seq: counter,
   };
 
   let line = (counter & 0xFFFF) as u32;
@@ -1,31 +1,37 @@
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::{
-  parse::{Parse, ParseStream},
+  parse::{Parse, ParseStream, Parser},
   parse_macro_input, Expr, Ident, LitStr, Token,
 };
 
 struct LogInput {
+  logger: Option<Expr>,
   kvs: Vec<(Ident, Expr)>,
   message: Option<LitStr>,
+  args: Vec<Expr>,
 }
 
 impl Parse for LogInput {
   fn parse(input: ParseStream) -> syn::Result<Self> {
+    let logger = parse_logger(input)?;
     let mut kvs = Vec::new();
     let mut message = None;
+    let mut args = Vec::new();
 
     while !input.is_empty() {
-      if input.peek(LitStr) {
-        if message.is_some() {
-          return Err(input.error("multiple message strings not allowed"));
-        }
-        message = Some(input.parse()?);
-      } else {
+      if input.peek(Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
         let key: Ident = input.parse()?;
         input.parse::<Token![=]>()?;
         let value: Expr = input.parse()?;
         kvs.push((key, value));
+      } else if message.is_none() && input.peek(LitStr) {
+        message = Some(input.parse()?);
+      } else if message.is_some() {
+        // Anything after the message that is not `key = value` is a format argument.
+        args.push(input.parse()?);
+      } else {
+        return Err(input.error("expected `key = value` or a message string literal"));
       }
 
       if input.peek(Token![,]) {
@@ -33,13 +39,195 @@ impl Parse for LogInput {
       }
     }
 
-    Ok(LogInput { kvs, message })
+    Ok(LogInput {
+      logger,
+      kvs,
+      message,
+      args,
+    })
+  }
+}
+
+/// Parse an optional leading `logger: <expr>,` selecting the `Trace` to
+/// write to instead of the current one.
+fn parse_logger(input: ParseStream) -> syn::Result<Option<Expr>> {
+  let fork = input.fork();
+  let is_logger = fork.parse::<Ident>().is_ok_and(|ident| ident == "logger")
+    && fork.peek(Token![:])
+    && !fork.peek(Token![::]);
+  if !is_logger {
+    return Ok(None);
+  }
+
+  input.parse::<Ident>()?;
+  input.parse::<Token![:]>()?;
+  let logger: Expr = input.parse()?;
+  input.parse::<Token![,]>()?;
+  Ok(Some(logger))
+}
+
+/// `Option<&Trace>` expression for the explicit logger, or for the current
+/// one (scoped, else global) when there is none.
+fn logger_expr(logger: Option<&Expr>) -> proc_macro2::TokenStream {
+  match logger {
+    Some(logger) => quote! {
+      ::std::option::Option::Some::<&ttlog::trace::Trace>(#logger)
+    },
+    None => quote! { ttlog::trace::current().as_deref() },
+  }
+}
+
+enum Placeholder {
+  Next,
+  Index(usize),
+  Named(String),
+}
+
+/// Parse a message template with the same rules as `format_args!`, returning
+/// the argument reference of every placeholder.
+fn parse_template(template: &str) -> Result<Vec<Placeholder>, String> {
+  let mut placeholders = Vec::new();
+  let mut chars = template.char_indices().peekable();
+
+  while let Some((start, c)) = chars.next() {
+    match c {
+      '{' => {
+        if matches!(chars.peek(), Some((_, '{'))) {
+          chars.next();
+          continue;
+        }
+
+        let end = loop {
+          match chars.next() {
+            Some((idx, '}')) => break idx,
+            Some((_, '{')) | None => {
+              return Err("invalid format string: expected `}`".to_string());
+            },
+            Some(_) => {},
+          }
+        };
+
+        let inner = &template[start + 1..end];
+        let (arg, spec) = inner.split_once(':').unwrap_or((inner, ""));
+        if takes_argument(spec) {
+          return Err(format!(
+            "unsupported format spec `{}`: width and precision cannot come from arguments (`.*`, `N$`, `name$`)",
+            spec
+          ));
+        }
+
+        let placeholder = if arg.is_empty() {
+          Placeholder::Next
+        } else if let Ok(index) = arg.parse::<usize>() {
+          Placeholder::Index(index)
+        } else if syn::parse_str::<Ident>(arg).is_ok() {
+          Placeholder::Named(arg.to_string())
+        } else {
+          return Err(format!("invalid format string argument `{}`", arg));
+        };
+
+        placeholders.push(placeholder);
+      },
+      '}' => {
+        if matches!(chars.peek(), Some((_, '}'))) {
+          chars.next();
+        } else {
+          return Err("invalid format string: unmatched `}` found".to_string());
+        }
+      },
+      _ => {},
+    }
+  }
+
+  Ok(placeholders)
+}
+
+/// Whether a format spec reads its width or precision from an argument. The
+/// events only carry the values being formatted, so these are rejected.
+fn takes_argument(spec: &str) -> bool {
+  let mut chars = spec.chars();
+  let rest = match (chars.next(), chars.next()) {
+    (Some(_), Some('<' | '^' | '>')) => chars.as_str(),
+    (Some('<' | '^' | '>'), _) => &spec[1..],
+    _ => spec,
+  };
+  rest.contains(['$', '*'])
+}
+
+/// Validate the template against the positional arguments and resolve named
+/// placeholders. Names that are not explicit KV keys are captured from the
+/// surrounding scope and recorded as KV fields, mirroring inline format args.
+fn resolve_template(
+  message: &LitStr,
+  args: &[Expr],
+  kvs: &mut Vec<(Ident, Expr)>,
+) -> syn::Result<()> {
+  let placeholders =
+    parse_template(&message.value()).map_err(|err| syn::Error::new(message.span(), err))?;
+
+  let mut used = vec![false; args.len()];
+  let mut next = 0usize;
+
+  for placeholder in placeholders {
+    let index = match placeholder {
+      Placeholder::Next => {
+        next += 1;
+        next - 1
+      },
+      Placeholder::Index(index) => index,
+      Placeholder::Named(name) => {
+        if !kvs.iter().any(|(key, _)| key == name.as_str()) {
+          let ident = Ident::new(&name, message.span());
+          kvs.push((ident.clone(), syn::parse_quote!(#ident)));
+        }
+        continue;
+      },
+    };
+
+    match used.get_mut(index) {
+      Some(slot) => *slot = true,
+      None => {
+        return Err(syn::Error::new(
+          message.span(),
+          format!(
+            "invalid reference to positional argument {} (there {} {} argument{})",
+            index,
+            if args.len() == 1 { "is" } else { "are" },
+            args.len(),
+            if args.len() == 1 { "" } else { "s" },
+          ),
+        ));
+      },
+    }
+  }
+
+  if let Some(unused) = used.iter().position(|used| !used) {
+    return Err(syn::Error::new_spanned(
+      &args[unused],
+      "argument never used in the message template",
+    ));
   }
+
+  Ok(())
 }
 
 fn generate_log_call(level: u8, parsed: LogInput) -> TokenStream {
+  let LogInput {
+    logger,
+    mut kvs,
+    message,
+    args,
+  } = parsed;
+  let logger = logger_expr(logger.as_ref());
+
+  if let Some(message) = &message {
+    if let Err(err) = resolve_template(message, &args, &mut kvs) {
+      return err.to_compile_error().into();
+    }
+  }
+
   let thread_id_expr = quote! {
-    ttlog::utils::current_thread_id_u32() as u8
+    ttlog::thread_registry::current_id()
   };
 
   let common_constants = quote! {
@@ -50,31 +238,71 @@ fn generate_log_call(level: u8, parsed: LogInput) -> TokenStream {
   };
 
   let common_statics = quote! {
-    static TARGET_ID: std::sync::OnceLock<u16> = std::sync::OnceLock::new();
-    static FILE_ID: std::sync::OnceLock<u16> = std::sync::OnceLock::new();
+    static CALLSITE: ttlog::directive::Callsite = ttlog::directive::Callsite::new();
+    static TARGET_ID: ttlog::string_interner::CachedId = ttlog::string_interner::CachedId::new();
+    static FILE_ID: ttlog::string_interner::CachedId = ttlog::string_interner::CachedId::new();
+  };
+
+  // KV fields and format arguments share one binary payload of interned key
+  // IDs and typed values; the arguments repeat `ttlog::format::ARGS_KEY`.
+  // Values go through `ttlog::kv_codec::MacroArg` so `Serialize` types keep
+  // their type and `Display`-only ones (`io::Error`, `Path::display()`) are
+  // stored as their `to_string()`.
+  let kv_block = {
+    let kv_keys: Vec<_> = kvs.iter().map(|(k, _)| k).collect();
+    let kv_values = kvs.iter().map(|(_, v)| v);
+    let key_ids: Vec<_> = (0..kvs.len())
+      .map(|i| format_ident!("KEY_ID_{}", i))
+      .collect();
+
+    let args_block = if args.is_empty() {
+      quote! {}
+    } else {
+      quote! {
+        static ARGS_KEY_ID: ttlog::string_interner::CachedId = ttlog::string_interner::CachedId::new();
+        let args_key = ARGS_KEY_ID.get_or_intern(&logger.interner, |interner| interner.intern_key(ttlog::format::ARGS_KEY));
+        #((&&ttlog::kv_codec::MacroArg(&#args)).encode_field(&mut kv, args_key);)*
+      }
+    };
+
+    quote! {
+      #(static #key_ids: ttlog::string_interner::CachedId = ttlog::string_interner::CachedId::new();)*
+
+      #[allow(unused_imports)]
+      use ttlog::kv_codec::{EncodeDisplay as _, EncodeSerialize as _};
+
+      let mut kv = ttlog::kv_codec::FieldEncoder::new();
+      #((&&ttlog::kv_codec::MacroArg(&#kv_values)).encode_field(
+        &mut kv,
+        #key_ids.get_or_intern(&logger.interner, |interner| interner.intern_key(stringify!(#kv_keys))),
+      );)*
+      #args_block
+    }
   };
 
+  let has_payload = !kvs.is_empty() || !args.is_empty();
+
   // Different expansion paths
-  match (parsed.message, parsed.kvs.is_empty()) {
+  match (message, has_payload) {
     // Case 1: Message only
-    (Some(message), true) => quote! {
+    (Some(message), false) => quote! {
       {
         #common_constants
         const MESSAGE: &str = #message;
 
         #common_statics
-        static MESSAGE_ID: std::sync::OnceLock<u16> = std::sync::OnceLock::new();
+        static MESSAGE_ID: ttlog::string_interner::CachedId = ttlog::string_interner::CachedId::new();
 
-        if let Some(logger) = ttlog::trace::GLOBAL_LOGGER.get() {
-          if LEVEL >= logger.level.load(std::sync::atomic::Ordering::Relaxed) {
-            let target_id = *TARGET_ID.get_or_init(|| logger.interner.intern_target(MODULE));
-            let message_id = *MESSAGE_ID.get_or_init(|| logger.interner.intern_message(MESSAGE));
-            let file_id = *FILE_ID.get_or_init(|| logger.interner.intern_file(FILE));
+        if let Some(logger) = #logger {
+          if CALLSITE.enabled(logger, MODULE, LEVEL) {
+            let target_id = TARGET_ID.get_or_intern(&logger.interner, |interner| interner.intern_target(MODULE));
+            let message_id = MESSAGE_ID.get_or_intern(&logger.interner, |interner| interner.intern_message(MESSAGE));
+            let file_id = FILE_ID.get_or_intern(&logger.interner, |interner| interner.intern_file(FILE));
 
             logger.send_event_fast(
               LEVEL,
               target_id,
-              std::num::NonZeroU16::new(message_id),
+              std::num::NonZeroU32::new(message_id),
               #thread_id_expr,
               file_id,
               POSITION,
@@ -85,111 +313,77 @@ fn generate_log_call(level: u8, parsed: LogInput) -> TokenStream {
       }
     },
 
-    // Case 2: Message + KV
-    (Some(message), false) => {
-      let kv_keys: Vec<_> = parsed.kvs.iter().map(|(k, _)| k).collect();
-      let kv_values = parsed.kvs.iter().map(|(_, v)| v);
-      let num_kvs = parsed.kvs.len();
+    // Case 2: Message (template) + KV and/or format arguments
+    (Some(message), true) => quote! {
+      {
+        #common_constants
+        const MESSAGE: &str = #message;
 
-      quote! {
-        {
-          #common_constants
-          const MESSAGE: &str = #message;
-          const NUM_VALUES: usize = #num_kvs;
-
-          #common_statics
-          static MESSAGE_ID: std::sync::OnceLock<u16> = std::sync::OnceLock::new();
-
-          if let Some(logger) = ttlog::trace::GLOBAL_LOGGER.get() {
-            if LEVEL >= logger.level.load(std::sync::atomic::Ordering::Relaxed) {
-              let mut buf = ttlog::kv::KvTransformer::with_capacity(128);
-              {
-                use serde::ser::{SerializeMap, Serializer};
-                let mut ser = serde_json::Serializer::new(&mut buf);
-                let mut map = ser.serialize_map(Some(NUM_VALUES)).unwrap();
-                #({
-                  let wrapper = ttlog::kv::IntOrSer(&#kv_values);
-                  map.serialize_entry(stringify!(#kv_keys), &wrapper).unwrap();
-                })*
-                map.end().unwrap();
-              }
-
-              let target_id = *TARGET_ID.get_or_init(|| logger.interner.intern_target(MODULE));
-              let file_id = *FILE_ID.get_or_init(|| logger.interner.intern_file(FILE));
-              let message_id = *MESSAGE_ID.get_or_init(|| logger.interner.intern_message(MESSAGE));
-              let kv_id = logger.interner.intern_kv(buf.into_inner());
-
-              logger.send_event_fast(
-                LEVEL,
-                target_id,
-                std::num::NonZeroU16::new(message_id),
-                #thread_id_expr,
-                file_id,
-                POSITION,
-                std::num::NonZeroU16::new(kv_id),
-              );
-            }
+        #common_statics
+        static MESSAGE_ID: ttlog::string_interner::CachedId = ttlog::string_interner::CachedId::new();
+
+        if let Some(logger) = #logger {
+          if CALLSITE.enabled(logger, MODULE, LEVEL) {
+            #kv_block
+
+            let target_id = TARGET_ID.get_or_intern(&logger.interner, |interner| interner.intern_target(MODULE));
+            let file_id = FILE_ID.get_or_intern(&logger.interner, |interner| interner.intern_file(FILE));
+            let message_id = MESSAGE_ID.get_or_intern(&logger.interner, |interner| interner.intern_message(MESSAGE));
+            let kv_id = logger.interner.store_kv(kv.finish());
+
+            logger.send_event_fast(
+              LEVEL,
+              target_id,
+              std::num::NonZeroU32::new(message_id),
+              #thread_id_expr,
+              file_id,
+              POSITION,
+              std::num::NonZeroU32::new(kv_id),
+            );
           }
         }
       }
     },
 
     // Case 3: KV only
-    (None, false) => {
-      let kv_keys: Vec<_> = parsed.kvs.iter().map(|(k, _)| k).collect();
-      let kv_values = parsed.kvs.iter().map(|(_, v)| v);
-      let num_kvs = parsed.kvs.len();
+    (None, true) => quote! {
+      {
+        #common_constants
 
-      quote! {
-        {
-          #common_constants
-          const NUM_VALUES: usize = #num_kvs;
-
-          #common_statics
-
-          if let Some(logger) = ttlog::trace::GLOBAL_LOGGER.get() {
-            if LEVEL >= logger.level.load(std::sync::atomic::Ordering::Relaxed) {
-              let mut buf = ttlog::kv::KvTransformer::with_capacity(128);
-              {
-                use serde::ser::{SerializeMap, Serializer};
-                let mut ser = serde_json::Serializer::new(&mut buf);
-                let mut map = ser.serialize_map(Some(NUM_VALUES)).unwrap();
-                #({
-                  let wrapper = ttlog::kv::IntOrSer(&#kv_values);
-                  map.serialize_entry(stringify!(#kv_keys), &wrapper).unwrap();
-                })*
-                map.end().unwrap();
-              }
-
-              let kv_id = logger.interner.intern_kv(buf.into_inner());
-              let target_id = *TARGET_ID.get_or_init(|| logger.interner.intern_target(MODULE));
-              let file_id = *FILE_ID.get_or_init(|| logger.interner.intern_file(FILE));
-
-              logger.send_event_fast(
-                LEVEL,
-                target_id,
-                None,
-                #thread_id_expr,
-                file_id,
-                POSITION,
-                std::num::NonZeroU16::new(kv_id),
-              );
-            }
+        #common_statics
+
+        if let Some(logger) = #logger {
+          if CALLSITE.enabled(logger, MODULE, LEVEL) {
+            #kv_block
+
+            let kv_id = logger.interner.store_kv(kv.finish());
+            let target_id = TARGET_ID.get_or_intern(&logger.interner, |interner| interner.intern_target(MODULE));
+            let file_id = FILE_ID.get_or_intern(&logger.interner, |interner| interner.intern_file(FILE));
+
+            logger.send_event_fast(
+              LEVEL,
+              target_id,
+              None,
+              #thread_id_expr,
+              file_id,
+              POSITION,
+              std::num::NonZeroU32::new(kv_id),
+            );
           }
         }
       }
     },
 
     // Case 4: Empty call
-    (None, true) => quote! {
+    (None, false) => quote! {
       {
         #common_constants
         #common_statics
 
-        if let Some(logger) = ttlog::trace::GLOBAL_LOGGER.get() {
-          if LEVEL >= logger.level.load(std::sync::atomic::Ordering::Relaxed) {
-            let target_id = *TARGET_ID.get_or_init(|| logger.interner.intern_target(MODULE));
-            let file_id = *FILE_ID.get_or_init(|| logger.interner.intern_file(FILE));
+        if let Some(logger) = #logger {
+          if CALLSITE.enabled(logger, MODULE, LEVEL) {
+            let target_id = TARGET_ID.get_or_intern(&logger.interner, |interner| interner.intern_target(MODULE));
+            let file_id = FILE_ID.get_or_intern(&logger.interner, |interner| interner.intern_file(FILE));
 
             logger.send_event_fast(
               LEVEL,
@@ -208,6 +402,140 @@ fn generate_log_call(level: u8, parsed: LogInput) -> TokenStream {
   .into()
 }
 
+struct SpanInput {
+  logger: Option<Expr>,
+  level: u8,
+  name: LitStr,
+  kvs: Vec<(Ident, Expr)>,
+}
+
+impl Parse for SpanInput {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let logger = parse_logger(input)?;
+    let mut level = 2;
+
+    // Optional leading level, e.g. `span!(DEBUG, "name", ...)`.
+    if input.peek(Ident) && !input.peek2(Token![=]) {
+      let ident: Ident = input.parse()?;
+      level = match ident.to_string().as_str() {
+        "TRACE" => 0,
+        "DEBUG" => 1,
+        "INFO" => 2,
+        "WARN" => 3,
+        "ERROR" => 4,
+        "FATAL" => 5,
+        _ => {
+          return Err(syn::Error::new(
+            ident.span(),
+            "expected a level: TRACE, DEBUG, INFO, WARN, ERROR or FATAL",
+          ))
+        },
+      };
+      input.parse::<Token![,]>()?;
+    }
+
+    let mut span = parse_span_body(input, level)?;
+    span.logger = logger;
+    Ok(span)
+  }
+}
+
+fn parse_span_body(input: ParseStream, level: u8) -> syn::Result<SpanInput> {
+  let LogInput {
+    logger,
+    kvs,
+    message,
+    args,
+  } = input.parse()?;
+
+  let Some(name) = message else {
+    return Err(input.error("expected a span name string literal"));
+  };
+
+  if let Some(arg) = args.first() {
+    return Err(syn::Error::new_spanned(
+      arg,
+      "span names are static; record values as `key = value` fields",
+    ));
+  }
+
+  Ok(SpanInput {
+    logger,
+    level,
+    name,
+    kvs,
+  })
+}
+
+fn level_span(input: TokenStream, level: u8) -> TokenStream {
+  let parser = |input: ParseStream| parse_span_body(input, level);
+  match parser.parse(input) {
+    Ok(parsed) => generate_span(parsed),
+    Err(err) => err.to_compile_error().into(),
+  }
+}
+
+fn generate_span(parsed: SpanInput) -> TokenStream {
+  let SpanInput {
+    logger,
+    level,
+    name,
+    kvs,
+  } = parsed;
+  let logger = logger_expr(logger.as_ref());
+  let kv_keys = kvs.iter().map(|(k, _)| k);
+  let kv_values = kvs.iter().map(|(_, v)| v);
+  let key_ids: Vec<_> = (0..kvs.len())
+    .map(|i| format_ident!("KEY_ID_{}", i))
+    .collect();
+
+  quote! {
+    {
+      const LEVEL: u8 = #level;
+      const MODULE: &str = module_path!();
+      const FILE: &str = file!();
+      const POSITION: (u32, u32) = (line!(), column!());
+      const NAME: &str = #name;
+
+      static CALLSITE: ttlog::directive::Callsite = ttlog::directive::Callsite::new();
+      static TARGET_ID: ttlog::string_interner::CachedId = ttlog::string_interner::CachedId::new();
+      static FILE_ID: ttlog::string_interner::CachedId = ttlog::string_interner::CachedId::new();
+      static NAME_ID: ttlog::string_interner::CachedId = ttlog::string_interner::CachedId::new();
+      #(static #key_ids: ttlog::string_interner::CachedId = ttlog::string_interner::CachedId::new();)*
+
+      match #logger {
+        Some(logger) if CALLSITE.enabled(logger, MODULE, LEVEL) => {
+          let target_id = TARGET_ID.get_or_intern(&logger.interner, |interner| interner.intern_target(MODULE));
+          let file_id = FILE_ID.get_or_intern(&logger.interner, |interner| interner.intern_file(FILE));
+          let name_id = NAME_ID.get_or_intern(&logger.interner, |interner| interner.intern_message(NAME));
+
+          #[allow(unused_imports)]
+          use ttlog::kv_codec::{EncodeDisplay as _, EncodeSerialize as _};
+
+          #[allow(unused_mut)]
+          let mut fields = ttlog::kv_codec::FieldEncoder::new();
+          #((&&ttlog::kv_codec::MacroArg(&#kv_values)).encode_field(
+            &mut fields,
+            #key_ids.get_or_intern(&logger.interner, |interner| interner.intern_key(stringify!(#kv_keys))),
+          );)*
+
+          ttlog::span::Span::new(
+            logger,
+            LEVEL,
+            target_id,
+            name_id,
+            file_id,
+            POSITION,
+            fields,
+          )
+        },
+        _ => ttlog::span::Span::none(),
+      }
+    }
+  }
+  .into()
+}
+
 #[proc_macro]
 pub fn trace(input: TokenStream) -> TokenStream {
   generate_log_call(0, parse_macro_input!(input as LogInput))
@@ -232,3 +560,30 @@ pub fn error(input: TokenStream) -> TokenStream {
 pub fn fatal(input: TokenStream) -> TokenStream {
   generate_log_call(5, parse_macro_input!(input as LogInput))
 }
+
+/// Create a [`ttlog::span::Span`]: `span!("name", key = value)` at INFO, or
+/// `span!(DEBUG, "name", ...)` with an explicit level.
+#[proc_macro]
+pub fn span(input: TokenStream) -> TokenStream {
+  generate_span(parse_macro_input!(input as SpanInput))
+}
+#[proc_macro]
+pub fn trace_span(input: TokenStream) -> TokenStream {
+  level_span(input, 0)
+}
+#[proc_macro]
+pub fn debug_span(input: TokenStream) -> TokenStream {
+  level_span(input, 1)
+}
+#[proc_macro]
+pub fn info_span(input: TokenStream) -> TokenStream {
+  level_span(input, 2)
+}
+#[proc_macro]
+pub fn warn_span(input: TokenStream) -> TokenStream {
+  level_span(input, 3)
+}
+#[proc_macro]
+pub fn error_span(input: TokenStream) -> TokenStream {
+  level_span(input, 4)
+}
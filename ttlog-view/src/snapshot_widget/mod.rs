@@ -14,7 +14,7 @@ use ratatui::{
 use crate::{logs_widget::LogsWidget, snapshots::SnapshotFile, utils::Utils};
 use ttlog::{
   event::{LogEvent, LogLevel},
-  snapshot::ResolvedEvent,
+  snapshot::{ResolvedEvent, SnapShot},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -113,12 +113,17 @@ impl<'a> SnapshotWidget<'a> {
     widget
   }
 
+  // Get the selected snapshot
+  fn get_current_snapshot(&self) -> Option<&SnapShot> {
+    let snapshots = self.filtered_and_sorted_snapshots();
+    snapshots.get(self.selected_row).map(|(_, s)| &s.data)
+  }
+
   // Get events from current snapshot
   fn get_current_snapshot_events(&self) -> Option<&[ResolvedEvent]> {
-    let snapshots = self.filtered_and_sorted_snapshots();
-    snapshots
-      .get(self.selected_row)
-      .map(|(_, s)| s.data.events.as_slice())
+    self
+      .get_current_snapshot()
+      .map(|snapshot| snapshot.events.as_slice())
   }
 
   // Get selected event details
@@ -732,7 +737,7 @@ impl<'a> SnapshotWidget<'a> {
       .enumerate()
       .map(|(i, event)| {
         let (timestamp, level, _) = LogEvent::unpack_meta(event.packed_meta);
-        let mut summary = event.message.clone();
+        let mut summary = event.rendered_message().into_owned();
         if summary.len() > 50 {
           summary.truncate(47);
           summary.push_str("...");
@@ -791,12 +796,16 @@ impl<'a> SnapshotWidget<'a> {
       let (timestamp, level, thread_id) = LogEvent::unpack_meta(resolved.packed_meta);
       let level = LogLevel::from_u8(&level);
       let timestamp = Utils::format_timestamp(timestamp);
+      let thread = self
+        .get_current_snapshot()
+        .map(|snapshot| snapshot.thread_label(thread_id))
+        .unwrap_or_else(|| format!("t{}", thread_id));
 
       let json = serde_json::json!({
         "level": level,
         "timestamp": timestamp,
-        "thread_id": thread_id,
-        "message": resolved.message,
+        "thread": thread,
+        "message": resolved.rendered_message(),
         "target": resolved.target,
         "kv": resolved.kv,
         "file": resolved.file,
@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod __test__ {
+
+  use std::time::Duration;
+
+  use crossbeam_channel::bounded;
+
+  use crate::backpressure::{Backpressure, DropStats, OverflowPolicy};
+
+  #[test]
+  fn drop_newest_keeps_the_queued_items() {
+    let (tx, rx) = bounded(2);
+    let backpressure = Backpressure::new(OverflowPolicy::DropNewest, None);
+
+    assert!(backpressure.send(&tx, 1));
+    assert!(backpressure.send(&tx, 2));
+    assert!(!backpressure.send(&tx, 3));
+
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(backpressure.stats().dropped_newest, 1);
+  }
+
+  #[test]
+  fn drop_oldest_evicts_from_the_front() {
+    let (tx, rx) = bounded(2);
+    let backpressure = Backpressure::new(OverflowPolicy::DropOldest, Some(rx.clone()));
+
+    for i in 1..=4 {
+      assert!(backpressure.send(&tx, i));
+    }
+
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), [3, 4]);
+    assert_eq!(backpressure.stats().dropped_oldest, 2);
+  }
+
+  #[test]
+  fn drop_oldest_without_a_receiver_drops_newest() {
+    let (tx, _rx) = bounded(1);
+    let backpressure = Backpressure::new(OverflowPolicy::DropOldest, None);
+
+    assert!(backpressure.send(&tx, 1));
+    assert!(!backpressure.send(&tx, 2));
+    assert_eq!(backpressure.stats().dropped_newest, 1);
+  }
+
+  #[test]
+  fn block_waits_then_gives_up() {
+    let (tx, rx) = bounded(1);
+    let backpressure = Backpressure::new(OverflowPolicy::Block(Duration::from_millis(10)), None);
+
+    assert!(backpressure.send(&tx, 1));
+    assert!(!backpressure.send(&tx, 2));
+    assert_eq!(backpressure.stats().timed_out, 1);
+
+    let consumer = std::thread::spawn(move || {
+      std::thread::sleep(Duration::from_millis(20));
+      rx.recv().unwrap();
+      rx
+    });
+    let backpressure = Backpressure::new(OverflowPolicy::Block(Duration::from_secs(5)), None);
+    assert!(backpressure.send(&tx, 3));
+    consumer.join().unwrap();
+  }
+
+  #[test]
+  fn sample_thins_out_under_pressure() {
+    let (tx, rx) = bounded(8);
+    let backpressure = Backpressure::new(OverflowPolicy::Sample(2), None);
+
+    for i in 0..4 {
+      assert!(backpressure.send(&tx, i));
+    }
+    let admitted = (4..8).filter(|&i| backpressure.send(&tx, i)).count();
+    assert_eq!(admitted, 2);
+    assert_eq!(backpressure.stats().sampled_out, 2);
+    assert_eq!(rx.len(), 6);
+  }
+
+  #[test]
+  fn unreported_drops_are_reported_once() {
+    let (tx, _rx) = bounded(1);
+    let backpressure = Backpressure::new(OverflowPolicy::DropNewest, None);
+    assert!(backpressure.take_unreported().is_none());
+
+    backpressure.send(&tx, 1);
+    backpressure.send(&tx, 2);
+    backpressure.send(&tx, 3);
+
+    let (dropped, stats) = backpressure.take_unreported().unwrap();
+    assert_eq!(dropped, 2);
+    assert_eq!(
+      stats,
+      DropStats {
+        dropped_newest: 2,
+        ..DropStats::default()
+      }
+    );
+    assert_eq!(stats.total(), 2);
+    assert!(backpressure.take_unreported().is_none());
+  }
+
+  #[test]
+  fn policy_names() {
+    assert_eq!(OverflowPolicy::default(), OverflowPolicy::DropNewest);
+    assert_eq!(OverflowPolicy::DropOldest.to_string(), "drop_oldest");
+    assert_eq!(OverflowPolicy::Sample(4).as_str(), "sample");
+  }
+}
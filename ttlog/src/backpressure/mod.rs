@@ -0,0 +1,213 @@
+mod __test__;
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, SendTimeoutError, Sender, TrySendError};
+
+/// Minimum time between two "dropped N events" reports.
+pub const DROP_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Target of the "dropped N events" report.
+pub const DROP_REPORT_TARGET: &str = "ttlog::backpressure";
+
+/// Message of the drop report; its KVs are `dropped` (since the previous
+/// report), `total` and `policy`.
+pub const DROP_REPORT_MESSAGE: &str = "dropped {dropped} events";
+
+/// What happens to an event when the listener queue is full.
+///
+/// Only listener delivery is affected: every event still reaches the
+/// snapshot ring buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+  /// Discard the event being logged.
+  #[default]
+  DropNewest,
+  /// Discard the oldest queued event to make room.
+  DropOldest,
+  /// Wait up to the given time for room, then discard the event. Stalls the
+  /// logging thread while the listeners catch up.
+  Block(Duration),
+  /// Once the queue is half full, admit only one event in `n` and discard the
+  /// rest; discard everything while it is full.
+  Sample(u32),
+}
+
+impl OverflowPolicy {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      OverflowPolicy::DropNewest => "drop_newest",
+      OverflowPolicy::DropOldest => "drop_oldest",
+      OverflowPolicy::Block(_) => "block",
+      OverflowPolicy::Sample(_) => "sample",
+    }
+  }
+}
+
+impl fmt::Display for OverflowPolicy {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// Events discarded by a queue since it was created, by cause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DropStats {
+  /// Refused because the queue was full ([`OverflowPolicy::DropNewest`]).
+  pub dropped_newest: u64,
+  /// Evicted to make room ([`OverflowPolicy::DropOldest`]).
+  pub dropped_oldest: u64,
+  /// Still no room after waiting ([`OverflowPolicy::Block`]).
+  pub timed_out: u64,
+  /// Skipped while sampling ([`OverflowPolicy::Sample`]).
+  pub sampled_out: u64,
+  /// Delivered, but without their KVs because the KV arena slot they needed
+  /// was still pinned. Only set by `Trace::dropped_events`; not part of
+  /// [`Self::total`].
+  pub kv_dropped: u64,
+}
+
+impl DropStats {
+  pub fn total(&self) -> u64 {
+    self.dropped_newest + self.dropped_oldest + self.timed_out + self.sampled_out
+  }
+}
+
+#[derive(Debug, Default)]
+struct DropCounters {
+  dropped_newest: AtomicU64,
+  dropped_oldest: AtomicU64,
+  timed_out: AtomicU64,
+  sampled_out: AtomicU64,
+}
+
+/// Applies an [`OverflowPolicy`] to sends on a bounded channel and counts
+/// what it discards.
+pub struct Backpressure<T> {
+  policy: OverflowPolicy,
+  /// Receiving end used to evict the oldest item under
+  /// [`OverflowPolicy::DropOldest`]. Without it that policy drops the newest.
+  evict: Option<Receiver<T>>,
+  counters: DropCounters,
+  /// Sends seen while sampling, to pick every n-th.
+  sample_tick: AtomicU64,
+  /// Total already covered by a drop report.
+  reported: AtomicU64,
+}
+
+impl<T> Backpressure<T> {
+  pub fn new(policy: OverflowPolicy, evict: Option<Receiver<T>>) -> Self {
+    Self {
+      policy,
+      evict,
+      counters: DropCounters::default(),
+      sample_tick: AtomicU64::new(0),
+      reported: AtomicU64::new(0),
+    }
+  }
+
+  pub fn policy(&self) -> OverflowPolicy {
+    self.policy
+  }
+
+  /// Send `item` according to the policy. Returns `false` if it was
+  /// discarded or the channel is disconnected.
+  pub fn send(&self, sender: &Sender<T>, item: T) -> bool {
+    match self.policy {
+      OverflowPolicy::DropNewest => self.send_or_drop(sender, item),
+      OverflowPolicy::DropOldest => self.send_evicting(sender, item),
+      OverflowPolicy::Block(timeout) => match sender.send_timeout(item, timeout) {
+        Ok(()) => true,
+        Err(SendTimeoutError::Timeout(_)) => {
+          self.counters.timed_out.fetch_add(1, Ordering::Relaxed);
+          false
+        },
+        Err(SendTimeoutError::Disconnected(_)) => false,
+      },
+      OverflowPolicy::Sample(n) => {
+        let pressured = sender
+          .capacity()
+          .is_some_and(|capacity| sender.len() * 2 >= capacity);
+        if pressured {
+          let tick = self.sample_tick.fetch_add(1, Ordering::Relaxed);
+          if !tick.is_multiple_of(u64::from(n.max(1))) {
+            self.counters.sampled_out.fetch_add(1, Ordering::Relaxed);
+            return false;
+          }
+        }
+        match sender.try_send(item) {
+          Ok(()) => true,
+          Err(TrySendError::Full(_)) => {
+            self.counters.sampled_out.fetch_add(1, Ordering::Relaxed);
+            false
+          },
+          Err(TrySendError::Disconnected(_)) => false,
+        }
+      },
+    }
+  }
+
+  fn send_or_drop(&self, sender: &Sender<T>, item: T) -> bool {
+    match sender.try_send(item) {
+      Ok(()) => true,
+      Err(TrySendError::Full(_)) => {
+        self.counters.dropped_newest.fetch_add(1, Ordering::Relaxed);
+        false
+      },
+      Err(TrySendError::Disconnected(_)) => false,
+    }
+  }
+
+  fn send_evicting(&self, sender: &Sender<T>, mut item: T) -> bool {
+    let Some(evict) = &self.evict else {
+      return self.send_or_drop(sender, item);
+    };
+
+    // Other producers can refill the freed slot; give up after a few rounds
+    // rather than spin.
+    for _ in 0..4 {
+      match sender.try_send(item) {
+        Ok(()) => return true,
+        Err(TrySendError::Full(rejected)) => {
+          item = rejected;
+          if evict.try_recv().is_ok() {
+            self.counters.dropped_oldest.fetch_add(1, Ordering::Relaxed);
+          }
+        },
+        Err(TrySendError::Disconnected(_)) => return false,
+      }
+    }
+    self.counters.dropped_newest.fetch_add(1, Ordering::Relaxed);
+    false
+  }
+
+  pub fn stats(&self) -> DropStats {
+    DropStats {
+      dropped_newest: self.counters.dropped_newest.load(Ordering::Relaxed),
+      dropped_oldest: self.counters.dropped_oldest.load(Ordering::Relaxed),
+      timed_out: self.counters.timed_out.load(Ordering::Relaxed),
+      sampled_out: self.counters.sampled_out.load(Ordering::Relaxed),
+      kv_dropped: 0,
+    }
+  }
+
+  /// Drops since the previous call, with the current stats; `None` if there
+  /// were none.
+  pub fn take_unreported(&self) -> Option<(u64, DropStats)> {
+    let stats = self.stats();
+    let total = stats.total();
+    let previous = self.reported.swap(total, Ordering::Relaxed);
+    (total > previous).then(|| (total - previous, stats))
+  }
+}
+
+impl<T> fmt::Debug for Backpressure<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Backpressure")
+      .field("policy", &self.policy)
+      .field("stats", &self.stats())
+      .finish_non_exhaustive()
+  }
+}
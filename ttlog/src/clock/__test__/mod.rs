@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod __test__ {
+
+  use std::collections::HashSet;
+  use std::thread;
+  use std::time::{SystemTime, UNIX_EPOCH};
+
+  use crate::clock;
+
+  #[test]
+  fn now_is_monotonic() {
+    let mut last = clock::now_ns();
+    for _ in 0..1000 {
+      let now = clock::now_ns();
+      assert!(now >= last);
+      last = now;
+    }
+  }
+
+  #[test]
+  fn unix_time_tracks_the_wall_clock() {
+    let wall = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap()
+      .as_nanos() as u64;
+    let derived = clock::unix_ns(clock::now_ns());
+
+    let skew = derived.abs_diff(wall);
+    assert!(skew < 1_000_000_000, "skew of {skew}ns");
+    assert!(clock::anchor_unix_ns() <= derived);
+  }
+
+  #[test]
+  fn sequence_numbers_are_unique_across_threads() {
+    let handles: Vec<_> = (0..4)
+      .map(|_| thread::spawn(|| (0..1000).map(|_| clock::next_seq()).collect::<Vec<_>>()))
+      .collect();
+
+    let mut seen = HashSet::new();
+    for handle in handles {
+      let seqs = handle.join().unwrap();
+      assert!(seqs.windows(2).all(|pair| pair[0] < pair[1]));
+      for seq in seqs {
+        assert!(seq > 0);
+        assert!(seen.insert(seq));
+      }
+    }
+  }
+}
@@ -0,0 +1,61 @@
+mod __test__;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Monotonic base and the wall-clock time it corresponds to, taken together
+/// on first use.
+struct Anchor {
+  instant: Instant,
+  unix_ns: u64,
+}
+
+static ANCHOR: OnceLock<Anchor> = OnceLock::new();
+
+/// Next event sequence number. Starts at 1 so 0 marks events that never got
+/// one.
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(1);
+
+fn anchor() -> &'static Anchor {
+  ANCHOR.get_or_init(|| Anchor {
+    instant: Instant::now(),
+    unix_ns: SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_nanos() as u64,
+  })
+}
+
+/// Nanoseconds since the process anchor on the monotonic clock. Never goes
+/// backwards, unlike `SystemTime`, so it orders events within a process.
+#[inline]
+pub fn now_ns() -> u64 {
+  anchor().instant.elapsed().as_nanos() as u64
+}
+
+/// Wall-clock time of the anchor in nanoseconds since the Unix epoch.
+/// Snapshots record it so monotonic readings can be turned back into dates.
+pub fn anchor_unix_ns() -> u64 {
+  anchor().unix_ns
+}
+
+/// Wall-clock nanoseconds since the Unix epoch for a [`now_ns`] reading.
+#[inline]
+pub fn unix_ns(mono_ns: u64) -> u64 {
+  anchor_unix_ns().saturating_add(mono_ns)
+}
+
+/// Wall-clock milliseconds since the Unix epoch for a [`now_ns`] reading, as
+/// stored in `LogEvent::packed_meta`.
+#[inline]
+pub fn unix_millis(mono_ns: u64) -> u64 {
+  unix_ns(mono_ns) / 1_000_000
+}
+
+/// Allocate the next event sequence number, unique and increasing across
+/// all loggers in the process.
+#[inline]
+pub fn next_seq() -> u64 {
+  NEXT_SEQ.fetch_add(1, Ordering::Relaxed)
+}
@@ -0,0 +1,224 @@
+#[cfg(test)]
+mod __test__ {
+
+  use std::collections::HashMap;
+
+  use crate::config::{ConfigError, FilterConfig, ListenerConfig, RotationConfig, TtlogConfig};
+  use crate::event::LogLevel;
+  use crate::snapshot::{SnapshotMode, SnapshotModes, StorageLayout};
+
+  const JSON: &str = r#"{
+    "service_name": "checkout",
+    "capacity": 4096,
+    "level": "info",
+    "directives": "checkout::db=trace",
+    "storage": { "path": "/tmp/snapshots", "layout": "per_service" },
+    "snapshots": {
+      "interval_secs": 0,
+      "panic": false,
+      "signals": true,
+      "modes": { "manual": "peek" }
+    },
+    "listeners": [
+      { "type": "stdout", "level": "warn" },
+      {
+        "type": "file",
+        "path": "/tmp/app.log",
+        "targets": ["checkout::*"],
+        "rotation": { "max_bytes": 1024, "keep": 3, "compression": "lz4" }
+      }
+    ]
+  }"#;
+
+  // ── Parsing ────────────────────────────────────────────────────────
+
+  #[test]
+  fn parses_json() {
+    let config = TtlogConfig::from_json_str(JSON).unwrap();
+    assert_eq!(config.service_name.as_deref(), Some("checkout"));
+    assert_eq!(config.capacity, Some(4096));
+    assert_eq!(config.storage.layout.as_deref(), Some("per_service"));
+    assert_eq!(config.snapshots.interval_secs, Some(0));
+    assert_eq!(config.listeners.len(), 2);
+    assert_eq!(
+      config.listeners[0],
+      ListenerConfig::Stdout {
+        filter: FilterConfig {
+          level: Some("warn".to_string()),
+          ..FilterConfig::default()
+        }
+      }
+    );
+    match &config.listeners[1] {
+      ListenerConfig::File {
+        path,
+        rotation,
+        filter,
+      } => {
+        assert_eq!(path, "/tmp/app.log");
+        assert_eq!(filter.targets, vec!["checkout::*".to_string()]);
+        assert_eq!(rotation.as_ref().unwrap().keep, Some(3));
+      },
+      other => panic!("unexpected listener {:?}", other),
+    }
+  }
+
+  #[test]
+  fn empty_config_is_default() {
+    assert_eq!(
+      TtlogConfig::from_json_str("{}").unwrap(),
+      TtlogConfig::default()
+    );
+  }
+
+  #[test]
+  fn rejects_unknown_fields() {
+    let err = TtlogConfig::from_json_str(r#"{ "capacityy": 1 }"#).unwrap_err();
+    assert!(matches!(err, ConfigError::Parse(_)));
+  }
+
+  #[test]
+  fn rejects_unknown_listener_fields() {
+    for listener in [
+      r#"{ "type": "stdout", "levle": "warn" }"#,
+      r#"{ "type": "file", "path": "/tmp/app.log", "deny_target": ["noisy"] }"#,
+    ] {
+      let json = format!(r#"{{ "listeners": [{}] }}"#, listener);
+      let err = TtlogConfig::from_json_str(&json).unwrap_err();
+      assert!(matches!(err, ConfigError::Parse(_)), "{}", listener);
+    }
+  }
+
+  #[cfg(feature = "toml")]
+  #[test]
+  fn parses_toml() {
+    let config = TtlogConfig::from_toml_str(
+      r#"
+        service_name = "checkout"
+        directives = "info,checkout::db=trace"
+
+        [snapshots]
+        interval_secs = 300
+
+        [[listeners]]
+        type = "file"
+        path = "./logs/app.log"
+        rotation = { interval_secs = 3600, compression = "none" }
+      "#,
+    )
+    .unwrap();
+    assert_eq!(config.snapshots.interval_secs, Some(300));
+    assert!(matches!(config.listeners[0], ListenerConfig::File { .. }));
+  }
+
+  // ── Environment ────────────────────────────────────────────────────
+
+  #[test]
+  fn env_overrides_file_values() {
+    let mut config = TtlogConfig::from_json_str(JSON).unwrap();
+    let env: HashMap<&str, &str> = [
+      ("TTLOG_CAPACITY", "128"),
+      ("TTLOG_LEVEL", "debug"),
+      ("TTLOG_SNAPSHOT_INTERVAL_SECS", "30"),
+      ("TTLOG_PANIC_HOOK", "true"),
+    ]
+    .into_iter()
+    .collect();
+
+    config
+      .apply_env_with(|key| env.get(key).map(|v| v.to_string()))
+      .unwrap();
+    assert_eq!(config.capacity, Some(128));
+    assert_eq!(config.level.as_deref(), Some("debug"));
+    assert_eq!(config.snapshots.interval_secs, Some(30));
+    assert_eq!(config.snapshots.panic, Some(true));
+    assert_eq!(config.service_name.as_deref(), Some("checkout"));
+  }
+
+  #[test]
+  fn env_rejects_malformed_numbers() {
+    let mut config = TtlogConfig::default();
+    let err = config
+      .apply_env_with(|key| (key == "TTLOG_CAPACITY").then(|| "lots".to_string()))
+      .unwrap_err();
+    assert!(matches!(err, ConfigError::Parse(_)));
+  }
+
+  // ── Builder translation ────────────────────────────────────────────
+
+  #[test]
+  fn into_builder_applies_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = TtlogConfig::from_json_str(JSON).unwrap();
+    if let ListenerConfig::File { path, .. } = &mut config.listeners[1] {
+      *path = dir.path().join("app.log").to_string_lossy().into_owned();
+    }
+
+    let builder = config.into_builder().unwrap();
+    assert_eq!(builder.service_name, "checkout");
+    assert_eq!(builder.capacity, 4096);
+    assert_eq!(builder.level, LogLevel::INFO);
+    assert!(builder.directives.is_some());
+    assert_eq!(builder.storage_layout, StorageLayout::PerService);
+    assert_eq!(builder.snapshot_interval, None);
+    assert!(!builder.panic_hook);
+    assert!(builder.signal_hook);
+    assert_eq!(
+      builder.snapshot_modes,
+      SnapshotModes {
+        manual: SnapshotMode::Peek,
+        ..SnapshotModes::default()
+      }
+    );
+    assert_eq!(builder.listeners.len(), 2);
+    assert!(dir.path().join("app.log").exists());
+  }
+
+  #[test]
+  fn into_builder_rejects_invalid_values() {
+    let config = TtlogConfig {
+      level: Some("loud".to_string()),
+      ..TtlogConfig::default()
+    };
+    assert!(matches!(
+      config.into_builder(),
+      Err(ConfigError::Invalid(_))
+    ));
+
+    let config = TtlogConfig {
+      directives: Some("db=loud".to_string()),
+      ..TtlogConfig::default()
+    };
+    assert!(matches!(
+      config.into_builder(),
+      Err(ConfigError::Directives(_))
+    ));
+
+    let config =
+      TtlogConfig::from_json_str(r#"{ "snapshots": { "modes": { "panic": "keep" } } }"#).unwrap();
+    assert!(matches!(
+      config.into_builder(),
+      Err(ConfigError::Invalid(_))
+    ));
+    assert!(matches!(
+      TtlogConfig::from_json_str(r#"{ "snapshots": { "modes": { "manul": "peek" } } }"#),
+      Err(ConfigError::Parse(_))
+    ));
+  }
+
+  #[test]
+  fn rotation_config_builds_policy() {
+    let rotation = RotationConfig {
+      interval_secs: Some(60),
+      compression: Some("zstd".to_string()),
+      ..RotationConfig::default()
+    };
+    assert!(matches!(rotation.build(), Err(ConfigError::Invalid(_))));
+
+    let rotation = RotationConfig {
+      interval_secs: Some(60),
+      ..RotationConfig::default()
+    };
+    assert!(rotation.build().is_ok());
+  }
+}
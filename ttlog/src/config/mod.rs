@@ -0,0 +1,539 @@
+mod __test__;
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::directive::{self, DirectiveParseError, Directives};
+use crate::event::LogLevel;
+use crate::file_listener::{Compression, FileListener, RotationPolicy};
+use crate::listener::ListenerFilter;
+use crate::snapshot::{SnapshotMode, SnapshotModes, StorageLayout};
+use crate::stdout_listener::StdoutListener;
+use crate::trace::Trace;
+use crate::trace_builder::{TraceBuilder, TraceError};
+
+/// Environment variable naming the config file read by [`TtlogConfig::load`].
+pub const CONFIG_PATH_ENV: &str = "TTLOG_CONFIG";
+
+/// Why a configuration could not be loaded or applied.
+#[derive(Debug)]
+pub enum ConfigError {
+  Io(io::Error),
+  /// The file or an environment variable is malformed.
+  Parse(String),
+  /// A well-formed value that ttlog cannot use (unknown level, disabled
+  /// compression feature, ...).
+  Invalid(String),
+  Directives(DirectiveParseError),
+  Trace(TraceError),
+}
+
+impl fmt::Display for ConfigError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ConfigError::Io(e) => write!(f, "failed to read config: {}", e),
+      ConfigError::Parse(msg) => write!(f, "failed to parse config: {}", msg),
+      ConfigError::Invalid(msg) => write!(f, "invalid config: {}", msg),
+      ConfigError::Directives(e) => write!(f, "invalid config: {}", e),
+      ConfigError::Trace(e) => write!(f, "failed to start trace: {}", e),
+    }
+  }
+}
+
+impl Error for ConfigError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      ConfigError::Io(e) => Some(e),
+      ConfigError::Directives(e) => Some(e),
+      ConfigError::Trace(e) => Some(e),
+      ConfigError::Parse(_) | ConfigError::Invalid(_) => None,
+    }
+  }
+}
+
+impl From<io::Error> for ConfigError {
+  fn from(e: io::Error) -> Self {
+    ConfigError::Io(e)
+  }
+}
+
+/// Runtime configuration of ttlog, loaded from `ttlog.toml` / a JSON file and
+/// `TTLOG_*` environment variables, so behaviour can change without a
+/// recompile. Every field is optional; missing ones keep the
+/// [`TraceBuilder`] defaults.
+///
+/// ```toml
+/// service_name = "checkout"
+/// capacity = 10000
+/// directives = "info,checkout::db=trace"
+///
+/// [storage]
+/// path = "/var/lib/checkout/snapshots"
+/// layout = "per_service"
+///
+/// [snapshots]
+/// interval_secs = 300   # 0 disables periodic snapshots
+/// panic = true
+/// signals = true
+/// modes = { periodic = "peek", manual = "peek" }   # "drain" (default) or "peek"
+///
+/// [[listeners]]
+/// type = "stdout"
+/// level = "warn"
+///
+/// [[listeners]]
+/// type = "file"
+/// path = "./logs/checkout.log"
+/// targets = ["checkout::*"]
+/// rotation = { max_bytes = 104857600, interval_secs = 3600, keep = 24, compression = "lz4" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TtlogConfig {
+  pub service_name: Option<String>,
+  pub capacity: Option<usize>,
+  pub channel_capacity: Option<usize>,
+  /// Default level; `directives` may override it.
+  pub level: Option<String>,
+  /// `env_logger`-style directives, see [`Directives`].
+  pub directives: Option<String>,
+  pub storage: StorageConfig,
+  pub snapshots: SnapshotConfig,
+  pub listeners: Vec<ListenerConfig>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+  pub path: Option<String>,
+  /// `"flat"` or `"per_service"`.
+  pub layout: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnapshotConfig {
+  /// Periodic snapshot interval; 0 disables periodic snapshots.
+  pub interval_secs: Option<u64>,
+  /// Snapshot on panic.
+  pub panic: Option<bool>,
+  /// Snapshot on termination signals (unix).
+  pub signals: Option<bool>,
+  pub modes: SnapshotModesConfig,
+}
+
+/// Declarative form of [`SnapshotModes`]: `"drain"` or `"peek"` per trigger.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnapshotModesConfig {
+  pub panic: Option<String>,
+  pub signal: Option<String>,
+  pub periodic: Option<String>,
+  pub manual: Option<String>,
+}
+
+/// One listener to attach, selected by its `type`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "RawListenerConfig")]
+pub enum ListenerConfig {
+  Stdout {
+    filter: FilterConfig,
+  },
+  File {
+    path: String,
+    rotation: Option<RotationConfig>,
+    filter: FilterConfig,
+  },
+}
+
+/// Wire form of [`ListenerConfig`]. The filter keys are spelled out because
+/// `#[serde(flatten)]` would silently accept misspelled ones.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum RawListenerConfig {
+  Stdout {
+    #[serde(default)]
+    level: Option<String>,
+    #[serde(default)]
+    targets: Vec<String>,
+    #[serde(default)]
+    deny_targets: Vec<String>,
+  },
+  File {
+    path: String,
+    #[serde(default)]
+    rotation: Option<RotationConfig>,
+    #[serde(default)]
+    level: Option<String>,
+    #[serde(default)]
+    targets: Vec<String>,
+    #[serde(default)]
+    deny_targets: Vec<String>,
+  },
+}
+
+impl From<RawListenerConfig> for ListenerConfig {
+  fn from(raw: RawListenerConfig) -> Self {
+    match raw {
+      RawListenerConfig::Stdout {
+        level,
+        targets,
+        deny_targets,
+      } => ListenerConfig::Stdout {
+        filter: FilterConfig {
+          level,
+          targets,
+          deny_targets,
+        },
+      },
+      RawListenerConfig::File {
+        path,
+        rotation,
+        level,
+        targets,
+        deny_targets,
+      } => ListenerConfig::File {
+        path,
+        rotation,
+        filter: FilterConfig {
+          level,
+          targets,
+          deny_targets,
+        },
+      },
+    }
+  }
+}
+
+impl ListenerConfig {
+  pub fn filter(&self) -> &FilterConfig {
+    match self {
+      ListenerConfig::Stdout { filter } | ListenerConfig::File { filter, .. } => filter,
+    }
+  }
+
+  /// Whether both configs describe the same output, ignoring filters.
+  pub fn same_sink(&self, other: &ListenerConfig) -> bool {
+    match (self, other) {
+      (ListenerConfig::Stdout { .. }, ListenerConfig::Stdout { .. }) => true,
+      (
+        ListenerConfig::File { path, rotation, .. },
+        ListenerConfig::File {
+          path: other_path,
+          rotation: other_rotation,
+          ..
+        },
+      ) => path == other_path && rotation == other_rotation,
+      _ => false,
+    }
+  }
+}
+
+/// Declarative form of a [`ListenerFilter`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterConfig {
+  pub level: Option<String>,
+  pub targets: Vec<String>,
+  pub deny_targets: Vec<String>,
+}
+
+/// Declarative form of a [`RotationPolicy`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RotationConfig {
+  pub max_bytes: Option<u64>,
+  pub interval_secs: Option<u64>,
+  pub keep: Option<usize>,
+  /// `"none"`, `"lz4"` or `"gzip"` (requires the `gzip` feature).
+  pub compression: Option<String>,
+}
+
+/// A level name as the directives spell it, except `off`, which only makes
+/// sense per target.
+fn parse_level(input: &str) -> Result<LogLevel, ConfigError> {
+  directive::parse_level(input)
+    .filter(|&level| level != directive::LEVEL_OFF)
+    .map(|level| LogLevel::from_u8(&level))
+    .ok_or_else(|| ConfigError::Invalid(format!("unknown level `{}`", input)))
+}
+
+fn parse_env<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, ConfigError> {
+  value
+    .trim()
+    .parse()
+    .map_err(|_| ConfigError::Parse(format!("{}={:?}", key, value)))
+}
+
+impl TtlogConfig {
+  pub fn from_json_str(input: &str) -> Result<Self, ConfigError> {
+    serde_json::from_str(input).map_err(|e| ConfigError::Parse(e.to_string()))
+  }
+
+  #[cfg(feature = "toml")]
+  pub fn from_toml_str(input: &str) -> Result<Self, ConfigError> {
+    toml::from_str(input).map_err(|e| ConfigError::Parse(e.to_string()))
+  }
+
+  /// Read a config file, choosing the format from its extension (`.json`,
+  /// or `.toml` with the `toml` feature).
+  pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)?;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+      Some("json") => Self::from_json_str(&contents),
+      #[cfg(feature = "toml")]
+      Some("toml") => Self::from_toml_str(&contents),
+      _ => Err(ConfigError::Invalid(format!(
+        "unsupported config format: {}",
+        path.display()
+      ))),
+    }
+  }
+
+  /// Load the file named by `TTLOG_CONFIG`, else `./ttlog.toml` (with the
+  /// `toml` feature) or `./ttlog.json` if present, then apply the `TTLOG_*`
+  /// environment overrides. Without any file this is the defaults plus the
+  /// environment.
+  pub fn load() -> Result<Self, ConfigError> {
+    let mut config = match std::env::var(CONFIG_PATH_ENV) {
+      Ok(path) => Self::from_file(path)?,
+      Err(_) => {
+        let candidates: &[&str] = if cfg!(feature = "toml") {
+          &["ttlog.toml", "ttlog.json"]
+        } else {
+          &["ttlog.json"]
+        };
+        match candidates.iter().find(|path| Path::new(path).exists()) {
+          Some(path) => Self::from_file(path)?,
+          None => Self::default(),
+        }
+      },
+    };
+
+    config.apply_env()?;
+    Ok(config)
+  }
+
+  /// Override fields from the process environment; see [`Self::apply_env_with`].
+  pub fn apply_env(&mut self) -> Result<(), ConfigError> {
+    self.apply_env_with(|key| std::env::var(key).ok())
+  }
+
+  /// Override fields from `TTLOG_SERVICE_NAME`, `TTLOG_CAPACITY`,
+  /// `TTLOG_CHANNEL_CAPACITY`, `TTLOG_LEVEL`, `TTLOG_DIRECTIVES`,
+  /// `TTLOG_STORAGE_PATH`, `TTLOG_STORAGE_LAYOUT`,
+  /// `TTLOG_SNAPSHOT_INTERVAL_SECS`, `TTLOG_PANIC_HOOK` and
+  /// `TTLOG_SIGNAL_HOOK`, looked up through `var`. Listeners are only
+  /// configurable in the file.
+  pub fn apply_env_with(
+    &mut self,
+    var: impl Fn(&str) -> Option<String>,
+  ) -> Result<(), ConfigError> {
+    if let Some(value) = var("TTLOG_SERVICE_NAME") {
+      self.service_name = Some(value);
+    }
+    if let Some(value) = var("TTLOG_CAPACITY") {
+      self.capacity = Some(parse_env("TTLOG_CAPACITY", &value)?);
+    }
+    if let Some(value) = var("TTLOG_CHANNEL_CAPACITY") {
+      self.channel_capacity = Some(parse_env("TTLOG_CHANNEL_CAPACITY", &value)?);
+    }
+    if let Some(value) = var("TTLOG_LEVEL") {
+      self.level = Some(value);
+    }
+    if let Some(value) = var("TTLOG_DIRECTIVES") {
+      self.directives = Some(value);
+    }
+    if let Some(value) = var("TTLOG_STORAGE_PATH") {
+      self.storage.path = Some(value);
+    }
+    if let Some(value) = var("TTLOG_STORAGE_LAYOUT") {
+      self.storage.layout = Some(value);
+    }
+    if let Some(value) = var("TTLOG_SNAPSHOT_INTERVAL_SECS") {
+      self.snapshots.interval_secs = Some(parse_env("TTLOG_SNAPSHOT_INTERVAL_SECS", &value)?);
+    }
+    if let Some(value) = var("TTLOG_PANIC_HOOK") {
+      self.snapshots.panic = Some(parse_env("TTLOG_PANIC_HOOK", &value)?);
+    }
+    if let Some(value) = var("TTLOG_SIGNAL_HOOK") {
+      self.snapshots.signals = Some(parse_env("TTLOG_SIGNAL_HOOK", &value)?);
+    }
+    Ok(())
+  }
+
+  /// Translate into a [`TraceBuilder`], opening any file listeners.
+  pub fn into_builder(self) -> Result<TraceBuilder, ConfigError> {
+    let mut builder = TraceBuilder::new();
+
+    if let Some(service_name) = &self.service_name {
+      builder = builder.service_name(service_name.as_str());
+    }
+    if let Some(capacity) = self.capacity {
+      builder = builder.capacity(capacity);
+    }
+    if let Some(channel_capacity) = self.channel_capacity {
+      builder = builder.channel_capacity(channel_capacity);
+    }
+    if let Some(level) = &self.level {
+      builder = builder.level(parse_level(level)?);
+    }
+    if let Some(directives) = &self.directives {
+      builder = builder.directives(Directives::parse(directives).map_err(ConfigError::Directives)?);
+    }
+
+    if let Some(path) = &self.storage.path {
+      builder = builder.storage_path(path.as_str());
+    }
+    builder = builder.storage_layout(self.storage_layout()?);
+
+    if let Some(interval) = self.snapshot_interval() {
+      builder = builder.snapshot_interval(interval);
+    }
+    if let Some(panic) = self.snapshots.panic {
+      builder = builder.panic_hook(panic);
+    }
+    if let Some(signals) = self.snapshots.signals {
+      builder = builder.signal_hook(signals);
+    }
+    builder.snapshot_modes = self.snapshot_modes()?;
+
+    for listener in self.listeners {
+      builder = match listener {
+        ListenerConfig::Stdout { filter } => {
+          builder.listener_with_filter(Arc::new(StdoutListener::new()), filter.build()?)
+        },
+        ListenerConfig::File {
+          path,
+          rotation,
+          filter,
+        } => {
+          let policy = rotation.unwrap_or_default().build()?;
+          let listener = FileListener::with_rotation(&path, policy)?;
+          builder.listener_with_filter(Arc::new(listener), filter.build()?)
+        },
+      };
+    }
+
+    Ok(builder)
+  }
+
+  /// The complete level configuration: `directives` with `level` (or the
+  /// builder default, `WARN`) as the default when they do not set one.
+  pub fn resolved_directives(&self) -> Result<Directives, ConfigError> {
+    let directives = match &self.directives {
+      Some(directives) => Directives::parse(directives).map_err(ConfigError::Directives)?,
+      None => Directives::new(),
+    };
+
+    if directives.default_level().is_some() {
+      return Ok(directives);
+    }
+
+    let level = match &self.level {
+      Some(level) => parse_level(level)?,
+      None => LogLevel::WARN,
+    };
+    Ok(directives.with_default(level))
+  }
+
+  pub fn storage_layout(&self) -> Result<StorageLayout, ConfigError> {
+    match self.storage.layout.as_deref() {
+      None | Some("flat") => Ok(StorageLayout::Flat),
+      Some("per_service") => Ok(StorageLayout::PerService),
+      Some(other) => Err(ConfigError::Invalid(format!(
+        "unknown storage layout `{}`",
+        other
+      ))),
+    }
+  }
+
+  /// `Some(None)` when periodic snapshots are disabled, `None` when unset.
+  pub fn snapshot_interval(&self) -> Option<Option<Duration>> {
+    self
+      .snapshots
+      .interval_secs
+      .map(|secs| (secs > 0).then(|| Duration::from_secs(secs)))
+  }
+
+  /// The mode of each trigger, `Drain` where unset.
+  pub fn snapshot_modes(&self) -> Result<SnapshotModes, ConfigError> {
+    fn parse(mode: &Option<String>) -> Result<SnapshotMode, ConfigError> {
+      match mode.as_deref() {
+        None | Some("drain") => Ok(SnapshotMode::Drain),
+        Some("peek") => Ok(SnapshotMode::Peek),
+        Some(other) => Err(ConfigError::Invalid(format!(
+          "unknown snapshot mode `{}`",
+          other
+        ))),
+      }
+    }
+
+    let modes = &self.snapshots.modes;
+    Ok(SnapshotModes {
+      panic: parse(&modes.panic)?,
+      signal: parse(&modes.signal)?,
+      periodic: parse(&modes.periodic)?,
+      manual: parse(&modes.manual)?,
+    })
+  }
+
+  /// Start the global logger described by this config.
+  pub fn build(self) -> Result<Trace, ConfigError> {
+    self.into_builder()?.build().map_err(ConfigError::Trace)
+  }
+}
+
+impl FilterConfig {
+  pub fn build(&self) -> Result<ListenerFilter, ConfigError> {
+    let mut filter = ListenerFilter::new();
+    if let Some(level) = &self.level {
+      filter = filter.min_level(parse_level(level)?);
+    }
+    for target in &self.targets {
+      filter = filter.allow_target(target.as_str());
+    }
+    for target in &self.deny_targets {
+      filter = filter.deny_target(target.as_str());
+    }
+    Ok(filter)
+  }
+}
+
+impl RotationConfig {
+  pub fn build(&self) -> Result<RotationPolicy, ConfigError> {
+    let mut policy = RotationPolicy::new();
+    if let Some(bytes) = self.max_bytes {
+      policy = policy.max_size(bytes);
+    }
+    if let Some(secs) = self.interval_secs {
+      policy = policy.every(Duration::from_secs(secs));
+    }
+    if let Some(keep) = self.keep {
+      policy = policy.keep(keep);
+    }
+    if let Some(compression) = &self.compression {
+      policy = policy.compress(match compression.as_str() {
+        "none" => Compression::None,
+        "lz4" => Compression::Lz4,
+        #[cfg(feature = "gzip")]
+        "gzip" => Compression::Gzip,
+        other => {
+          return Err(ConfigError::Invalid(format!(
+            "unsupported compression `{}`",
+            other
+          )))
+        },
+      });
+    }
+    Ok(policy)
+  }
+}
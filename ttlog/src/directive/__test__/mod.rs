@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod __test__ {
+
+  use std::sync::atomic::Ordering;
+  use std::sync::Arc;
+
+  use crossbeam_channel::{bounded, unbounded};
+
+  use crate::directive::{Callsite, Directives, LEVEL_OFF, USE_GLOBAL};
+  use crate::event::{LogEvent, LogLevel};
+  use crate::lf_buffer::LockFreeRingBuffer;
+  use crate::string_interner::StringInterner;
+  use crate::trace::{EventBroadcast, ListenerMessage, Message, Trace};
+
+  fn build_trace() -> Trace {
+    let (msg_tx, _msg_rx) = bounded::<Message>(10);
+    let (listener_tx, _listener_rx) = bounded::<ListenerMessage>(10);
+    let (event_tx, _event_rx) = unbounded::<EventBroadcast>();
+    let interner = Arc::new(StringInterner::new());
+    let buffer = Arc::new(LockFreeRingBuffer::<LogEvent>::new(16));
+    Trace::new(msg_tx, listener_tx, event_tx, interner, buffer)
+  }
+
+  // ── Parsing ────────────────────────────────────────────────────────
+
+  #[test]
+  fn parse_default_and_targets() {
+    let directives = Directives::parse("info, my_crate::db=trace ,hyper=WARN").unwrap();
+    assert_eq!(directives.default_level(), Some(LogLevel::INFO as u8));
+    assert_eq!(
+      directives.threshold_for("my_crate::db"),
+      LogLevel::TRACE as u8
+    );
+    assert_eq!(
+      directives.threshold_for("hyper::client"),
+      LogLevel::WARN as u8
+    );
+    assert_eq!(directives.threshold_for("my_crate"), USE_GLOBAL);
+  }
+
+  #[test]
+  fn parse_bare_target_enables_everything() {
+    let directives = Directives::parse("my_crate").unwrap();
+    assert_eq!(directives.default_level(), None);
+    assert_eq!(
+      directives.threshold_for("my_crate::x"),
+      LogLevel::TRACE as u8
+    );
+  }
+
+  #[test]
+  fn parse_off_and_empty_segments() {
+    let directives = Directives::parse(",noisy=off,,").unwrap();
+    assert_eq!(directives.threshold_for("noisy"), LEVEL_OFF);
+    assert_eq!(Directives::parse("").unwrap(), Directives::new());
+  }
+
+  #[test]
+  fn parse_rejects_unknown_level_and_missing_target() {
+    let err = Directives::parse("db=loud").unwrap_err();
+    assert_eq!(err.directive, "db=loud");
+    assert!(Directives::parse("=info").is_err());
+    assert!(Directives::parse("a b").is_err());
+  }
+
+  #[test]
+  fn longest_target_wins_and_boundaries_respected() {
+    let directives = Directives::parse("app=warn,app::db=trace").unwrap();
+    assert_eq!(
+      directives.threshold_for("app::db::pool"),
+      LogLevel::TRACE as u8
+    );
+    assert_eq!(directives.threshold_for("app::http"), LogLevel::WARN as u8);
+    assert_eq!(directives.threshold_for("application"), USE_GLOBAL);
+  }
+
+  #[test]
+  fn display_round_trips() {
+    let directives = Directives::parse("debug,app=warn,app::db=off").unwrap();
+    assert_eq!(
+      Directives::parse(&directives.to_string()).unwrap(),
+      directives
+    );
+  }
+
+  // ── Callsite cache ─────────────────────────────────────────────────
+
+  #[test]
+  fn callsite_follows_global_level_without_directives() {
+    let trace = build_trace();
+    let callsite = Callsite::new();
+    trace.level.store(LogLevel::INFO as u8, Ordering::Relaxed);
+    assert!(!callsite.enabled(&trace, "app", LogLevel::DEBUG as u8));
+
+    trace.level.store(LogLevel::DEBUG as u8, Ordering::Relaxed);
+    assert!(callsite.enabled(&trace, "app", LogLevel::DEBUG as u8));
+  }
+
+  #[test]
+  fn callsite_is_invalidated_by_new_directives() {
+    let trace = build_trace();
+    let callsite = Callsite::new();
+    trace.set_directives_str("error,app::db=trace").unwrap();
+    assert!(callsite.enabled(&trace, "app::db", LogLevel::TRACE as u8));
+
+    trace.set_directives_str("error,app::db=warn").unwrap();
+    assert!(!callsite.enabled(&trace, "app::db", LogLevel::TRACE as u8));
+    assert!(callsite.enabled(&trace, "app::db", LogLevel::WARN as u8));
+    assert!(!trace.is_enabled(LogLevel::WARN as u8, "app::http"));
+  }
+
+  #[test]
+  fn default_off_disables_everything() {
+    let trace = build_trace();
+    trace.set_directives_str("off").unwrap();
+    assert!(!trace.is_enabled(LogLevel::FATAL as u8, "app"));
+    assert_eq!(trace.get_level(), LogLevel::FATAL);
+  }
+}
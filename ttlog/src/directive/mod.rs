@@ -0,0 +1,221 @@
+mod __test__;
+
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::event::LogLevel;
+use crate::trace::Trace;
+use crate::utils;
+
+/// Threshold above every level; `my_crate=off` disables a target entirely.
+pub const LEVEL_OFF: u8 = LogLevel::FATAL as u8 + 1;
+
+/// Threshold value meaning "no directive matched, use `Trace::level`".
+pub const USE_GLOBAL: u8 = u8::MAX;
+
+/// Per-target level directives in `env_logger` syntax:
+/// `info,my_crate::db=trace,hyper=warn`.
+///
+/// A bare level sets the default (stored in `Trace::level`); `target=level`
+/// overrides it for `target` and its submodules, the longest matching target
+/// winning. A bare target enables everything for it. Levels are
+/// case-insensitive and include `off`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Directives {
+  default: Option<u8>,
+  /// `(target, threshold)`, longest target first.
+  targets: Vec<(String, u8)>,
+}
+
+/// A directive string that could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectiveParseError {
+  pub directive: String,
+  pub reason: &'static str,
+}
+
+impl fmt::Display for DirectiveParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "invalid directive `{}`: {}", self.directive, self.reason)
+  }
+}
+
+impl Error for DirectiveParseError {}
+
+/// A level name, case-insensitive, or `off`/`none` as [`LEVEL_OFF`]. The
+/// config loader reads levels through it too.
+pub(crate) fn parse_level(input: &str) -> Option<u8> {
+  match input.trim().to_ascii_lowercase().as_str() {
+    "off" | "none" => Some(LEVEL_OFF),
+    "trace" => Some(LogLevel::TRACE as u8),
+    "debug" => Some(LogLevel::DEBUG as u8),
+    "info" => Some(LogLevel::INFO as u8),
+    "warn" | "warning" => Some(LogLevel::WARN as u8),
+    "error" => Some(LogLevel::ERROR as u8),
+    "fatal" => Some(LogLevel::FATAL as u8),
+    _ => None,
+  }
+}
+
+impl Directives {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Parse a comma separated directive list. Empty segments are ignored.
+  pub fn parse(input: &str) -> Result<Self, DirectiveParseError> {
+    let mut directives = Self::new();
+
+    for segment in input.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+      let error = |reason| DirectiveParseError {
+        directive: segment.to_string(),
+        reason,
+      };
+
+      match segment.split_once('=') {
+        Some((target, level)) => {
+          let target = target.trim();
+          if target.is_empty() {
+            return Err(error("missing target before `=`"));
+          }
+          let level = parse_level(level).ok_or_else(|| error("unknown level"))?;
+          directives = directives.with_target(target, level);
+        },
+        None => match parse_level(segment) {
+          Some(level) => directives.default = Some(level),
+          None
+            if segment
+              .chars()
+              .all(|c| c.is_alphanumeric() || c == '_' || c == ':') =>
+          {
+            directives = directives.with_target(segment, LogLevel::TRACE as u8);
+          },
+          None => return Err(error("expected a level, a target or `target=level`")),
+        },
+      }
+    }
+
+    Ok(directives)
+  }
+
+  /// Read and parse the directives in environment variable `var`. Returns
+  /// `None` if it is unset.
+  pub fn from_env(var: &str) -> Option<Result<Self, DirectiveParseError>> {
+    std::env::var(var).ok().map(|value| Self::parse(&value))
+  }
+
+  /// Set the default level.
+  pub fn with_default(mut self, level: LogLevel) -> Self {
+    self.default = Some(level as u8);
+    self
+  }
+
+  /// Add or replace the override for `target`; `level` is a `LogLevel` as
+  /// `u8` or [`LEVEL_OFF`].
+  pub fn with_target(mut self, target: impl Into<String>, level: u8) -> Self {
+    let target = target.into();
+    let target = target.strip_suffix("::*").unwrap_or(&target).to_string();
+    self.targets.retain(|(existing, _)| *existing != target);
+    self.targets.push((target, level));
+    self
+      .targets
+      .sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+    self
+  }
+
+  /// Default level given by a bare level directive, if any.
+  pub fn default_level(&self) -> Option<u8> {
+    self.default
+  }
+
+  pub fn has_targets(&self) -> bool {
+    !self.targets.is_empty()
+  }
+
+  /// Threshold for `target`, or [`USE_GLOBAL`] when no target directive
+  /// matches it.
+  pub fn threshold_for(&self, target: &str) -> u8 {
+    self
+      .targets
+      .iter()
+      .find(|(prefix, _)| utils::target_matches(target, prefix))
+      .map(|(_, level)| *level)
+      .unwrap_or(USE_GLOBAL)
+  }
+}
+
+impl fmt::Display for Directives {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let level_name = |level: u8| match level {
+      LEVEL_OFF => "off",
+      level => LogLevel::from_u8(&level).as_str(),
+    };
+
+    let mut first = true;
+    if let Some(level) = self.default {
+      write!(f, "{}", level_name(level).to_ascii_lowercase())?;
+      first = false;
+    }
+    for (target, level) in self.targets.iter().rev() {
+      if !first {
+        write!(f, ",")?;
+      }
+      write!(f, "{}={}", target, level_name(*level).to_ascii_lowercase())?;
+      first = false;
+    }
+    Ok(())
+  }
+}
+
+/// Level cache for one macro callsite.
+///
+/// Holds the directive generation it was computed for in the upper 56 bits
+/// and the threshold in the low byte, so a single load answers whether it is
+/// still valid. `Trace::set_directives` bumps the generation, which makes
+/// every callsite re-resolve its target on its next call.
+#[derive(Debug)]
+pub struct Callsite {
+  state: AtomicU64,
+}
+
+impl Callsite {
+  pub const fn new() -> Self {
+    Self {
+      state: AtomicU64::new(0),
+    }
+  }
+
+  /// Whether an event at `level` from `target` passes `logger`'s filter.
+  #[inline(always)]
+  pub fn enabled(&self, logger: &Trace, target: &str, level: u8) -> bool {
+    let generation = logger.directives_generation();
+    let state = self.state.load(Ordering::Relaxed);
+
+    let threshold = if state >> 8 == generation {
+      state as u8
+    } else {
+      self.refresh(logger, target, generation)
+    };
+
+    match threshold {
+      USE_GLOBAL => level >= logger.level.load(Ordering::Relaxed),
+      threshold => level >= threshold,
+    }
+  }
+
+  #[cold]
+  fn refresh(&self, logger: &Trace, target: &str, generation: u64) -> u8 {
+    let threshold = logger.threshold_for(target);
+    self
+      .state
+      .store((generation << 8) | threshold as u64, Ordering::Relaxed);
+    threshold
+  }
+}
+
+impl Default for Callsite {
+  fn default() -> Self {
+    Self::new()
+  }
+}
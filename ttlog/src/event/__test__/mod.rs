@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod __test__ {
 
-  use std::num::NonZeroU16;
+  use std::num::NonZeroU32;
   use std::sync::Arc;
 
   use crate::event::{Field, FieldValue, LogEvent, LogLevel};
@@ -54,6 +54,15 @@ mod __test__ {
     assert_eq!(event.position, (0, 0));
   }
 
+  #[test]
+  fn test_log_event_without_span_and_clock_fields_deserializes() {
+    let json = r#"{"packed_meta":0,"target_id":1,"message_id":null,"kv_id":null,"file_id":2,"position":[3,4]}"#;
+    let event: LogEvent = serde_json::from_str(json).unwrap();
+    assert_eq!(event.span_id, 0);
+    assert_eq!(event.mono_ns, 0);
+    assert_eq!(event.seq, 0);
+  }
+
   #[test]
   fn test_log_event_pack_unpack_meta() {
     let timestamp = 1234567890;
@@ -88,8 +97,8 @@ mod __test__ {
     let mut event = LogEvent::new();
     event.packed_meta = 12345;
     event.target_id = 1;
-    event.message_id = NonZeroU16::new(2);
-    event.kv_id = NonZeroU16::new(3);
+    event.message_id = NonZeroU32::new(2);
+    event.kv_id = NonZeroU32::new(3);
     event.file_id = 4;
     event.position = (10, 20);
 
@@ -174,8 +183,7 @@ mod __test__ {
     let kv_bytes = interner
       .get_kv(event.kv_id.unwrap().get())
       .expect("kv stored");
-    let kv_str = std::str::from_utf8(&kv_bytes).expect("utf8");
-    let parsed: serde_json::Value = serde_json::from_str(kv_str).expect("json");
+    let parsed = crate::kv_codec::decode(&kv_bytes, &interner).expect("decodes");
 
     assert_eq!(parsed["key1"], serde_json::json!(42));
     assert_eq!(parsed["key2"], serde_json::json!(true));
@@ -193,7 +201,7 @@ mod __test__ {
   fn test_log_event_display() {
     let mut event = LogEvent::new();
     event.target_id = 5;
-    event.message_id = NonZeroU16::new(10);
+    event.message_id = NonZeroU32::new(10);
 
     let display_str = format!("{}", event);
     assert_eq!(display_str, "Event(target_id=5, message_id=10)");
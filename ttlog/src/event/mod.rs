@@ -40,6 +40,17 @@ impl LogLevel {
     }
   }
 
+  #[cfg(feature = "log")]
+  pub fn from_log_level(level: log::Level) -> Self {
+    match level {
+      log::Level::Trace => LogLevel::TRACE,
+      log::Level::Debug => LogLevel::DEBUG,
+      log::Level::Info => LogLevel::INFO,
+      log::Level::Warn => LogLevel::WARN,
+      log::Level::Error => LogLevel::ERROR,
+    }
+  }
+
   #[inline]
   pub fn from_u8(level: &u8) -> LogLevel {
     match level {
@@ -92,12 +103,12 @@ pub enum FieldValue {
   I64(i64),
   F32(f32),
   F64(f64),
-  StringId(u16),
+  StringId(u32),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Copy)]
 pub struct Field {
-  pub key_id: u16,
+  pub key_id: u32,
   pub value: FieldValue,
 }
 
@@ -136,12 +147,26 @@ impl FieldValue {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEvent {
+  /// Wall-clock milliseconds, level and thread ID (see [`Self::pack_meta`]).
+  /// The milliseconds are derived from `mono_ns`, not read from a second
+  /// clock, and are kept for readers of snapshots that predate `mono_ns`.
   pub packed_meta: u64,
-  pub target_id: u16,
-  pub message_id: Option<num::NonZeroU16>,
-  pub kv_id: Option<num::NonZeroU16>,
-  pub file_id: u16,
+  pub target_id: u32,
+  pub message_id: Option<num::NonZeroU32>,
+  pub kv_id: Option<num::NonZeroU32>,
+  pub file_id: u32,
   pub position: (u32, u32),
+  /// Innermost span entered when the event was logged, 0 outside any span.
+  #[serde(default)]
+  pub span_id: u64,
+  /// Monotonic nanoseconds since the process clock anchor (see
+  /// [`crate::clock`]), 0 when the event was not stamped.
+  #[serde(default)]
+  pub mono_ns: u64,
+  /// Process-wide sequence number giving a total order over events, 0 when
+  /// unassigned.
+  #[serde(default)]
+  pub seq: u64,
 }
 
 impl Default for LogEvent {
@@ -177,7 +202,7 @@ impl LogEvent {
   }
 
   #[inline]
-  pub fn target(&mut self, target_id: u16) -> &mut Self {
+  pub fn target(&mut self, target_id: u32) -> &mut Self {
     self.target_id = target_id;
     self
   }
@@ -187,10 +212,13 @@ impl LogEvent {
     Self {
       packed_meta: 0,
       target_id: 0,
-      message_id: num::NonZeroU16::new(0),
-      kv_id: num::NonZeroU16::new(0),
+      message_id: num::NonZeroU32::new(0),
+      kv_id: num::NonZeroU32::new(0),
       file_id: 0,
       position: (0, 0),
+      span_id: 0,
+      mono_ns: 0,
+      seq: 0,
     }
   }
 
@@ -198,10 +226,13 @@ impl LogEvent {
   pub fn reset(&mut self) {
     self.packed_meta = 0;
     self.target_id = 0;
-    self.message_id = num::NonZeroU16::new(0);
-    self.kv_id = num::NonZeroU16::new(0);
+    self.message_id = num::NonZeroU32::new(0);
+    self.kv_id = num::NonZeroU32::new(0);
     self.file_id = 0;
     self.position = (0, 0);
+    self.span_id = 0;
+    self.mono_ns = 0;
+    self.seq = 0;
     // Note: fields array is not cleared for performance -
     // it will be overwritten as field_count increases
   }
@@ -217,7 +248,7 @@ impl LogEvent {
 }
 
 const _: () = {
-  assert!(std::mem::size_of::<LogEvent>() == 24);
+  assert!(std::mem::size_of::<LogEvent>() == 56);
   assert!(std::mem::align_of::<LogEvent>() >= 8);
 };
 
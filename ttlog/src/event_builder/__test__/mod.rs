@@ -5,6 +5,7 @@ mod __test__ {
 
   use crate::event::{FieldValue, LogLevel};
   use crate::event_builder::EventBuilder;
+  use crate::kv_codec;
   use crate::string_interner::StringInterner;
 
   fn make_builder() -> (EventBuilder, Arc<StringInterner>) {
@@ -61,7 +62,7 @@ mod __test__ {
     assert!(event.kv_id.is_some());
 
     let kv_bytes = interner.get_kv(event.kv_id.unwrap().get()).unwrap();
-    let parsed: serde_json::Value = serde_json::from_slice(&kv_bytes).unwrap();
+    let parsed = kv_codec::decode(&kv_bytes, &interner).unwrap();
     assert_eq!(parsed["count"], serde_json::json!(10));
     assert_eq!(parsed["active"], serde_json::json!(true));
   }
@@ -80,7 +81,7 @@ mod __test__ {
 
     let event = builder.build_with_fields(0, LogLevel::DEBUG, "math", "const", &fields);
     let kv_bytes = interner.get_kv(event.kv_id.unwrap().get()).unwrap();
-    let parsed: serde_json::Value = serde_json::from_slice(&kv_bytes).unwrap();
+    let parsed = kv_codec::decode(&kv_bytes, &interner).unwrap();
     let pi = parsed["pi"].as_f64().unwrap();
     assert!((pi - 3.14159).abs() < 1e-5);
   }
@@ -95,7 +96,7 @@ mod __test__ {
 
     let event = builder.build_with_fields(0, LogLevel::WARN, "err", "neg", &fields);
     let kv_bytes = interner.get_kv(event.kv_id.unwrap().get()).unwrap();
-    let parsed: serde_json::Value = serde_json::from_slice(&kv_bytes).unwrap();
+    let parsed = kv_codec::decode(&kv_bytes, &interner).unwrap();
     assert_eq!(parsed["code"], serde_json::json!(-1));
     assert_eq!(parsed["big"], serde_json::json!(-9999999));
   }
@@ -140,12 +141,13 @@ mod __test__ {
   #[test]
   fn build_with_fields_string_id_variant() {
     let (builder, interner) = make_builder();
-    let fields = vec![("ref_id".to_string(), FieldValue::StringId(42))];
+    let region = interner.intern_key("eu-west");
+    let fields = vec![("region".to_string(), FieldValue::StringId(region))];
 
     let event = builder.build_with_fields(0, LogLevel::INFO, "t", "m", &fields);
     let kv_bytes = interner.get_kv(event.kv_id.unwrap().get()).unwrap();
-    let parsed: serde_json::Value = serde_json::from_slice(&kv_bytes).unwrap();
-    assert_eq!(parsed["ref_id"], serde_json::json!(42));
+    let parsed = kv_codec::decode(&kv_bytes, &interner).unwrap();
+    assert_eq!(parsed["region"], serde_json::json!("eu-west"));
   }
 
   #[test]
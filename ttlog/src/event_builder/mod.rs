@@ -1,11 +1,11 @@
 mod __test__;
 
-use std::num::NonZeroU16;
+use std::num::NonZeroU32;
 use std::sync::Arc;
 
-use smallvec::SmallVec;
-
+use crate::clock;
 use crate::event::{FieldValue, LogEvent, LogLevel};
+use crate::kv_codec::FieldEncoder;
 use crate::string_interner::StringInterner;
 
 /// Helper for constructing `LogEvent` instances outside of the macro pipeline.
@@ -45,16 +45,13 @@ impl EventBuilder {
       return self.build_fast(timestamp, level, target, message);
     }
 
-    let mut serialized = serde_json::Map::with_capacity(fields.len());
+    let mut encoder = FieldEncoder::new();
     for (key, value) in fields {
-      serialized.insert(key.clone(), value.to_json_value());
+      encoder.value(self.interner.intern_key(key), *value);
     }
-    let json = serde_json::Value::Object(serialized).to_string();
-    let mut buf: SmallVec<[u8; 128]> = SmallVec::with_capacity(json.len());
-    buf.extend_from_slice(json.as_bytes());
 
-    let kv_id = self.interner.intern_kv(buf);
-    let kv_id = NonZeroU16::new(kv_id);
+    let kv_id = self.interner.store_kv(encoder.finish());
+    let kv_id = NonZeroU32::new(kv_id);
 
     self.build_event(timestamp, level, target, message, kv_id)
   }
@@ -65,7 +62,7 @@ impl EventBuilder {
     level: LogLevel,
     target: &str,
     message: &str,
-    kv_id: Option<NonZeroU16>,
+    kv_id: Option<NonZeroU32>,
   ) -> LogEvent {
     let target_id = self.interner.intern_target(target);
     let message_id = self.interner.intern_message(message);
@@ -73,10 +70,13 @@ impl EventBuilder {
     LogEvent {
       packed_meta: LogEvent::pack_meta(timestamp, level, 0),
       target_id,
-      message_id: NonZeroU16::new(message_id),
+      message_id: NonZeroU32::new(message_id),
       kv_id,
       file_id: 0,
       position: (0, 0),
+      span_id: 0,
+      mono_ns: 0,
+      seq: clock::next_seq(),
     }
   }
 }
@@ -1,106 +1,506 @@
+use crate::clock;
 use crate::event::LogEvent;
+use crate::format;
+use crate::kv_codec;
 use crate::listener::LogListener;
 use crate::string_interner::StringInterner;
-use std::fs::OpenOptions;
+use chrono::Utc;
+use crossbeam_channel::{unbounded, Sender};
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, Write as IoWrite};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 const FILE_FLUSH_THRESHOLD_BYTES: usize = 64 * 1024;
+/// Most bytes kept for another attempt while writes keep failing; the oldest
+/// lines beyond it are dropped.
+const MAX_PENDING_BYTES: usize = 16 * FILE_FLUSH_THRESHOLD_BYTES;
+
+/// Compression applied to rotated log segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+  #[default]
+  None,
+  /// LZ4 frame format, readable with the `lz4` command line tool. Adds `.lz4`.
+  Lz4,
+  /// gzip, readable with `zcat`. Adds `.gz`.
+  #[cfg(feature = "gzip")]
+  Gzip,
+}
+
+impl Compression {
+  fn extension(self) -> Option<&'static str> {
+    match self {
+      Compression::None => None,
+      Compression::Lz4 => Some("lz4"),
+      #[cfg(feature = "gzip")]
+      Compression::Gzip => Some("gz"),
+    }
+  }
+}
+
+/// When and how a [`FileListener`] rotates its file.
+///
+/// The active file keeps its configured path. On rotation it is renamed to
+/// `<path>.<UTC timestamp>` and a fresh file is opened in its place; rotated
+/// segments are then compressed and pruned on a background thread so the
+/// listener never blocks on them.
+///
+/// ```ignore
+/// let policy = RotationPolicy::new()
+///   .max_size(100 * 1024 * 1024)
+///   .every(Duration::from_secs(3600))
+///   .keep(24)
+///   .compress(Compression::Lz4);
+/// let listener = FileListener::with_rotation("./logs/app.log", policy)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RotationPolicy {
+  max_bytes: Option<u64>,
+  interval: Option<Duration>,
+  max_files: Option<usize>,
+  compression: Compression,
+}
+
+impl RotationPolicy {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Rotate once the active file would grow beyond `bytes`.
+  pub fn max_size(mut self, bytes: u64) -> Self {
+    self.max_bytes = Some(bytes);
+    self
+  }
+
+  /// Rotate once the active file has been open for `interval`.
+  pub fn every(mut self, interval: Duration) -> Self {
+    self.interval = Some(interval);
+    self
+  }
+
+  /// Keep at most `files` rotated segments, deleting the oldest.
+  pub fn keep(mut self, files: usize) -> Self {
+    self.max_files = Some(files);
+    self
+  }
+
+  pub fn compress(mut self, compression: Compression) -> Self {
+    self.compression = compression;
+    self
+  }
+}
+
+/// The file currently written to, with what rotation needs to know about it.
+struct ActiveFile {
+  file: File,
+  written: u64,
+  opened: Instant,
+}
+
+/// Background thread compressing and pruning rotated segments.
+struct Rotator {
+  sender: Option<Sender<PathBuf>>,
+  handle: Option<JoinHandle<()>>,
+}
 
 /// File listener for structured logs
 pub struct FileListener {
   buffer: Mutex<String>,
-  file: Mutex<std::fs::File>,
+  file: Mutex<ActiveFile>,
+  path: PathBuf,
+  policy: RotationPolicy,
+  rotator: Mutex<Rotator>,
+  dropped_lines: AtomicU64,
 }
 
 impl FileListener {
   /// Open (or create if missing) file for appending logs
   pub fn new(path: &str) -> io::Result<Self> {
-    let path_obj = Path::new(path);
+    Self::with_rotation(path, RotationPolicy::default())
+  }
+
+  /// Open (or create if missing) file for appending logs, rotating it
+  /// according to `policy`.
+  pub fn with_rotation(path: &str, policy: RotationPolicy) -> io::Result<Self> {
+    let path = PathBuf::from(path);
 
     // Ensure parent directories exist
-    if let Some(parent) = path_obj.parent() {
-      std::fs::create_dir_all(parent)?;
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
     }
 
-    let file = OpenOptions::new()
-      .create(true)
-      .append(true)
-      .open(path_obj)?;
+    let file = open_active(&path)?;
+
+    let rotator = if policy.compression != Compression::None || policy.max_files.is_some() {
+      let (sender, receiver) = unbounded::<PathBuf>();
+      let base = path.clone();
+      let compression = policy.compression;
+      let max_files = policy.max_files;
+      let handle = thread::Builder::new()
+        .name("ttlog-rotate".into())
+        .spawn(move || {
+          for rotated in receiver {
+            if let Err(err) = compress_segment(&rotated, compression) {
+              eprintln!("[Trace] Failed to compress {}: {}", rotated.display(), err);
+            }
+            if let Some(max_files) = max_files {
+              if let Err(err) = prune_segments(&base, max_files) {
+                eprintln!("[Trace] Failed to prune rotated logs: {}", err);
+              }
+            }
+          }
+        })?;
+      Rotator {
+        sender: Some(sender),
+        handle: Some(handle),
+      }
+    } else {
+      Rotator {
+        sender: None,
+        handle: None,
+      }
+    };
 
     Ok(Self {
       buffer: Mutex::new(String::with_capacity(256)),
       file: Mutex::new(file),
+      path,
+      policy,
+      rotator: Mutex::new(rotator),
+      dropped_lines: AtomicU64::new(0),
+    })
+  }
+
+  /// Lines dropped because the file could not be written and more than
+  /// [`MAX_PENDING_BYTES`] were waiting for another attempt.
+  pub fn dropped_lines(&self) -> u64 {
+    self.dropped_lines.load(Ordering::Relaxed)
+  }
+
+  /// Write `buf` to the active file, rotating whenever it is due. With a size
+  /// limit the buffer is split at line boundaries so segments stay within it;
+  /// only a single line longer than the limit produces a larger segment.
+  fn write_out(&self, active: &mut ActiveFile, mut buf: &str) -> io::Result<()> {
+    let over_time = self
+      .policy
+      .interval
+      .is_some_and(|interval| active.opened.elapsed() >= interval);
+    if over_time {
+      if active.written > 0 {
+        self.rotate(active)?;
+      } else {
+        active.opened = Instant::now();
+      }
+    }
+
+    if let Some(max) = self.policy.max_bytes {
+      while active.written + buf.len() as u64 > max {
+        let room = (max.saturating_sub(active.written) as usize).min(buf.len());
+        let split = match buf.as_bytes()[..room].iter().rposition(|&b| b == b'\n') {
+          Some(newline) => newline + 1,
+          None if active.written == 0 => buf.find('\n').map_or(buf.len(), |newline| newline + 1),
+          None => 0,
+        };
+
+        active.file.write_all(&buf.as_bytes()[..split])?;
+        active.written += split as u64;
+        buf = &buf[split..];
+
+        if buf.is_empty() {
+          return Ok(());
+        }
+        self.rotate(active)?;
+      }
+    }
+
+    active.file.write_all(buf.as_bytes())?;
+    active.written += buf.len() as u64;
+    Ok(())
+  }
+
+  fn rotate(&self, active: &mut ActiveFile) -> io::Result<()> {
+    active.file.flush()?;
+
+    let rotated = rotated_path(&self.path);
+    fs::rename(&self.path, &rotated)?;
+    *active = open_active(&self.path)?;
+
+    if let Ok(rotator) = self.rotator.lock() {
+      if let Some(sender) = &rotator.sender {
+        let _ = sender.send(rotated);
+      }
+    }
+    Ok(())
+  }
+
+  fn rotation_due(&self) -> bool {
+    match self.policy.interval {
+      Some(interval) => self
+        .file
+        .lock()
+        .map(|active| active.opened.elapsed() >= interval)
+        .unwrap_or(false),
+      None => false,
+    }
+  }
+}
+
+fn open_active(path: &Path) -> io::Result<ActiveFile> {
+  let file = OpenOptions::new().create(true).append(true).open(path)?;
+  let written = file.metadata()?.len();
+
+  Ok(ActiveFile {
+    file,
+    written,
+    opened: Instant::now(),
+  })
+}
+
+/// `<path>.<timestamp>`, with a counter appended if several rotations happen
+/// within the same millisecond.
+fn rotated_path(path: &Path) -> PathBuf {
+  let stamp = Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+  let mut candidate = PathBuf::from(format!("{}.{}", path.display(), stamp));
+  let mut n = 1;
+  while candidate.exists() {
+    candidate = PathBuf::from(format!("{}.{}-{}", path.display(), stamp, n));
+    n += 1;
+  }
+  candidate
+}
+
+fn compress_segment(path: &Path, compression: Compression) -> io::Result<()> {
+  let Some(extension) = compression.extension() else {
+    return Ok(());
+  };
+
+  let target = PathBuf::from(format!("{}.{}", path.display(), extension));
+  let mut input = File::open(path)?;
+  let output = File::create(&target)?;
+
+  match compression {
+    Compression::None => {},
+    Compression::Lz4 => {
+      let mut encoder = lz4::EncoderBuilder::new().build(output)?;
+      io::copy(&mut input, &mut encoder)?;
+      let (_, result) = encoder.finish();
+      result?;
+    },
+    #[cfg(feature = "gzip")]
+    Compression::Gzip => {
+      let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+      io::copy(&mut input, &mut encoder)?;
+      encoder.finish()?;
+    },
+  }
+
+  fs::remove_file(path)
+}
+
+/// Rotation order of `name` if it is a segment [`rotated_path`] and
+/// [`compress_segment`] produce for `base`: `<base>.<timestamp>`, optionally
+/// followed by `-<counter>` and a compression extension.
+fn segment_order<'a>(name: &'a str, base: &str) -> Option<(&'a str, u64)> {
+  let rest = name.strip_prefix(base)?.strip_prefix('.')?;
+  let rest = ["lz4", "gz"]
+    .iter()
+    .find_map(|extension| rest.strip_suffix(extension)?.strip_suffix('.'))
+    .unwrap_or(rest);
+
+  // `%Y%m%dT%H%M%S%.3fZ`, e.g. `20240131T235959.123Z`.
+  const STAMP: &[u8] = b"00000000T000000.000Z";
+  let (stamp, counter) = rest.split_at_checked(STAMP.len())?;
+  let is_stamp = stamp.bytes().zip(STAMP).all(|(b, &pattern)| match pattern {
+    b'0' => b.is_ascii_digit(),
+    _ => b == pattern,
+  });
+  if !is_stamp {
+    return None;
+  }
+
+  let counter = match counter.strip_prefix('-') {
+    None if counter.is_empty() => 0,
+    Some(n) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => n.parse().ok()?,
+    _ => return None,
+  };
+  Some((stamp, counter))
+}
+
+/// Delete the oldest rotated segments of `base` beyond `max_files`. Other
+/// files sharing the `<base>.` prefix are left alone.
+fn prune_segments(base: &Path, max_files: usize) -> io::Result<()> {
+  let dir = match base.parent() {
+    Some(dir) if !dir.as_os_str().is_empty() => dir,
+    _ => Path::new("."),
+  };
+  let base = match base.file_name() {
+    Some(name) => name.to_string_lossy().into_owned(),
+    None => return Ok(()),
+  };
+
+  let mut segments: Vec<(String, u64, PathBuf)> = fs::read_dir(dir)?
+    .filter_map(|entry| entry.ok())
+    .filter_map(|entry| {
+      let name = entry.file_name().into_string().ok()?;
+      let (stamp, counter) = segment_order(&name, &base)?;
+      Some((stamp.to_string(), counter, entry.path()))
     })
+    .collect();
+  // Timestamps sort lexicographically; the counter orders segments rotated
+  // within the same millisecond.
+  segments.sort();
+
+  let excess = segments.len().saturating_sub(max_files);
+  for (_, _, path) in &segments[..excess] {
+    fs::remove_file(path)?;
+  }
+  Ok(())
+}
+
+impl FileListener {
+  /// Write out and clear `buf`; it is kept for the next attempt on failure,
+  /// up to [`MAX_PENDING_BYTES`].
+  fn write_buffer(&self, buf: &mut String) {
+    if buf.is_empty() {
+      return;
+    }
+    if let Ok(mut active) = self.file.lock() {
+      match self.write_out(&mut active, buf) {
+        Ok(()) => buf.clear(),
+        Err(err) => {
+          eprintln!("[Trace] Failed to write log file: {}", err);
+          self.drop_oldest_lines(buf);
+        },
+      }
+    }
+  }
+
+  /// Drop whole lines from the front of `buf` until it fits in
+  /// [`MAX_PENDING_BYTES`].
+  fn drop_oldest_lines(&self, buf: &mut String) {
+    let Some(excess) = buf.len().checked_sub(MAX_PENDING_BYTES) else {
+      return;
+    };
+    let cut = buf.as_bytes()[excess..]
+      .iter()
+      .position(|&b| b == b'\n')
+      .map_or(buf.len(), |newline| excess + newline + 1);
+    let lines = buf[..cut].matches('\n').count() as u64;
+
+    buf.drain(..cut);
+    self.dropped_lines.fetch_add(lines, Ordering::Relaxed);
+  }
+}
+
+/// Append `event` to `buf` as one line of newline-delimited JSON.
+fn append_line(buf: &mut String, event: &LogEvent, interner: &StringInterner) {
+  // Keep Arc values alive by binding them to variables
+  let target_arc = interner.get_target(event.target_id);
+  let target = target_arc.as_deref().unwrap_or("unknown");
+
+  let message_arc = event.message_id.and_then(|v| interner.get_message(v.get()));
+  let message = message_arc.as_deref().unwrap_or("unknown");
+
+  let file_arc = interner.get_file(event.file_id);
+  let file = file_arc.as_deref().unwrap_or("unknown");
+
+  let kv_data = event.kv_id.and_then(|id| {
+    interner
+      .get_kv(id.get())
+      .and_then(|bytes| kv_codec::decode(&bytes, interner))
+  });
+
+  // Format arguments are rendered into the message, not written as KV.
+  let (kv_data, args) = match kv_data {
+    Some(kv) => {
+      let (kv, args) = format::split_args(kv);
+      (Some(kv), args)
+    },
+    None => (None, Vec::new()),
+  };
+  let message = format::render(
+    message,
+    &args,
+    kv_data.as_ref().unwrap_or(&serde_json::Value::Null),
+  );
+
+  let (timestamp, level, thread_id) = LogEvent::unpack_meta(event.packed_meta);
+  let timestamp_ns = match event.mono_ns {
+    0 => timestamp.saturating_mul(1_000_000),
+    mono_ns => clock::unix_ns(mono_ns),
+  };
+
+  // Build structured JSON object
+  let log_json = serde_json::json!({
+      "timestamp": timestamp,
+      "level": level,
+      "thread_id": thread_id,
+      "file": file,
+      "kv": kv_data,
+      "target": target,
+      "message": message,
+      "position": event.position,
+      "span_id": event.span_id,
+      "timestamp_ns": timestamp_ns,
+      "seq": event.seq,
+  });
+
+  // Serialize as compact JSON (not pretty) to make one JSON object per line
+  match serde_json::to_string(&log_json) {
+    Ok(line) => {
+      buf.push_str(&line);
+      buf.push('\n'); // newline-delimited JSON (NDJSON)
+    },
+    Err(err) => {
+      eprintln!("[Trace] Failed to serialize log JSON: {}", err);
+    },
   }
 }
 
 impl LogListener for FileListener {
   fn handle(&self, event: &LogEvent, interner: &StringInterner) {
     if let Ok(mut buf) = self.buffer.lock() {
-      // Keep Arc values alive by binding them to variables
-      let target_arc = interner.get_target(event.target_id);
-      let target = target_arc.as_deref().unwrap_or("unknown");
-
-      let message_arc = event.message_id.and_then(|v| interner.get_message(v.get()));
-      let message = message_arc.as_deref().unwrap_or("unknown");
-
-      let file_arc = interner.get_file(event.file_id);
-      let file = file_arc.as_deref().unwrap_or("unknown");
-
-      let kv_data = event.kv_id.and_then(|id| {
-        interner.get_kv(id.get()).and_then(|bytes| {
-          std::str::from_utf8(&bytes)
-            .ok()
-            .and_then(|s| serde_json::from_str::<serde_json::Value>(s).ok())
-        })
-      });
-
-      let (timestamp, level, thread_id) = LogEvent::unpack_meta(event.packed_meta);
-
-      // Build structured JSON object
-      let log_json = serde_json::json!({
-          "timestamp": timestamp,
-          "level": level,
-          "thread_id": thread_id,
-          "file": file,
-          "kv": kv_data,
-          "target": target,
-          "message": message,
-          "position": event.position,
-      });
-
-      // Serialize as compact JSON (not pretty) to make one JSON object per line
-      match serde_json::to_string(&log_json) {
-        Ok(line) => {
-          buf.push_str(&line);
-          buf.push('\n'); // newline-delimited JSON (NDJSON)
-
-          // Batch writes to reduce per-event syscalls and lock contention.
-          if buf.len() >= FILE_FLUSH_THRESHOLD_BYTES {
-            if let Ok(mut file) = self.file.lock() {
-              if file.write_all(buf.as_bytes()).is_ok() {
-                buf.clear();
-              }
-            }
-          }
-        },
-        Err(err) => {
-          eprintln!("[Trace] Failed to serialize log JSON: {}", err);
-        },
+      append_line(&mut buf, event, interner);
+
+      // Batch writes to reduce per-event syscalls and lock contention.
+      // A due time-based rotation also forces a write so that quiet
+      // periods do not stretch a segment past its interval.
+      if buf.len() >= FILE_FLUSH_THRESHOLD_BYTES || self.rotation_due() {
+        self.write_buffer(&mut buf);
+      }
+    }
+  }
+
+  /// Format the whole batch under one lock and write it with one call.
+  fn handle_batch(&self, events: &[LogEvent], interner: &StringInterner) {
+    if let Ok(mut buf) = self.buffer.lock() {
+      for event in events {
+        append_line(&mut buf, event, interner);
       }
+      self.write_buffer(&mut buf);
     }
   }
 
   fn on_shutdown(&self) {
     if let Ok(mut buf) = self.buffer.lock() {
-      if let Ok(mut file) = self.file.lock() {
+      if let Ok(mut active) = self.file.lock() {
         if !buf.is_empty() {
-          let _ = file.write_all(buf.as_bytes());
+          let _ = self.write_out(&mut active, &buf);
           buf.clear();
         }
-        let _ = file.flush();
+        let _ = active.file.flush();
+      }
+    }
+
+    // Let pending compression finish before the process goes away.
+    if let Ok(mut rotator) = self.rotator.lock() {
+      rotator.sender.take();
+      if let Some(handle) = rotator.handle.take() {
+        let _ = handle.join();
       }
     }
   }
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod __test__ {
+
+  use serde_json::json;
+  use std::borrow::Cow;
+
+  use crate::format::{escape, render, split_args, ARGS_KEY};
+
+  // ── split_args ─────────────────────────────────────────────────────
+
+  #[test]
+  fn split_args_removes_reserved_key() {
+    let kv = json!({ "user": "alice", ARGS_KEY: [1, "two"] });
+    let (kv, args) = split_args(kv);
+
+    assert_eq!(kv, json!({ "user": "alice" }));
+    assert_eq!(args, vec![json!(1), json!("two")]);
+  }
+
+  #[test]
+  fn split_args_without_args() {
+    let (kv, args) = split_args(json!({ "a": 1 }));
+    assert_eq!(kv, json!({ "a": 1 }));
+    assert!(args.is_empty());
+  }
+
+  #[test]
+  fn split_args_non_object_passthrough() {
+    let (kv, args) = split_args(json!(null));
+    assert_eq!(kv, json!(null));
+    assert!(args.is_empty());
+  }
+
+  // ── render ─────────────────────────────────────────────────────────
+
+  #[test]
+  fn render_plain_message_is_borrowed() {
+    let rendered = render("no placeholders", &[], &json!({}));
+    assert!(matches!(rendered, Cow::Borrowed("no placeholders")));
+  }
+
+  #[test]
+  fn render_positional_arguments() {
+    let args = [json!(42), json!("bob")];
+    assert_eq!(render("user {} is {}", &args, &json!({})), "user 42 is bob");
+  }
+
+  #[test]
+  fn render_indexed_arguments() {
+    let args = [json!("a"), json!("b")];
+    assert_eq!(render("{1}{0}{1}", &args, &json!({})), "bab");
+  }
+
+  #[test]
+  fn render_named_from_kv() {
+    let kv = json!({ "user": "alice" });
+    assert_eq!(render("hello {user}", &[], &kv), "hello alice");
+  }
+
+  #[test]
+  fn render_escaped_braces() {
+    assert_eq!(
+      render("{{literal}} {}", &[json!(1)], &json!({})),
+      "{literal} 1"
+    );
+  }
+
+  #[test]
+  fn render_missing_argument_kept_verbatim() {
+    assert_eq!(render("{} and {}", &[json!(1)], &json!({})), "1 and {}");
+    assert_eq!(render("{missing}", &[], &json!({})), "{missing}");
+  }
+
+  #[test]
+  fn render_unterminated_placeholder() {
+    assert_eq!(render("oops {", &[], &json!({})), "oops {");
+  }
+
+  #[test]
+  fn render_debug_quotes_strings() {
+    assert_eq!(render("{:?}", &[json!("x")], &json!({})), "\"x\"");
+  }
+
+  #[test]
+  fn render_precision_and_width() {
+    let args = [json!(3.14159), json!(7), json!("ab")];
+    assert_eq!(
+      render("{:.2}|{:03}|{:>4}", &args, &json!({})),
+      "3.14|007|  ab"
+    );
+  }
+
+  #[test]
+  fn render_ignores_precision_for_integers() {
+    let args = [json!(5), json!(-5), json!(5.0)];
+    assert_eq!(
+      render("{:.2}|{:>6.1}|{:.2}", &args, &json!({})),
+      format!("{:.2}|{:>6.1}|{:.2}", 5, -5, 5.0)
+    );
+  }
+
+  #[test]
+  fn render_fill_and_center() {
+    assert_eq!(render("{:*^7}", &[json!("ab")], &json!({})), "**ab***");
+  }
+
+  #[test]
+  fn render_hex_and_sign() {
+    let args = [json!(255), json!(5), json!(-3)];
+    assert_eq!(render("{:x} {:+} {:04}", &args, &json!({})), "ff +5 -003");
+  }
+
+  #[test]
+  fn render_non_scalar_values_as_json() {
+    let args = [json!([1, 2]), json!(null), json!(true)];
+    assert_eq!(render("{} {} {}", &args, &json!({})), "[1,2] null true");
+  }
+
+  // ── escape ─────────────────────────────────────────────────────────
+
+  #[test]
+  fn escape_round_trips_through_render() {
+    let message = r#"payload {"id": 1} done {}"#;
+    let escaped = escape(message);
+    assert_eq!(render(&escaped, &[json!(2)], &json!({ "id": 3 })), message);
+  }
+
+  #[test]
+  fn escape_plain_message_is_borrowed() {
+    assert!(matches!(escape("plain"), Cow::Borrowed("plain")));
+  }
+}
@@ -0,0 +1,268 @@
+mod __test__;
+
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// Reserved KV key under which the logging macros store positional format
+/// arguments (`info!("user {} logged in", id)`).
+///
+/// The template itself is interned once per callsite; only the captured values
+/// travel with the event and are rendered lazily by whoever reads it.
+pub const ARGS_KEY: &str = "__args";
+
+/// Message template of events whose text was formatted before ttlog saw it
+/// (`log` and `tracing` records). The text travels as the single [`ARGS_KEY`]
+/// argument in the event's KV payload, so distinct messages do not grow the
+/// interner.
+pub const FORMATTED_TEMPLATE: &str = "{}";
+
+/// Separate the captured format arguments from the user supplied KV fields.
+///
+/// Returns the KV object without [`ARGS_KEY`] and the positional arguments in
+/// call order. Non-object values are returned untouched with no arguments.
+pub fn split_args(kv: Value) -> (Value, Vec<Value>) {
+  match kv {
+    Value::Object(mut map) => {
+      let args = match map.remove(ARGS_KEY) {
+        Some(Value::Array(args)) => args,
+        Some(other) => vec![other],
+        None => Vec::new(),
+      };
+      (Value::Object(map), args)
+    },
+    other => (other, Vec::new()),
+  }
+}
+
+/// Escape literal braces so that an already formatted message survives
+/// [`render`] unchanged. Used for messages that do not originate from a
+/// ttlog macro template (e.g. `tracing` or `log` records).
+pub fn escape(message: &str) -> Cow<'_, str> {
+  if !message.contains(['{', '}']) {
+    return Cow::Borrowed(message);
+  }
+
+  Cow::Owned(message.replace('{', "{{").replace('}', "}}"))
+}
+
+/// Render a message template with `format_args!`-like semantics.
+///
+/// - `{}` consumes the next positional argument, `{N}` addresses one by index.
+/// - `{name}` resolves against the event's KV fields.
+/// - `{{` and `}}` are literal braces.
+/// - Fill, alignment, width, precision, `?`, `x`, `X`, `b`, `o` and `e` specs
+///   are honoured on the captured JSON values; precision is ignored for
+///   integers. The macros reject widths and precisions taken from arguments
+///   (`.*`, `N$`, `name$`).
+///
+/// Placeholders that cannot be resolved are kept verbatim so that a malformed
+/// or foreign message never loses information. Templates without braces are
+/// returned borrowed.
+pub fn render<'a>(template: &'a str, args: &[Value], kv: &Value) -> Cow<'a, str> {
+  if !template.contains(['{', '}']) {
+    return Cow::Borrowed(template);
+  }
+
+  let mut out = String::with_capacity(template.len() + args.len() * 8);
+  let mut next_positional = 0usize;
+  let mut rest = template;
+
+  while let Some(idx) = rest.find(['{', '}']) {
+    out.push_str(&rest[..idx]);
+    let tail = &rest[idx..];
+
+    if tail.starts_with("{{") {
+      out.push('{');
+      rest = &tail[2..];
+      continue;
+    }
+
+    if tail.starts_with("}}") {
+      out.push('}');
+      rest = &tail[2..];
+      continue;
+    }
+
+    if tail.starts_with('}') {
+      out.push('}');
+      rest = &tail[1..];
+      continue;
+    }
+
+    let Some(close) = tail.find('}') else {
+      out.push_str(tail);
+      rest = "";
+      break;
+    };
+
+    let inner = &tail[1..close];
+    let (arg, spec) = inner.split_once(':').unwrap_or((inner, ""));
+
+    let value = if arg.is_empty() {
+      let value = args.get(next_positional);
+      next_positional += 1;
+      value
+    } else if let Ok(index) = arg.parse::<usize>() {
+      args.get(index)
+    } else {
+      kv.get(arg)
+    };
+
+    match value {
+      Some(value) => write_value(&mut out, value, &FormatSpec::parse(spec)),
+      None => out.push_str(&tail[..=close]),
+    }
+
+    rest = &tail[close + 1..];
+  }
+
+  out.push_str(rest);
+  Cow::Owned(out)
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct FormatSpec {
+  fill: Option<char>,
+  align: Option<char>,
+  sign_plus: bool,
+  zero_pad: bool,
+  width: Option<usize>,
+  precision: Option<usize>,
+  kind: Option<char>,
+}
+
+impl FormatSpec {
+  fn parse(spec: &str) -> Self {
+    let mut out = FormatSpec::default();
+    let chars: Vec<char> = spec.chars().collect();
+    let mut i = 0;
+
+    if chars.len() >= 2 && matches!(chars[1], '<' | '^' | '>') {
+      out.fill = Some(chars[0]);
+      out.align = Some(chars[1]);
+      i = 2;
+    } else if !chars.is_empty() && matches!(chars[0], '<' | '^' | '>') {
+      out.align = Some(chars[0]);
+      i = 1;
+    }
+
+    if chars.get(i) == Some(&'+') {
+      out.sign_plus = true;
+      i += 1;
+    }
+
+    if chars.get(i) == Some(&'#') {
+      i += 1;
+    }
+
+    if chars.get(i) == Some(&'0') {
+      out.zero_pad = true;
+      i += 1;
+    }
+
+    let width_start = i;
+    while chars.get(i).is_some_and(|c| c.is_ascii_digit()) {
+      i += 1;
+    }
+    if i > width_start {
+      out.width = chars[width_start..i]
+        .iter()
+        .collect::<String>()
+        .parse()
+        .ok();
+    }
+
+    if chars.get(i) == Some(&'.') {
+      i += 1;
+      let precision_start = i;
+      while chars.get(i).is_some_and(|c| c.is_ascii_digit()) {
+        i += 1;
+      }
+      out.precision = chars[precision_start..i]
+        .iter()
+        .collect::<String>()
+        .parse()
+        .ok();
+    }
+
+    out.kind = chars.get(i).copied();
+    out
+  }
+}
+
+fn write_value(out: &mut String, value: &Value, spec: &FormatSpec) {
+  let body = match (value, spec.kind) {
+    (Value::String(s), Some('?')) => format!("{:?}", s),
+    (Value::String(s), _) => match spec.precision {
+      Some(precision) => s.chars().take(precision).collect(),
+      None => s.clone(),
+    },
+    (Value::Number(n), kind) => format_number(n, spec, kind),
+    (Value::Bool(b), _) => b.to_string(),
+    (Value::Null, _) => "null".to_string(),
+    (other, _) => other.to_string(),
+  };
+
+  pad(out, &body, spec, value.is_number());
+}
+
+fn format_number(n: &serde_json::Number, spec: &FormatSpec, kind: Option<char>) -> String {
+  let integer = n.as_i64().map(i128::from).or(n.as_u64().map(i128::from));
+
+  let body = match (kind, integer) {
+    (Some('x'), Some(i)) => format!("{:x}", i),
+    (Some('X'), Some(i)) => format!("{:X}", i),
+    (Some('b'), Some(i)) => format!("{:b}", i),
+    (Some('o'), Some(i)) => format!("{:o}", i),
+    (Some('e'), _) => format!("{:e}", n.as_f64().unwrap_or_default()),
+    // Precision does not apply to integers, as with `format!`.
+    (_, Some(i)) => i.to_string(),
+    (_, None) => {
+      let f = n.as_f64().unwrap_or_default();
+      match spec.precision {
+        Some(precision) => format!("{:.*}", precision, f),
+        None => n.to_string(),
+      }
+    },
+  };
+
+  if spec.sign_plus && !body.starts_with('-') {
+    format!("+{}", body)
+  } else {
+    body
+  }
+}
+
+fn pad(out: &mut String, body: &str, spec: &FormatSpec, numeric: bool) {
+  let len = body.chars().count();
+  let width = spec.width.unwrap_or(0);
+
+  if len >= width {
+    out.push_str(body);
+    return;
+  }
+
+  let padding = width - len;
+
+  if spec.zero_pad && numeric && spec.align.is_none() {
+    let (sign, digits) = match body.strip_prefix(['-', '+']) {
+      Some(digits) => (&body[..1], digits),
+      None => ("", body),
+    };
+    let _ = write!(out, "{}{}{}", sign, "0".repeat(padding), digits);
+    return;
+  }
+
+  let fill = spec.fill.unwrap_or(' ');
+  let default_align = if numeric { '>' } else { '<' };
+  let (left, right) = match spec.align.unwrap_or(default_align) {
+    '>' => (padding, 0),
+    '^' => (padding / 2, padding - padding / 2),
+    _ => (0, padding),
+  };
+
+  out.extend(std::iter::repeat_n(fill, left));
+  out.push_str(body);
+  out.extend(std::iter::repeat_n(fill, right));
+}
@@ -63,42 +63,35 @@ mod __test__ {
   }
 
   #[test]
-  fn encode_i64_as_string() {
+  fn encode_i64_as_number() {
     let val: i64 = 42;
     let t = KvEncoder::encode(&val).unwrap();
     let raw = std::str::from_utf8(t.as_slice()).unwrap();
-    // IntOrSer serializes i64 as a string
-    assert_eq!(raw, "\"42\"");
+    assert_eq!(raw, "42");
   }
 
   #[test]
-  fn encode_u64_as_string() {
-    let val: u64 = 999;
+  fn encode_u64_as_number() {
+    let val: u64 = u64::MAX;
     let t = KvEncoder::encode(&val).unwrap();
     let raw = std::str::from_utf8(t.as_slice()).unwrap();
-    assert_eq!(raw, "\"999\"");
+    assert_eq!(raw, "18446744073709551615");
   }
 
   #[test]
-  fn encode_f64_as_string() {
+  fn encode_f64_as_number() {
     let val: f64 = 3.14;
     let t = KvEncoder::encode(&val).unwrap();
     let raw = std::str::from_utf8(t.as_slice()).unwrap();
-    // ryu formats floats, wrapped in quotes
-    assert!(raw.starts_with('"'));
-    assert!(raw.ends_with('"'));
-    let inner: f64 = raw.trim_matches('"').parse().unwrap();
-    assert!((inner - 3.14).abs() < 1e-10);
+    assert_eq!(raw, "3.14");
   }
 
   #[test]
-  fn encode_f32_as_string() {
+  fn encode_f32_as_number() {
     let val: f32 = 2.5;
     let t = KvEncoder::encode(&val).unwrap();
     let raw = std::str::from_utf8(t.as_slice()).unwrap();
-    assert!(raw.starts_with('"'));
-    let inner: f32 = raw.trim_matches('"').parse().unwrap();
-    assert!((inner - 2.5).abs() < 1e-5);
+    assert_eq!(raw, "2.5");
   }
 
   #[test]
@@ -207,8 +200,7 @@ mod __test__ {
   // ── IntOrSer ───────────────────────────────────────────────────────
 
   #[test]
-  fn int_or_ser_i32_uses_fallback() {
-    // i32 is NOT i64, so it should use normal serialize (as number, not string)
+  fn int_or_ser_i32_is_a_number() {
     let val: i32 = 7;
     let json = serde_json::to_string(&IntOrSer(&val)).unwrap();
     assert_eq!(json, "7");
@@ -218,7 +210,7 @@ mod __test__ {
   fn int_or_ser_negative_i64() {
     let val: i64 = -100;
     let json = serde_json::to_string(&IntOrSer(&val)).unwrap();
-    assert_eq!(json, "\"-100\"");
+    assert_eq!(json, "-100");
   }
 
   #[test]
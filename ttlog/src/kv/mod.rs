@@ -47,20 +47,20 @@ impl KvEncoder {
   /// Encode any serializable value into a KvTransformer
   pub fn encode<T>(value: &T) -> Result<KvTransformer, serde_json::Error>
   where
-    T: Serialize + 'static,
+    T: Serialize + ?Sized,
   {
     let mut transformer = KvTransformer::with_capacity(128);
-    serde_json::to_writer(&mut transformer, &IntOrSer(value))?;
+    serde_json::to_writer(&mut transformer, value)?;
     Ok(transformer)
   }
 
   /// Encode to pretty JSON
   pub fn encode_pretty<T>(value: &T) -> Result<KvTransformer, serde_json::Error>
   where
-    T: Serialize + 'static,
+    T: Serialize + ?Sized,
   {
     let mut transformer = KvTransformer::with_capacity(128);
-    serde_json::to_writer_pretty(&mut transformer, &IntOrSer(value))?;
+    serde_json::to_writer_pretty(&mut transformer, value)?;
     Ok(transformer)
   }
 }
@@ -117,41 +117,20 @@ impl KvDecoder {
   }
 }
 
-pub struct IntOrSer<'a, T>(pub &'a T);
+/// Serializes the wrapped value unchanged.
+///
+/// Earlier versions wrote `i64`, `u64`, `f32` and `f64` as strings; KVs in
+/// snapshots from those versions can be read back with [`IntOrDe`].
+pub struct IntOrSer<'a, T: ?Sized>(pub &'a T);
 
 impl<'a, T> Serialize for IntOrSer<'a, T>
 where
-  T: Serialize + 'static,
+  T: Serialize + ?Sized,
 {
   fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
   where
     S: serde::Serializer,
   {
-    // handle i64
-    if let Some(i) = (self.0 as &dyn std::any::Any).downcast_ref::<i64>() {
-      let mut buf = itoa::Buffer::new();
-      return serializer.serialize_str(buf.format(*i));
-    }
-
-    // handle u64
-    if let Some(u) = (self.0 as &dyn std::any::Any).downcast_ref::<u64>() {
-      let mut buf = itoa::Buffer::new();
-      return serializer.serialize_str(buf.format(*u));
-    }
-
-    // handle f64
-    if let Some(f) = (self.0 as &dyn std::any::Any).downcast_ref::<f64>() {
-      let mut buf = ryu::Buffer::new();
-      return serializer.serialize_str(buf.format(*f));
-    }
-
-    // handle f32
-    if let Some(f) = (self.0 as &dyn std::any::Any).downcast_ref::<f32>() {
-      let mut buf = ryu::Buffer::new();
-      return serializer.serialize_str(buf.format(*f));
-    }
-
-    // fallback
     self.0.serialize(serializer)
   }
 }
@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod __test__ {
+
+  use smallvec::SmallVec;
+  use std::sync::Arc;
+  use std::thread;
+
+  use crate::kv_arena::{KvArena, ARENA_BIT};
+
+  fn bytes(value: &str) -> SmallVec<[u8; 128]> {
+    SmallVec::from_slice(value.as_bytes())
+  }
+
+  #[test]
+  fn stored_payloads_resolve() {
+    let arena = KvArena::new(4);
+
+    let first = arena.store(bytes("a"));
+    let second = arena.store(bytes("a"));
+
+    assert_ne!(first, second);
+    assert_ne!(first & ARENA_BIT, 0);
+    assert_eq!(arena.get(first).unwrap().as_slice(), b"a");
+    assert_eq!(arena.get(second).unwrap().as_slice(), b"a");
+  }
+
+  #[test]
+  fn slot_count_rounds_up_to_power_of_two() {
+    assert_eq!(KvArena::new(5).capacity(), 8);
+    assert_eq!(KvArena::new(0).capacity(), 2);
+  }
+
+  #[test]
+  fn reused_slots_do_not_alias() {
+    let arena = KvArena::new(2);
+
+    let old = arena.store(bytes("old"));
+    arena.store(bytes("b"));
+    let new = arena.store(bytes("new"));
+
+    assert_eq!(old & 1, new & 1);
+    assert!(arena.get(old).is_none());
+    assert_eq!(arena.get(new).unwrap().as_slice(), b"new");
+  }
+
+  #[test]
+  fn pinned_payloads_are_not_overwritten() {
+    let arena = KvArena::new(4);
+
+    let pinned_id = arena.store(bytes("pinned"));
+    let pin = arena.pin(pinned_id).unwrap();
+    let stored: Vec<u32> = (0..100)
+      .map(|i| arena.store(bytes(&format!("request-{}", i))))
+      .collect();
+
+    // Stores that landed on the pinned slot dropped their payload.
+    assert_eq!(stored.iter().filter(|&&id| id == 0).count(), 25);
+    assert_eq!(arena.dropped(), 25);
+    assert_eq!(arena.get(pinned_id).unwrap().as_slice(), b"pinned");
+    assert_eq!(pin.as_slice(), b"pinned");
+
+    // A clone holds the slot too.
+    let clone = pin.clone();
+    drop(pin);
+    for i in 0..4 {
+      arena.store(bytes(&format!("again-{}", i)));
+    }
+    assert_eq!(arena.get(pinned_id).unwrap().as_slice(), b"pinned");
+
+    drop(clone);
+    for i in 0..4 {
+      arena.store(bytes(&format!("later-{}", i)));
+    }
+    assert!(arena.get(pinned_id).is_none());
+  }
+
+  #[test]
+  fn get_does_not_pin() {
+    let arena = KvArena::new(2);
+
+    let id = arena.store(bytes("a"));
+    let payload = arena.get(id).unwrap();
+    arena.store(bytes("b"));
+    assert_ne!(arena.store(bytes("c")), 0);
+
+    assert!(arena.get(id).is_none());
+    assert_eq!(payload.as_slice(), b"a");
+    assert_eq!(arena.dropped(), 0);
+  }
+
+  #[test]
+  fn memory_stays_bounded() {
+    let arena = KvArena::new(8);
+    for i in 0..10_000 {
+      arena.store(bytes(&format!("request-{}", i)));
+    }
+
+    assert_eq!(arena.len(), 8);
+  }
+
+  #[test]
+  fn ids_without_arena_bit_are_unknown() {
+    let arena = KvArena::new(4);
+    arena.store(bytes("a"));
+
+    assert!(arena.get(0).is_none());
+    assert!(arena.get(1).is_none());
+  }
+
+  #[test]
+  fn concurrent_stores_keep_their_payloads() {
+    let arena = Arc::new(KvArena::new(1024));
+
+    let handles: Vec<_> = (0..4)
+      .map(|t| {
+        let arena = Arc::clone(&arena);
+        thread::spawn(move || {
+          (0..100)
+            .map(|i| {
+              let value = format!("{}-{}", t, i);
+              (arena.store(bytes(&value)), value)
+            })
+            .collect::<Vec<_>>()
+        })
+      })
+      .collect();
+
+    for handle in handles {
+      for (id, value) in handle.join().unwrap() {
+        assert_eq!(arena.get(id).unwrap().as_slice(), value.as_bytes());
+      }
+    }
+  }
+}
@@ -0,0 +1,251 @@
+mod __test__;
+
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use smallvec::SmallVec;
+
+type KvBytes = SmallVec<[u8; 128]>;
+
+/// Set on every arena ID. Interned KV IDs stay below it, so one `kv_id`
+/// field can hold either.
+pub const ARENA_BIT: u32 = 1 << 31;
+
+/// Default slot count for interners created without a ring buffer in mind.
+pub const DEFAULT_SLOTS: usize = 4096;
+
+/// Set in a slot state while its payload is being replaced.
+const WRITING: u64 = 1 << 32;
+/// One pin in a slot state; the pin count takes the bits above `WRITING`.
+const PIN: u64 = 1 << 33;
+
+/// One stored payload. The low 32 bits of `state` are the ID it was stored
+/// under (0 while empty or being written), above them the `WRITING` bit and
+/// the pin count.
+struct Slot {
+  state: AtomicU64,
+  bytes: UnsafeCell<Option<Arc<KvBytes>>>,
+}
+
+// SAFETY: `bytes` is only written under `WRITING` with no pins and only read
+// under a pin, which keep writers and readers apart.
+unsafe impl Sync for Slot {}
+
+/// Bounded store for per-event KV payloads.
+///
+/// Payloads take slots round-robin, each replacing the one stored `slots`
+/// payloads earlier, so memory stays bounded however many distinct values are
+/// logged. IDs carry the lap their slot was written in; an ID whose slot was
+/// reused resolves to `None` instead of another event's payload.
+///
+/// [`Self::pin`] keeps a payload from being replaced while the returned
+/// [`KvPin`] lives. `Trace` pins the payload of every event waiting for a
+/// listener (see [`crate::trace::EventBroadcast`]), so listeners resolve the
+/// KV of every event they receive, however far behind they are. A store
+/// whose slot is pinned does not look for another one: it drops its payload,
+/// the event goes without KVs and [`Self::dropped`] counts it. `Trace` sizes
+/// the arena for its ring buffer plus all of its listener queues, so that
+/// only happens to slots pinned for longer than a lap of the arena.
+///
+/// Every operation is a few atomic instructions on one slot; nothing locks
+/// or waits.
+pub struct KvArena {
+  slots: Arc<[Slot]>,
+  slot_bits: u32,
+  next: AtomicU32,
+  dropped: AtomicU64,
+}
+
+impl KvArena {
+  /// Arena with room for `slots` payloads, rounded up to a power of two.
+  pub fn new(slots: usize) -> Self {
+    let slots = slots.clamp(2, 1 << 24).next_power_of_two();
+
+    Self {
+      slots: (0..slots)
+        .map(|_| Slot {
+          state: AtomicU64::new(0),
+          bytes: UnsafeCell::new(None),
+        })
+        .collect(),
+      slot_bits: slots.trailing_zeros(),
+      next: AtomicU32::new(0),
+      dropped: AtomicU64::new(0),
+    }
+  }
+
+  /// Store `bytes` and return its ID, which always has [`ARENA_BIT`] set, or
+  /// 0 if the slot it would take is pinned or still being written.
+  pub fn store(&self, bytes: KvBytes) -> u32 {
+    let seq = self.next.fetch_add(1, Ordering::Relaxed);
+    let index = seq & self.index_mask();
+    let lap = (seq >> self.slot_bits) & self.lap_mask();
+    let id = ARENA_BIT | (lap << self.slot_bits) | index;
+
+    let slot = &self.slots[index as usize];
+    let state = slot.state.load(Ordering::Relaxed);
+    if state >> 32 != 0
+      || slot
+        .state
+        .compare_exchange(state, WRITING, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+      self.dropped.fetch_add(1, Ordering::Relaxed);
+      return 0;
+    }
+
+    // SAFETY: `WRITING` without pins keeps every other thread out of `bytes`.
+    unsafe { *slot.bytes.get() = Some(Arc::new(bytes)) };
+    slot.state.store(u64::from(id), Ordering::Release);
+    id
+  }
+
+  /// The payload stored under `id`, unless its slot has been reused.
+  pub fn get(&self, id: u32) -> Option<Arc<KvBytes>> {
+    let (index, bytes) = self.acquire(id)?;
+    unpin(&self.slots[index]);
+    Some(bytes)
+  }
+
+  /// Like [`Self::get`], also keeping the slot from being reused until the
+  /// returned pin is dropped.
+  pub fn pin(&self, id: u32) -> Option<KvPin> {
+    let (index, bytes) = self.acquire(id)?;
+    Some(KvPin {
+      slots: Arc::clone(&self.slots),
+      index,
+      bytes,
+    })
+  }
+
+  /// Pin the slot holding `id` and clone its payload.
+  fn acquire(&self, id: u32) -> Option<(usize, Arc<KvBytes>)> {
+    if id & ARENA_BIT == 0 {
+      return None;
+    }
+
+    let index = (id & self.index_mask()) as usize;
+    let slot = &self.slots[index];
+    let mut state = slot.state.load(Ordering::Relaxed);
+    loop {
+      if state as u32 != id {
+        return None;
+      }
+      match slot.state.compare_exchange_weak(
+        state,
+        state + PIN,
+        Ordering::Acquire,
+        Ordering::Relaxed,
+      ) {
+        Ok(_) => break,
+        Err(current) => state = current,
+      }
+    }
+
+    // SAFETY: the pin keeps writers out, and the state showed `id`, so the
+    // payload stored under it is in place.
+    let bytes = unsafe { (*slot.bytes.get()).clone() };
+    match bytes {
+      Some(bytes) => Some((index, bytes)),
+      None => {
+        unpin(slot);
+        None
+      },
+    }
+  }
+
+  /// Payloads dropped because their slot was pinned, since the arena was
+  /// created.
+  pub fn dropped(&self) -> u64 {
+    self.dropped.load(Ordering::Relaxed)
+  }
+
+  /// Number of payloads the arena holds at once.
+  pub fn capacity(&self) -> usize {
+    self.slots.len()
+  }
+
+  /// Number of occupied slots.
+  pub fn len(&self) -> usize {
+    self
+      .slots
+      .iter()
+      .filter(|slot| slot.state.load(Ordering::Relaxed) as u32 != 0)
+      .count()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  fn index_mask(&self) -> u32 {
+    (1 << self.slot_bits) - 1
+  }
+
+  fn lap_mask(&self) -> u32 {
+    (1 << (31 - self.slot_bits)) - 1
+  }
+}
+
+fn unpin(slot: &Slot) {
+  slot.state.fetch_sub(PIN, Ordering::Release);
+}
+
+impl Default for KvArena {
+  fn default() -> Self {
+    Self::new(DEFAULT_SLOTS)
+  }
+}
+
+impl fmt::Debug for KvArena {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("KvArena")
+      .field("capacity", &self.capacity())
+      .field("dropped", &self.dropped())
+      .finish_non_exhaustive()
+  }
+}
+
+/// A payload whose arena slot is not reused while this lives, see
+/// [`KvArena::pin`].
+pub struct KvPin {
+  slots: Arc<[Slot]>,
+  index: usize,
+  bytes: Arc<KvBytes>,
+}
+
+impl Deref for KvPin {
+  type Target = KvBytes;
+
+  fn deref(&self) -> &KvBytes {
+    &self.bytes
+  }
+}
+
+impl Clone for KvPin {
+  fn clone(&self) -> Self {
+    self.slots[self.index]
+      .state
+      .fetch_add(PIN, Ordering::Relaxed);
+    Self {
+      slots: Arc::clone(&self.slots),
+      index: self.index,
+      bytes: Arc::clone(&self.bytes),
+    }
+  }
+}
+
+impl Drop for KvPin {
+  fn drop(&mut self) {
+    unpin(&self.slots[self.index]);
+  }
+}
+
+impl fmt::Debug for KvPin {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("KvPin").field(&self.bytes).finish()
+  }
+}
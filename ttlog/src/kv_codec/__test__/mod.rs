@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod __test__ {
+
+  use serde_json::json;
+  use std::collections::BTreeMap;
+
+  use crate::event::FieldValue;
+  use crate::format::ARGS_KEY;
+  use crate::kv_codec::{decode, FieldEncoder, FieldRef, Fields, MAGIC};
+  use crate::string_interner::StringInterner;
+
+  #[test]
+  fn scalars_keep_their_type() {
+    let interner = StringInterner::new();
+    let mut encoder = FieldEncoder::new();
+    encoder
+      .field(interner.intern_key("count"), &42u64)
+      .field(interner.intern_key("delta"), &-7i32)
+      .field(interner.intern_key("ratio"), &0.5f64)
+      .field(interner.intern_key("ok"), &true)
+      .field(interner.intern_key("name"), "alice")
+      .field(interner.intern_key("missing"), &None::<u8>);
+    let bytes = encoder.finish();
+
+    let fields: Vec<_> = Fields::new(&bytes)
+      .unwrap()
+      .map(|(_, value)| value)
+      .collect();
+    assert_eq!(
+      fields,
+      [
+        FieldRef::Value(FieldValue::U64(42)),
+        FieldRef::Value(FieldValue::I32(-7)),
+        FieldRef::Value(FieldValue::F64(0.5)),
+        FieldRef::Value(FieldValue::Bool(true)),
+        FieldRef::Str("alice"),
+        FieldRef::Null,
+      ]
+    );
+
+    assert_eq!(
+      decode(&bytes, &interner).unwrap(),
+      json!({
+        "count": 42,
+        "delta": -7,
+        "ratio": 0.5,
+        "ok": true,
+        "name": "alice",
+        "missing": null,
+      })
+    );
+  }
+
+  #[test]
+  fn compound_values_fall_back_to_json() {
+    let interner = StringInterner::new();
+    let map: BTreeMap<&str, Vec<u8>> = [("a", vec![1, 2])].into_iter().collect();
+    let mut encoder = FieldEncoder::new();
+    encoder.field(interner.intern_key("map"), &map);
+
+    let bytes = encoder.finish();
+    assert_eq!(
+      decode(&bytes, &interner).unwrap(),
+      json!({ "map": { "a": [1, 2] } })
+    );
+  }
+
+  #[test]
+  fn repeated_args_key_collects_positional_arguments() {
+    let interner = StringInterner::new();
+    let args = interner.intern_key(ARGS_KEY);
+    let mut encoder = FieldEncoder::new();
+    encoder
+      .field(interner.intern_key("user"), "bob")
+      .field(args, &1u8)
+      .field(args, &[1, 2]);
+
+    assert_eq!(
+      decode(&encoder.finish(), &interner).unwrap(),
+      json!({ "user": "bob", ARGS_KEY: [1, [1, 2]] })
+    );
+  }
+
+  #[test]
+  fn typed_values_and_string_ids_round_trip() {
+    let interner = StringInterner::new();
+    let mut encoder = FieldEncoder::new();
+    encoder
+      .value(interner.intern_key("port"), FieldValue::U16(8080))
+      .value(
+        interner.intern_key("env"),
+        FieldValue::StringId(interner.intern_key("prod")),
+      );
+
+    assert_eq!(
+      decode(&encoder.finish(), &interner).unwrap(),
+      json!({ "port": 8080, "env": "prod" })
+    );
+  }
+
+  #[test]
+  fn append_keeps_the_fields_of_both_encoders() {
+    let interner = StringInterner::new();
+    let mut span = FieldEncoder::new();
+    span.field(interner.intern_key("user"), "ann");
+    let mut event = FieldEncoder::new();
+    assert!(event.is_empty());
+    event
+      .append(&span)
+      .field(interner.intern_key("status"), &200u16);
+
+    assert_eq!(
+      decode(&event.finish(), &interner).unwrap(),
+      json!({ "user": "ann", "status": 200 })
+    );
+  }
+
+  #[test]
+  fn json_payloads_are_rejected() {
+    let interner = StringInterner::new();
+    assert!(decode(br#"{"a":1}"#, &interner).is_none());
+    assert!(Fields::new(br#"{"a":1}"#).is_none());
+  }
+
+  #[test]
+  fn truncated_payload_stops_iteration() {
+    let interner = StringInterner::new();
+    let mut encoder = FieldEncoder::new();
+    encoder
+      .field(interner.intern_key("a"), &1u64)
+      .field(interner.intern_key("b"), "long enough");
+    let bytes = encoder.finish();
+
+    let truncated = &bytes[..bytes.len() - 3];
+    assert_eq!(Fields::new(truncated).unwrap().count(), 1);
+    assert_eq!(Fields::new(&[MAGIC]).unwrap().count(), 0);
+  }
+}
@@ -0,0 +1,491 @@
+mod __test__;
+
+use std::fmt;
+
+use serde::ser::{self, Impossible, Serialize};
+use serde_json::{Map, Value};
+use smallvec::SmallVec;
+
+use crate::event::FieldValue;
+use crate::format::ARGS_KEY;
+use crate::string_interner::StringInterner;
+
+type KvBytes = SmallVec<[u8; 128]>;
+
+/// First byte of a binary field payload. Every KV producer writes this
+/// format; [`decode`] rejects payloads that do not start with it.
+pub const MAGIC: u8 = 0xB1;
+
+// Value tags. Numbers are little endian, `STR` and `JSON` carry a `u32`
+// length followed by that many bytes.
+const BOOL: u8 = 0;
+const U8: u8 = 1;
+const U16: u8 = 2;
+const U32: u8 = 3;
+const U64: u8 = 4;
+const I8: u8 = 5;
+const I16: u8 = 6;
+const I32: u8 = 7;
+const I64: u8 = 8;
+const F32: u8 = 9;
+const F64: u8 = 10;
+const STRING_ID: u8 = 11;
+const STR: u8 = 12;
+const JSON: u8 = 13;
+const NULL: u8 = 14;
+
+/// Writes KV fields as `MAGIC (key_id: u32, tag: u8, value)*`.
+///
+/// Keys are IDs from [`StringInterner::intern_key`]; values keep their type.
+/// Strings are stored inline so unique values do not grow the interner, and
+/// anything that is not a scalar falls back to its JSON encoding.
+///
+/// Numbers come out of [`decode`] as JSON numbers with every digit intact:
+/// integers up to 64 bits as written, floats in their shortest round-trip
+/// form (non-finite floats as `null`). 128-bit integers are narrowed to 64
+/// bits when they fit and otherwise kept as decimal strings, since JSON
+/// readers generally cannot hold them. Consumers in languages whose numbers
+/// are doubles should parse `u64`/`i64` fields beyond 2^53 as big integers.
+#[derive(Debug, Clone)]
+pub struct FieldEncoder {
+  buf: KvBytes,
+}
+
+impl FieldEncoder {
+  pub fn new() -> Self {
+    let mut buf = KvBytes::new();
+    buf.push(MAGIC);
+    Self { buf }
+  }
+
+  /// Append `value` under `key_id`. Repeating [`ARGS_KEY`] appends another
+  /// positional format argument.
+  pub fn field<T: Serialize + ?Sized>(&mut self, key_id: u32, value: &T) -> &mut Self {
+    self.buf.extend_from_slice(&key_id.to_le_bytes());
+
+    if value.serialize(ScalarWriter(&mut self.buf)).is_err() {
+      match serde_json::to_vec(value) {
+        Ok(json) => write_bytes(&mut self.buf, JSON, &json),
+        Err(_) => self.buf.push(NULL),
+      }
+    }
+    self
+  }
+
+  /// Append an already typed value.
+  pub fn value(&mut self, key_id: u32, value: FieldValue) -> &mut Self {
+    self.buf.extend_from_slice(&key_id.to_le_bytes());
+    match value {
+      FieldValue::Bool(v) => self.buf.extend_from_slice(&[BOOL, v as u8]),
+      FieldValue::U8(v) => self.buf.extend_from_slice(&[U8, v]),
+      FieldValue::U16(v) => write_tagged(&mut self.buf, U16, &v.to_le_bytes()),
+      FieldValue::U32(v) => write_tagged(&mut self.buf, U32, &v.to_le_bytes()),
+      FieldValue::U64(v) => write_tagged(&mut self.buf, U64, &v.to_le_bytes()),
+      FieldValue::I8(v) => write_tagged(&mut self.buf, I8, &v.to_le_bytes()),
+      FieldValue::I16(v) => write_tagged(&mut self.buf, I16, &v.to_le_bytes()),
+      FieldValue::I32(v) => write_tagged(&mut self.buf, I32, &v.to_le_bytes()),
+      FieldValue::I64(v) => write_tagged(&mut self.buf, I64, &v.to_le_bytes()),
+      FieldValue::F32(v) => write_tagged(&mut self.buf, F32, &v.to_le_bytes()),
+      FieldValue::F64(v) => write_tagged(&mut self.buf, F64, &v.to_le_bytes()),
+      FieldValue::StringId(v) => write_tagged(&mut self.buf, STRING_ID, &v.to_le_bytes()),
+    }
+    self
+  }
+
+  /// Append every field of `other`, whose keys come from the same interner.
+  pub fn append(&mut self, other: &FieldEncoder) -> &mut Self {
+    self.buf.extend_from_slice(&other.buf[1..]);
+    self
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.buf.len() == 1
+  }
+
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.buf
+  }
+
+  pub fn finish(self) -> KvBytes {
+    self.buf
+  }
+}
+
+impl Default for FieldEncoder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// A log macro argument or KV value, encoded by whichever of
+/// [`EncodeSerialize`] and [`EncodeDisplay`] applies first when called as
+/// `(&&MacroArg(&value)).encode_field(..)`: `Serialize` values keep their
+/// type, values that only implement `Display` are stored as their
+/// `to_string()`.
+#[doc(hidden)]
+pub struct MacroArg<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait EncodeSerialize {
+  fn encode_field(&self, kv: &mut FieldEncoder, key_id: u32);
+}
+
+impl<T: Serialize + ?Sized> EncodeSerialize for &MacroArg<'_, T> {
+  fn encode_field(&self, kv: &mut FieldEncoder, key_id: u32) {
+    kv.field(key_id, self.0);
+  }
+}
+
+#[doc(hidden)]
+pub trait EncodeDisplay {
+  fn encode_field(&self, kv: &mut FieldEncoder, key_id: u32);
+}
+
+impl<T: fmt::Display + ?Sized> EncodeDisplay for MacroArg<'_, T> {
+  fn encode_field(&self, kv: &mut FieldEncoder, key_id: u32) {
+    kv.field(key_id, self.0.to_string().as_str());
+  }
+}
+
+fn write_tagged(buf: &mut KvBytes, tag: u8, bytes: &[u8]) {
+  buf.push(tag);
+  buf.extend_from_slice(bytes);
+}
+
+fn write_bytes(buf: &mut KvBytes, tag: u8, bytes: &[u8]) {
+  buf.push(tag);
+  buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+  buf.extend_from_slice(bytes);
+}
+
+/// A decoded value borrowing from the payload.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldRef<'a> {
+  Value(FieldValue),
+  Str(&'a str),
+  /// JSON encoding of a non-scalar value.
+  Json(&'a [u8]),
+  Null,
+}
+
+impl FieldRef<'_> {
+  /// JSON form of the value. [`FieldValue::StringId`] is looked up as an
+  /// interned key.
+  pub fn to_json(&self, interner: &StringInterner) -> Value {
+    match self {
+      FieldRef::Value(FieldValue::StringId(id)) => interner
+        .get_key(*id)
+        .map(|s| Value::String(s.to_string()))
+        .unwrap_or(Value::Null),
+      FieldRef::Value(value) => value.to_json_value(),
+      FieldRef::Str(s) => Value::String((*s).to_string()),
+      FieldRef::Json(bytes) => serde_json::from_slice(bytes).unwrap_or(Value::Null),
+      FieldRef::Null => Value::Null,
+    }
+  }
+}
+
+/// Iterator over the `(key_id, value)` pairs of a binary payload. Stops at
+/// the first malformed field.
+#[derive(Debug, Clone)]
+pub struct Fields<'a> {
+  rest: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+  /// Fields of `bytes`, or `None` if it is not a binary payload.
+  pub fn new(bytes: &'a [u8]) -> Option<Self> {
+    match bytes.split_first() {
+      Some((&MAGIC, rest)) => Some(Self { rest }),
+      _ => None,
+    }
+  }
+
+  fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+    let (head, rest) = self.rest.split_first_chunk::<N>()?;
+    self.rest = rest;
+    Some(*head)
+  }
+
+  fn take_slice(&mut self) -> Option<&'a [u8]> {
+    let len = u32::from_le_bytes(self.take()?) as usize;
+    if self.rest.len() < len {
+      return None;
+    }
+    let (head, rest) = self.rest.split_at(len);
+    self.rest = rest;
+    Some(head)
+  }
+
+  fn next_field(&mut self) -> Option<(u32, FieldRef<'a>)> {
+    let key_id = u32::from_le_bytes(self.take()?);
+    let [tag] = self.take()?;
+
+    let value = match tag {
+      BOOL => FieldRef::Value(FieldValue::Bool(self.take::<1>()?[0] != 0)),
+      U8 => FieldRef::Value(FieldValue::U8(self.take::<1>()?[0])),
+      U16 => FieldRef::Value(FieldValue::U16(u16::from_le_bytes(self.take()?))),
+      U32 => FieldRef::Value(FieldValue::U32(u32::from_le_bytes(self.take()?))),
+      U64 => FieldRef::Value(FieldValue::U64(u64::from_le_bytes(self.take()?))),
+      I8 => FieldRef::Value(FieldValue::I8(i8::from_le_bytes(self.take()?))),
+      I16 => FieldRef::Value(FieldValue::I16(i16::from_le_bytes(self.take()?))),
+      I32 => FieldRef::Value(FieldValue::I32(i32::from_le_bytes(self.take()?))),
+      I64 => FieldRef::Value(FieldValue::I64(i64::from_le_bytes(self.take()?))),
+      F32 => FieldRef::Value(FieldValue::F32(f32::from_le_bytes(self.take()?))),
+      F64 => FieldRef::Value(FieldValue::F64(f64::from_le_bytes(self.take()?))),
+      STRING_ID => FieldRef::Value(FieldValue::StringId(u32::from_le_bytes(self.take()?))),
+      STR => FieldRef::Str(std::str::from_utf8(self.take_slice()?).ok()?),
+      JSON => FieldRef::Json(self.take_slice()?),
+      NULL => FieldRef::Null,
+      _ => return None,
+    };
+
+    Some((key_id, value))
+  }
+}
+
+impl<'a> Iterator for Fields<'a> {
+  type Item = (u32, FieldRef<'a>);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let field = self.next_field();
+    if field.is_none() {
+      self.rest = &[];
+    }
+    field
+  }
+}
+
+/// Decode a binary KV payload into a JSON object. Values under [`ARGS_KEY`]
+/// are collected into an array in call order. Returns `None` for payloads
+/// not written by [`FieldEncoder`]; KVs of snapshots saved before it existed
+/// are already JSON in the snapshot file and never pass through here.
+pub fn decode(bytes: &[u8], interner: &StringInterner) -> Option<Value> {
+  let fields = Fields::new(bytes)?;
+
+  let mut map = Map::new();
+  let mut args = Vec::new();
+  for (key_id, value) in fields {
+    let key = interner.get_key(key_id)?;
+    let value = value.to_json(interner);
+    if &*key == ARGS_KEY {
+      args.push(value);
+    } else {
+      map.insert(key.to_string(), value);
+    }
+  }
+
+  if !args.is_empty() {
+    map.insert(ARGS_KEY.to_string(), Value::Array(args));
+  }
+  Some(Value::Object(map))
+}
+
+/// Returned by [`ScalarWriter`] for values that need the JSON fallback.
+#[derive(Debug)]
+struct NotScalar;
+
+impl fmt::Display for NotScalar {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("not a scalar value")
+  }
+}
+
+impl std::error::Error for NotScalar {}
+
+impl ser::Error for NotScalar {
+  fn custom<T: fmt::Display>(_msg: T) -> Self {
+    NotScalar
+  }
+}
+
+/// Serializer that writes scalars as a tag and value and rejects everything
+/// else without writing.
+struct ScalarWriter<'a>(&'a mut KvBytes);
+
+impl<'a> ser::Serializer for ScalarWriter<'a> {
+  type Ok = ();
+  type Error = NotScalar;
+  type SerializeSeq = Impossible<(), NotScalar>;
+  type SerializeTuple = Impossible<(), NotScalar>;
+  type SerializeTupleStruct = Impossible<(), NotScalar>;
+  type SerializeTupleVariant = Impossible<(), NotScalar>;
+  type SerializeMap = Impossible<(), NotScalar>;
+  type SerializeStruct = Impossible<(), NotScalar>;
+  type SerializeStructVariant = Impossible<(), NotScalar>;
+
+  fn serialize_bool(self, v: bool) -> Result<(), NotScalar> {
+    self.0.extend_from_slice(&[BOOL, v as u8]);
+    Ok(())
+  }
+
+  fn serialize_i8(self, v: i8) -> Result<(), NotScalar> {
+    write_tagged(self.0, I8, &v.to_le_bytes());
+    Ok(())
+  }
+
+  fn serialize_i16(self, v: i16) -> Result<(), NotScalar> {
+    write_tagged(self.0, I16, &v.to_le_bytes());
+    Ok(())
+  }
+
+  fn serialize_i32(self, v: i32) -> Result<(), NotScalar> {
+    write_tagged(self.0, I32, &v.to_le_bytes());
+    Ok(())
+  }
+
+  fn serialize_i64(self, v: i64) -> Result<(), NotScalar> {
+    write_tagged(self.0, I64, &v.to_le_bytes());
+    Ok(())
+  }
+
+  fn serialize_i128(self, v: i128) -> Result<(), NotScalar> {
+    match i64::try_from(v) {
+      Ok(v) => self.serialize_i64(v),
+      Err(_) => self.serialize_str(&v.to_string()),
+    }
+  }
+
+  fn serialize_u8(self, v: u8) -> Result<(), NotScalar> {
+    self.0.extend_from_slice(&[U8, v]);
+    Ok(())
+  }
+
+  fn serialize_u16(self, v: u16) -> Result<(), NotScalar> {
+    write_tagged(self.0, U16, &v.to_le_bytes());
+    Ok(())
+  }
+
+  fn serialize_u32(self, v: u32) -> Result<(), NotScalar> {
+    write_tagged(self.0, U32, &v.to_le_bytes());
+    Ok(())
+  }
+
+  fn serialize_u64(self, v: u64) -> Result<(), NotScalar> {
+    write_tagged(self.0, U64, &v.to_le_bytes());
+    Ok(())
+  }
+
+  fn serialize_u128(self, v: u128) -> Result<(), NotScalar> {
+    match u64::try_from(v) {
+      Ok(v) => self.serialize_u64(v),
+      Err(_) => self.serialize_str(&v.to_string()),
+    }
+  }
+
+  fn serialize_f32(self, v: f32) -> Result<(), NotScalar> {
+    write_tagged(self.0, F32, &v.to_le_bytes());
+    Ok(())
+  }
+
+  fn serialize_f64(self, v: f64) -> Result<(), NotScalar> {
+    write_tagged(self.0, F64, &v.to_le_bytes());
+    Ok(())
+  }
+
+  fn serialize_char(self, v: char) -> Result<(), NotScalar> {
+    self.serialize_str(v.encode_utf8(&mut [0; 4]))
+  }
+
+  fn serialize_str(self, v: &str) -> Result<(), NotScalar> {
+    write_bytes(self.0, STR, v.as_bytes());
+    Ok(())
+  }
+
+  fn serialize_bytes(self, _v: &[u8]) -> Result<(), NotScalar> {
+    Err(NotScalar)
+  }
+
+  fn serialize_none(self) -> Result<(), NotScalar> {
+    self.0.push(NULL);
+    Ok(())
+  }
+
+  fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), NotScalar> {
+    value.serialize(self)
+  }
+
+  fn serialize_unit(self) -> Result<(), NotScalar> {
+    self.0.push(NULL);
+    Ok(())
+  }
+
+  fn serialize_unit_struct(self, _name: &'static str) -> Result<(), NotScalar> {
+    self.serialize_unit()
+  }
+
+  fn serialize_unit_variant(
+    self,
+    _name: &'static str,
+    _index: u32,
+    variant: &'static str,
+  ) -> Result<(), NotScalar> {
+    self.serialize_str(variant)
+  }
+
+  fn serialize_newtype_struct<T: Serialize + ?Sized>(
+    self,
+    _name: &'static str,
+    value: &T,
+  ) -> Result<(), NotScalar> {
+    value.serialize(self)
+  }
+
+  fn serialize_newtype_variant<T: Serialize + ?Sized>(
+    self,
+    _name: &'static str,
+    _index: u32,
+    _variant: &'static str,
+    _value: &T,
+  ) -> Result<(), NotScalar> {
+    Err(NotScalar)
+  }
+
+  fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, NotScalar> {
+    Err(NotScalar)
+  }
+
+  fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, NotScalar> {
+    Err(NotScalar)
+  }
+
+  fn serialize_tuple_struct(
+    self,
+    _name: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeTupleStruct, NotScalar> {
+    Err(NotScalar)
+  }
+
+  fn serialize_tuple_variant(
+    self,
+    _name: &'static str,
+    _index: u32,
+    _variant: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeTupleVariant, NotScalar> {
+    Err(NotScalar)
+  }
+
+  fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, NotScalar> {
+    Err(NotScalar)
+  }
+
+  fn serialize_struct(
+    self,
+    _name: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeStruct, NotScalar> {
+    Err(NotScalar)
+  }
+
+  fn serialize_struct_variant(
+    self,
+    _name: &'static str,
+    _index: u32,
+    _variant: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeStructVariant, NotScalar> {
+    Err(NotScalar)
+  }
+}
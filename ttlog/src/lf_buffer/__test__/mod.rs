@@ -22,7 +22,7 @@ mod __test__ {
     let buffer = LockFreeRingBuffer::<i32>::new(3);
 
     // Push single item
-    assert_eq!(buffer.push(42), Ok(None));
+    assert_eq!(buffer.push(42), None);
     assert_eq!(buffer.len(), 1);
     assert!(!buffer.is_empty());
     assert!(!buffer.is_full());
@@ -41,9 +41,9 @@ mod __test__ {
     let buffer = LockFreeRingBuffer::<i32>::new(3);
 
     // Push multiple items
-    assert_eq!(buffer.push(1), Ok(None));
-    assert_eq!(buffer.push(2), Ok(None));
-    assert_eq!(buffer.push(3), Ok(None));
+    assert_eq!(buffer.push(1), None);
+    assert_eq!(buffer.push(2), None);
+    assert_eq!(buffer.push(3), None);
 
     assert_eq!(buffer.len(), 3);
     assert!(buffer.is_full());
@@ -83,11 +83,11 @@ mod __test__ {
     let buffer = LockFreeRingBuffer::<i32>::new(2);
 
     // Fill buffer
-    assert_eq!(buffer.push(1), Ok(None));
-    assert_eq!(buffer.push(2), Ok(None));
+    assert_eq!(buffer.push(1), None);
+    assert_eq!(buffer.push(2), None);
 
     // Push third item - should evict 1
-    assert_eq!(buffer.push(3), Ok(Some(1)));
+    assert_eq!(buffer.push(3), Some(1));
 
     // Check remaining items
     assert_eq!(buffer.pop(), Some(2));
@@ -119,6 +119,133 @@ mod __test__ {
     assert!(buffer.is_empty());
   }
 
+  #[test]
+  fn test_peek_snapshot_keeps_items() {
+    let buffer = LockFreeRingBuffer::<i32>::new(3);
+    for i in 1..=4 {
+      buffer.push_overwrite(i);
+    }
+
+    assert_eq!(buffer.peek_snapshot(), vec![2, 3, 4]);
+    assert_eq!(buffer.peek_snapshot(), vec![2, 3, 4]);
+
+    // Order is preserved, so the oldest item is still evicted first.
+    buffer.push_overwrite(5);
+    assert_eq!(buffer.take_snapshot(), vec![3, 4, 5]);
+  }
+
+  #[test]
+  fn test_overwritten_counts_evictions() {
+    let buffer = LockFreeRingBuffer::<i32>::new(3);
+    for i in 0..10 {
+      buffer.push_overwrite(i);
+    }
+    assert_eq!(buffer.overwritten(), 7);
+
+    // Popped items are not counted when their slot is reused.
+    assert_eq!(buffer.pop(), Some(7));
+    buffer.push_overwrite(10);
+    assert_eq!(buffer.overwritten(), 7);
+    assert_eq!(buffer.take_snapshot(), vec![8, 9, 10]);
+  }
+
+  #[test]
+  fn test_concurrent_pushes_keep_the_newest() {
+    let capacity = 64;
+    let buffer = LockFreeRingBuffer::<u64>::new_shared(capacity);
+    let num_threads = 8;
+    let items_per_thread = 10_000u64;
+
+    let handles: Vec<_> = (0..num_threads)
+      .map(|t| {
+        let buffer = Arc::clone(&buffer);
+        thread::spawn(move || {
+          // The top bits say which thread pushed, the rest its order.
+          for i in 0..items_per_thread {
+            buffer.push_overwrite((t << 32) | i);
+          }
+        })
+      })
+      .collect();
+    for handle in handles {
+      handle.join().unwrap();
+    }
+
+    let total = num_threads * items_per_thread;
+    assert_eq!(buffer.len(), capacity);
+    assert_eq!(buffer.overwritten(), total - capacity as u64);
+
+    let items = buffer.take_snapshot();
+    assert_eq!(items.len(), capacity);
+    // The very last push survived, and each thread's items are its newest,
+    // in the order it pushed them.
+    assert!((0..num_threads).any(|t| items.contains(&((t << 32) | (items_per_thread - 1)))));
+    for t in 0..num_threads {
+      let mine: Vec<u64> = items
+        .iter()
+        .filter(|&&item| item >> 32 == t)
+        .map(|&item| item & u32::MAX as u64)
+        .collect();
+      let newest = (items_per_thread - mine.len() as u64..items_per_thread).collect::<Vec<_>>();
+      assert_eq!(mine, newest);
+    }
+  }
+
+  #[test]
+  fn test_peek_snapshot_during_pushes() {
+    let buffer = LockFreeRingBuffer::<u64>::new_shared(128);
+    let producer = {
+      let buffer = Arc::clone(&buffer);
+      thread::spawn(move || {
+        for i in 0..50_000u64 {
+          buffer.push_overwrite(i);
+        }
+      })
+    };
+
+    while !producer.is_finished() {
+      let items = buffer.peek_snapshot();
+      assert!(items.len() <= 128);
+      // A single producer's items stay in order in every copy.
+      assert!(items.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+    producer.join().unwrap();
+
+    let items = buffer.peek_snapshot();
+    assert_eq!(items, (50_000 - 128..50_000).collect::<Vec<_>>());
+    assert_eq!(buffer.len(), 128);
+  }
+
+  #[test]
+  fn test_drop_releases_items() {
+    let item = Arc::new(());
+    let buffer = LockFreeRingBuffer::new(4);
+    for _ in 0..6 {
+      buffer.push_overwrite(Arc::clone(&item));
+    }
+    let _ = buffer.pop();
+    assert_eq!(Arc::strong_count(&item), 4);
+
+    drop(buffer);
+    assert_eq!(Arc::strong_count(&item), 1);
+  }
+
+  #[test]
+  fn test_push_does_not_wait_for_a_held_slot() {
+    let buffer = LockFreeRingBuffer::<i32>::new(2);
+    buffer.push_overwrite(1);
+
+    // A reader that stalls while copying slot 0 leaves it empty.
+    let held = buffer.slot(0).take().unwrap();
+    buffer.push_overwrite(2);
+    buffer.push_overwrite(3);
+
+    // Putting the stale item back evicts it instead of the newer one.
+    assert_eq!(buffer.install(held), Some(1));
+    assert_eq!(buffer.overwritten(), 1);
+    assert_eq!(buffer.take_snapshot(), vec![2, 3]);
+  }
+
   #[test]
   fn test_clone() {
     let buffer = LockFreeRingBuffer::<i32>::new(3);
@@ -128,7 +255,7 @@ mod __test__ {
     // Clone first
     let cloned = buffer.clone();
 
-    // Original buffer should still have items (refilled after cloning)
+    // Cloning leaves the original's items in place
     let original_items = buffer.take_snapshot();
     assert_eq!(original_items, vec![1, 2]);
 
@@ -174,7 +301,7 @@ mod __test__ {
     assert_eq!(deserialized.capacity(), 3);
     assert_eq!(deserialized.take_snapshot(), vec![1, 2, 3]);
 
-    // Original buffer should still have items (refilled after serialization)
+    // Serializing leaves the original's items in place
     assert_eq!(buffer.take_snapshot(), vec![1, 2, 3]);
   }
 
@@ -1,15 +1,107 @@
 mod __test__;
 
-use crossbeam_queue::ArrayQueue;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
 use std::sync::Arc;
 
-#[derive(Debug)]
+/// An item and the position it was pushed at.
+struct Node<T> {
+  position: u64,
+  value: T,
+}
+
+/// Holds at most one node. Nodes only ever leave a slot through an atomic
+/// swap, so the thread that swaps one out owns it outright and no thread
+/// reads a node another one may free.
+struct Slot<T> {
+  node: AtomicPtr<Node<T>>,
+}
+
+impl<T> Slot<T> {
+  fn empty() -> Self {
+    Self {
+      node: AtomicPtr::new(ptr::null_mut()),
+    }
+  }
+
+  /// Move the node out, leaving the slot empty.
+  fn take(&self) -> Option<Box<Node<T>>> {
+    let node = self.node.swap(ptr::null_mut(), Ordering::AcqRel);
+    // SAFETY: slots only hold pointers from `Box::into_raw`, and the swap
+    // made this thread their only owner.
+    (!node.is_null()).then(|| unsafe { Box::from_raw(node) })
+  }
+
+  /// Leave the newer of `node` and the slot's current node in the slot, and
+  /// return the other one.
+  fn install(&self, mut node: Box<Node<T>>) -> Option<Box<Node<T>>> {
+    let raw = Box::into_raw(node);
+    match self
+      .node
+      .compare_exchange(ptr::null_mut(), raw, Ordering::AcqRel, Ordering::Acquire)
+    {
+      Ok(_) => return None,
+      // SAFETY: the CAS failed, so `raw` was never published.
+      Err(_) => node = unsafe { Box::from_raw(raw) },
+    }
+
+    loop {
+      let position = node.position;
+      let previous = self.node.swap(Box::into_raw(node), Ordering::AcqRel);
+      if previous.is_null() {
+        return None;
+      }
+      // SAFETY: as in `take`.
+      let previous = unsafe { Box::from_raw(previous) };
+      if previous.position < position {
+        return Some(previous);
+      }
+      // That displaced a newer node. Putting it back hands ours back to us,
+      // or to whichever thread displaces it first, which drops it; either way
+      // each iteration holds a newer node than the last, so this ends.
+      node = previous;
+    }
+  }
+}
+
+/// Fixed-size ring that keeps the newest `capacity` items, for any number of
+/// producers.
+///
+/// A push claims the next position with one atomic increment, boxes the item
+/// with its position and installs it in slot `position % capacity`. Slots are
+/// atomic pointers: an empty one is filled with a single CAS, an occupied one
+/// by swapping and comparing positions until the newer node is in the slot
+/// and the older one in hand. When two pushes a full lap apart race for a
+/// slot, the newer item wins regardless of which finishes first, so the
+/// newest `capacity` items always survive. No step waits for another thread:
+/// a push that is preempted midway holds up nothing.
+///
+/// Readers either drain items oldest first ([`Self::pop`],
+/// [`Self::take_snapshot`]) or copy them without removing anything
+/// ([`Self::peek_snapshot`]), which briefly takes each node out and installs
+/// it again. Readers wait for a push that has claimed the position they need
+/// but not installed it yet, and for another reader to put back a node it is
+/// copying.
 pub struct LockFreeRingBuffer<T> {
-  pub queue: ArrayQueue<T>,
-  pub capacity: usize,
+  slots: Box<[Slot<T>]>,
+  capacity: usize,
+  /// Next position to write.
+  head: AtomicU64,
+  /// Oldest position not yet popped; positions before `head - capacity` have
+  /// been overwritten, whatever `tail` says.
+  tail: AtomicU64,
+  overwritten: AtomicU64,
+  _items: PhantomData<T>,
 }
 
+// SAFETY: items only move between threads by handing over the node that owns
+// them, never by sharing a reference, so `T: Send` is enough.
+unsafe impl<T: Send> Send for LockFreeRingBuffer<T> {}
+unsafe impl<T: Send> Sync for LockFreeRingBuffer<T> {}
+
 impl<T> LockFreeRingBuffer<T> {
   pub fn new(capacity: usize) -> Self {
     if capacity == 0 {
@@ -17,56 +109,171 @@ impl<T> LockFreeRingBuffer<T> {
     }
 
     Self {
-      queue: ArrayQueue::new(capacity),
+      slots: (0..capacity).map(|_| Slot::empty()).collect(),
       capacity,
+      head: AtomicU64::new(0),
+      tail: AtomicU64::new(0),
+      overwritten: AtomicU64::new(0),
+      _items: PhantomData,
     }
   }
 
-  pub fn push(&self, item: T) -> Result<Option<T>, T> {
-    match self.queue.push(item) {
-      Ok(()) => Ok(None),
-      Err(rejected_item) => {
-        // Queue is full, evict oldest item to make space
-        let evicted = self.queue.pop();
+  fn slot(&self, position: u64) -> &Slot<T> {
+    &self.slots[(position % self.capacity as u64) as usize]
+  }
 
-        // Try to push again - this should succeed since we just made space
-        match self.queue.push(rejected_item) {
-          Ok(()) => Ok(evicted),
-          Err(item) => Err(item), // This shouldn't happen, but handle gracefully
-        }
-      },
-    }
+  /// Put a node taken out of its slot back, counting whatever that evicts.
+  fn install(&self, node: Box<Node<T>>) -> Option<T> {
+    let evicted = self.slot(node.position).install(node)?;
+    self.overwritten.fetch_add(1, Ordering::Relaxed);
+    Some(evicted.value)
+  }
+
+  /// Add an item, returning the item it pushed out of the ring: the oldest
+  /// one when full, or `item` itself if newer items already took its slot.
+  /// An item pushed out while a reader was copying it is dropped by that
+  /// reader instead.
+  pub fn push(&self, item: T) -> Option<T> {
+    let position = self.head.fetch_add(1, Ordering::AcqRel);
+    self.install(Box::new(Node {
+      position,
+      value: item,
+    }))
   }
 
   pub fn push_overwrite(&self, item: T) {
     let _ = self.push(item);
   }
 
+  /// Remove the oldest item.
   pub fn pop(&self) -> Option<T> {
-    self.queue.pop()
+    self.pop_before(u64::MAX)
   }
 
+  /// Remove the oldest item if its position is below `end`.
+  fn pop_before(&self, end: u64) -> Option<T> {
+    let mut spins = 0u32;
+    loop {
+      let tail = self.tail.load(Ordering::Acquire);
+      let head = self.head.load(Ordering::Acquire).min(end);
+      if tail >= head {
+        return None;
+      }
+
+      let oldest = head.saturating_sub(self.capacity as u64);
+      if tail < oldest {
+        // Everything before `oldest` has been overwritten.
+        let _ = self
+          .tail
+          .compare_exchange(tail, oldest, Ordering::AcqRel, Ordering::Relaxed);
+        continue;
+      }
+
+      match self.slot(tail).take() {
+        Some(node) if node.position == tail => {
+          if self
+            .tail
+            .compare_exchange(tail, tail + 1, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+          {
+            return Some(node.value);
+          }
+          // Another reader moved `tail` past it.
+          self.install(node);
+        },
+        Some(node) if node.position > tail => {
+          // Overwritten by a newer push.
+          self.install(node);
+          let _ = self
+            .tail
+            .compare_exchange(tail, tail + 1, Ordering::AcqRel, Ordering::Relaxed);
+        },
+        node => {
+          // The push that claimed `tail` has not installed it yet, or another
+          // reader is copying it.
+          if let Some(node) = node {
+            self.install(node);
+          }
+          backoff(&mut spins);
+        },
+      }
+    }
+  }
+
+  /// Remove and return every item pushed so far, oldest first. Items pushed
+  /// while it runs are left for the next call.
   pub fn take_snapshot(&self) -> Vec<T> {
-    let mut items = Vec::new();
-    while let Some(item) = self.queue.pop() {
+    let end = self.head.load(Ordering::Acquire);
+    let mut items = Vec::with_capacity(self.len());
+    while let Some(item) = self.pop_before(end) {
       items.push(item);
     }
     items
   }
 
+  /// Copy the items, oldest first, and leave them in the ring. The copy holds
+  /// the items present when it started, minus any overwritten or popped while
+  /// it ran; it waits for pushes that had claimed a position by then to
+  /// install it.
+  pub fn peek_snapshot(&self) -> Vec<T>
+  where
+    T: Clone,
+  {
+    let head = self.head.load(Ordering::Acquire);
+    let start = self
+      .tail
+      .load(Ordering::Acquire)
+      .max(head.saturating_sub(self.capacity as u64));
+
+    let mut items = Vec::with_capacity((head - start.min(head)) as usize);
+    for position in start..head {
+      let mut spins = 0u32;
+      loop {
+        match self.slot(position).take() {
+          Some(node) if node.position >= position => {
+            if node.position == position {
+              items.push(node.value.clone());
+            }
+            self.install(node);
+            break;
+          },
+          node => {
+            if let Some(node) = node {
+              self.install(node);
+            }
+            if position < self.tail.load(Ordering::Acquire) {
+              // Popped while this ran.
+              break;
+            }
+            backoff(&mut spins);
+          },
+        }
+      }
+    }
+    items
+  }
+
+  /// Items pushed out by newer ones before they were popped, since the
+  /// buffer was created.
+  pub fn overwritten(&self) -> u64 {
+    self.overwritten.load(Ordering::Relaxed)
+  }
+
   #[inline]
   pub fn len(&self) -> usize {
-    self.queue.len()
+    let head = self.head.load(Ordering::Acquire);
+    let tail = self.tail.load(Ordering::Acquire);
+    (head.saturating_sub(tail) as usize).min(self.capacity)
   }
 
   #[inline]
   pub fn is_empty(&self) -> bool {
-    self.queue.is_empty()
+    self.len() == 0
   }
 
   #[inline]
   pub fn is_full(&self) -> bool {
-    self.queue.is_full()
+    self.len() == self.capacity
   }
 
   #[inline]
@@ -79,23 +286,38 @@ impl<T> LockFreeRingBuffer<T> {
   }
 }
 
-impl<T: Clone> Clone for LockFreeRingBuffer<T> {
-  fn clone(&self) -> Self {
-    let new_buffer = Self::new(self.capacity);
+/// Spin briefly, then yield, while a reader waits for a slot to be filled.
+fn backoff(spins: &mut u32) {
+  if *spins < 64 {
+    std::hint::spin_loop();
+  } else {
+    std::thread::yield_now();
+  }
+  *spins = spins.saturating_add(1);
+}
 
-    // Take a snapshot: pop all items from original buffer
-    let mut temp = Vec::with_capacity(self.len());
-    while let Some(item) = self.queue.pop() {
-      temp.push(item);
+impl<T> Drop for LockFreeRingBuffer<T> {
+  fn drop(&mut self) {
+    for slot in self.slots.iter() {
+      drop(slot.take());
     }
+  }
+}
 
-    // Refill the original buffer immediately to minimize disruption
-    for item in &temp {
-      self.push_overwrite(item.clone());
-    }
+impl<T> fmt::Debug for LockFreeRingBuffer<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("LockFreeRingBuffer")
+      .field("capacity", &self.capacity)
+      .field("len", &self.len())
+      .field("overwritten", &self.overwritten())
+      .finish()
+  }
+}
 
-    // Populate the new buffer with the same data
-    for item in temp {
+impl<T: Clone> Clone for LockFreeRingBuffer<T> {
+  fn clone(&self) -> Self {
+    let new_buffer = Self::new(self.capacity);
+    for item in self.peek_snapshot() {
       new_buffer.push_overwrite(item);
     }
 
@@ -120,13 +342,7 @@ impl<T: Clone + Serialize> Serialize for LockFreeRingBuffer<T> {
   {
     use serde::ser::SerializeStruct;
 
-    // Take snapshot for serialization (this temporarily empties the buffer)
-    let items = self.take_snapshot();
-
-    // Refill buffer after taking snapshot to minimize disruption
-    for item in &items {
-      self.push_overwrite(item.clone());
-    }
+    let items = self.peek_snapshot();
 
     // Serialize the snapshot and capacity
     let mut state = serializer.serialize_struct("LockFreeRingBuffer", 2)?;
@@ -1,18 +1,35 @@
+pub mod backpressure;
+pub mod clock;
+pub mod config;
+pub mod directive;
 pub mod event;
 pub mod event_builder;
 pub mod file_listener;
+pub mod format;
 pub mod kv;
+pub mod kv_arena;
+pub mod kv_codec;
 pub mod lf_buffer;
 pub mod listener;
+pub mod listener_worker;
+#[cfg(feature = "log")]
+pub mod log_facade;
 pub mod panic_hook;
+pub mod reload;
 // signal_hook uses unix-only signal numbers (SIGBUS, SIGCHLD, etc).
 // Windows has no equivalents; gate the module out there.
 #[cfg(unix)]
 pub mod signal_hook;
 pub mod snapshot;
+pub mod span;
 pub mod stdout_listener;
 pub mod string_interner;
+pub mod testing;
+pub mod thread_registry;
 pub mod trace;
+pub mod trace_builder;
+#[cfg(feature = "tracing-layer")]
+pub mod tracing_layer;
 pub mod utils;
 
 pub extern crate ttlog_macros;
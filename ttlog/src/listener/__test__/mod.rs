@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod __test__ {
+
+  use std::num::NonZeroU32;
+  use std::sync::Arc;
+
+  use serde_json::json;
+
+  use crate::event::{LogEvent, LogLevel};
+  use crate::event_builder::EventBuilder;
+  use crate::kv_codec::FieldEncoder;
+  use crate::listener::ListenerFilter;
+  use crate::string_interner::StringInterner;
+
+  fn event(interner: &Arc<StringInterner>, level: LogLevel, target: &str) -> LogEvent {
+    EventBuilder::new(interner.clone()).build_fast(0, level, target, "message")
+  }
+
+  fn event_with_kv(interner: &Arc<StringInterner>, kv: serde_json::Value) -> LogEvent {
+    let mut event = event(interner, LogLevel::INFO, "app");
+    let mut encoder = FieldEncoder::new();
+    for (key, value) in kv.as_object().unwrap() {
+      encoder.field(interner.intern_key(key), value);
+    }
+    event.kv_id = NonZeroU32::new(interner.store_kv(encoder.finish()));
+    event
+  }
+
+  // ── Level and targets ──────────────────────────────────────────────
+
+  #[test]
+  fn default_filter_passes_everything() {
+    let interner = Arc::new(StringInterner::new());
+    let mut filter = ListenerFilter::default();
+    assert!(filter.is_pass_all());
+    assert!(filter.accepts(&event(&interner, LogLevel::TRACE, "any"), &interner));
+  }
+
+  #[test]
+  fn min_level_rejects_lower_levels() {
+    let interner = Arc::new(StringInterner::new());
+    let mut filter = ListenerFilter::new().min_level(LogLevel::INFO);
+    assert!(!filter.accepts(&event(&interner, LogLevel::DEBUG, "app"), &interner));
+    assert!(filter.accepts(&event(&interner, LogLevel::INFO, "app"), &interner));
+    assert!(filter.accepts(&event(&interner, LogLevel::ERROR, "app"), &interner));
+  }
+
+  #[test]
+  fn allow_target_matches_module_boundaries() {
+    let interner = Arc::new(StringInterner::new());
+    let mut filter = ListenerFilter::new().allow_target("db::*");
+    assert!(filter.accepts(&event(&interner, LogLevel::INFO, "db"), &interner));
+    assert!(filter.accepts(&event(&interner, LogLevel::INFO, "db::pool"), &interner));
+    assert!(!filter.accepts(&event(&interner, LogLevel::INFO, "dbx"), &interner));
+    assert!(!filter.accepts(&event(&interner, LogLevel::INFO, "http"), &interner));
+  }
+
+  #[test]
+  fn deny_target_wins_over_allow() {
+    let interner = Arc::new(StringInterner::new());
+    let mut filter = ListenerFilter::new()
+      .allow_target("db")
+      .deny_target("db::pool");
+    assert!(filter.accepts(&event(&interner, LogLevel::INFO, "db::query"), &interner));
+    assert!(!filter.accepts(
+      &event(&interner, LogLevel::INFO, "db::pool::conn"),
+      &interner
+    ));
+  }
+
+  // ── KV predicates ──────────────────────────────────────────────────
+
+  #[test]
+  fn kv_predicates_must_all_hold() {
+    let interner = Arc::new(StringInterner::new());
+    let mut filter = ListenerFilter::new()
+      .kv_equals("tenant", "acme")
+      .kv_matches("latency_ms", |v| v.as_u64().is_some_and(|ms| ms > 100));
+
+    let slow = event_with_kv(&interner, json!({ "tenant": "acme", "latency_ms": 250 }));
+    let fast = event_with_kv(&interner, json!({ "tenant": "acme", "latency_ms": 5 }));
+    let other = event_with_kv(&interner, json!({ "tenant": "other", "latency_ms": 250 }));
+    assert!(filter.accepts(&slow, &interner));
+    assert!(!filter.accepts(&fast, &interner));
+    assert!(!filter.accepts(&other, &interner));
+  }
+
+  #[test]
+  fn kv_exists_rejects_events_without_kv() {
+    let interner = Arc::new(StringInterner::new());
+    let mut filter = ListenerFilter::new().kv_exists("request_id");
+    assert!(!filter.accepts(&event(&interner, LogLevel::INFO, "app"), &interner));
+    assert!(filter.accepts(
+      &event_with_kv(&interner, json!({ "request_id": 1 })),
+      &interner
+    ));
+  }
+}
@@ -1,5 +1,16 @@
-use crate::event::LogEvent;
+mod __test__;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::event::{LogEvent, LogLevel};
+use crate::format;
+use crate::kv_codec;
 use crate::string_interner::StringInterner;
+use crate::utils;
 
 pub trait LogFormatter: Send + Sync {
   fn format(&self, event: &LogEvent, interner: &StringInterner, out: &mut String);
@@ -26,3 +37,166 @@ pub trait LogListener: Send + Sync + 'static {
   /// Optional: Called during shutdown (cleanup resources)  
   fn on_shutdown(&self) {}
 }
+
+/// Condition on one KV field of an event.
+#[derive(Clone)]
+pub enum KvPredicate {
+  /// The field is present, with any value.
+  Exists(String),
+  /// The field is present and equal to the value.
+  Equals(String, Value),
+  /// The field is present and the function accepts its value.
+  Matches(String, Arc<dyn Fn(&Value) -> bool + Send + Sync>),
+}
+
+impl KvPredicate {
+  fn matches(&self, kv: &Value) -> bool {
+    match self {
+      KvPredicate::Exists(key) => kv.get(key).is_some(),
+      KvPredicate::Equals(key, expected) => kv.get(key) == Some(expected),
+      KvPredicate::Matches(key, f) => kv.get(key).is_some_and(|value| f(value)),
+    }
+  }
+}
+
+impl fmt::Debug for KvPredicate {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      KvPredicate::Exists(key) => f.debug_tuple("Exists").field(key).finish(),
+      KvPredicate::Equals(key, value) => f.debug_tuple("Equals").field(key).field(value).finish(),
+      KvPredicate::Matches(key, _) => f.debug_tuple("Matches").field(key).finish(),
+    }
+  }
+}
+
+/// Declarative filter evaluated on the listener thread before an event is
+/// handed to a listener. The default filter accepts everything.
+///
+/// Targets match on module boundaries: `"db"` (or `"db::*"`) covers `db` and
+/// `db::pool` but not `dbx`. A deny entry always wins over an allow entry;
+/// an empty allow list allows every target. All KV predicates must hold.
+///
+/// ```ignore
+/// let filter = ListenerFilter::new()
+///   .min_level(LogLevel::DEBUG)
+///   .allow_target("db::*")
+///   .deny_target("db::pool")
+///   .kv_equals("tenant", "acme");
+/// trace.add_listener_with_filter(Arc::new(StdoutListener::new()), filter);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ListenerFilter {
+  min_level: Option<LogLevel>,
+  allow_targets: Vec<String>,
+  deny_targets: Vec<String>,
+  kv_predicates: Vec<KvPredicate>,
+  /// Target decisions by interned target ID; targets are few and stable.
+  target_cache: HashMap<u32, bool>,
+}
+
+impl ListenerFilter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn min_level(mut self, level: LogLevel) -> Self {
+    self.min_level = Some(level);
+    self
+  }
+
+  pub fn allow_target(mut self, prefix: impl Into<String>) -> Self {
+    self.allow_targets.push(prefix.into());
+    self.target_cache.clear();
+    self
+  }
+
+  pub fn deny_target(mut self, prefix: impl Into<String>) -> Self {
+    self.deny_targets.push(prefix.into());
+    self.target_cache.clear();
+    self
+  }
+
+  pub fn kv(mut self, predicate: KvPredicate) -> Self {
+    self.kv_predicates.push(predicate);
+    self
+  }
+
+  pub fn kv_exists(self, key: impl Into<String>) -> Self {
+    self.kv(KvPredicate::Exists(key.into()))
+  }
+
+  pub fn kv_equals(self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+    self.kv(KvPredicate::Equals(key.into(), value.into()))
+  }
+
+  pub fn kv_matches(
+    self,
+    key: impl Into<String>,
+    f: impl Fn(&Value) -> bool + Send + Sync + 'static,
+  ) -> Self {
+    self.kv(KvPredicate::Matches(key.into(), Arc::new(f)))
+  }
+
+  /// Whether the filter lets every event through, letting callers skip it.
+  pub fn is_pass_all(&self) -> bool {
+    self.min_level.is_none()
+      && self.allow_targets.is_empty()
+      && self.deny_targets.is_empty()
+      && self.kv_predicates.is_empty()
+  }
+
+  /// Evaluate the filter. Cheap checks run first; the KV payload is only
+  /// decoded when there are KV predicates.
+  pub fn accepts(&mut self, event: &LogEvent, interner: &StringInterner) -> bool {
+    if self.min_level.is_some_and(|min| event.level() < min) {
+      return false;
+    }
+
+    if !(self.allow_targets.is_empty() && self.deny_targets.is_empty()) {
+      let allowed = match self.target_cache.get(&event.target_id) {
+        Some(allowed) => *allowed,
+        None => {
+          let target = interner.get_target(event.target_id);
+          let allowed = self.target_allowed(target.as_deref().unwrap_or(""));
+          self.target_cache.insert(event.target_id, allowed);
+          allowed
+        },
+      };
+      if !allowed {
+        return false;
+      }
+    }
+
+    if self.kv_predicates.is_empty() {
+      return true;
+    }
+
+    let kv = event
+      .kv_id
+      .and_then(|id| interner.get_kv(id.get()))
+      .and_then(|bytes| kv_codec::decode(&bytes, interner))
+      .map(|kv| format::split_args(kv).0)
+      .unwrap_or(Value::Null);
+
+    self
+      .kv_predicates
+      .iter()
+      .all(|predicate| predicate.matches(&kv))
+  }
+
+  fn target_allowed(&self, target: &str) -> bool {
+    if self
+      .deny_targets
+      .iter()
+      .any(|prefix| utils::target_matches(target, prefix))
+    {
+      return false;
+    }
+
+    self.allow_targets.is_empty()
+      || self
+        .allow_targets
+        .iter()
+        .any(|prefix| utils::target_matches(target, prefix))
+  }
+}
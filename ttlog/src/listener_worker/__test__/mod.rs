@@ -0,0 +1,200 @@
+#[cfg(test)]
+mod __test__ {
+
+  use std::sync::{Arc, Mutex};
+  use std::time::Duration;
+
+  use crate::backpressure::OverflowPolicy;
+  use crate::event::{LogEvent, LogLevel};
+  use crate::event_builder::EventBuilder;
+  use crate::listener::LogListener;
+  use crate::listener_worker::{
+    DeliveryStats, ListenerStatus, ListenerWorker, WorkerOptions, WorkerRoom,
+  };
+  use crate::string_interner::StringInterner;
+  use crate::trace::{Batching, ListenerId};
+  use crate::trace_builder::TraceError;
+
+  #[derive(Default)]
+  struct Recorder {
+    batches: Mutex<Vec<usize>>,
+    threads: Mutex<Vec<String>>,
+    shutdowns: Mutex<u32>,
+    panic_on_first: Mutex<bool>,
+  }
+
+  impl LogListener for Recorder {
+    fn handle(&self, _event: &LogEvent, _interner: &StringInterner) {}
+
+    fn handle_batch(&self, events: &[LogEvent], _interner: &StringInterner) {
+      let name = std::thread::current().name().unwrap_or("").to_string();
+      self.threads.lock().unwrap().push(name);
+      if std::mem::take(&mut *self.panic_on_first.lock().unwrap()) {
+        panic!("listener failure");
+      }
+      self.batches.lock().unwrap().push(events.len());
+    }
+
+    fn on_shutdown(&self) {
+      *self.shutdowns.lock().unwrap() += 1;
+    }
+  }
+
+  fn events(interner: &Arc<StringInterner>, n: usize) -> Vec<LogEvent> {
+    let builder = EventBuilder::new(Arc::clone(interner));
+    (0..n)
+      .map(|_| builder.build_fast(0, LogLevel::INFO, "worker", "event"))
+      .collect()
+  }
+
+  fn spawn(
+    listener: Arc<Recorder>,
+    options: WorkerOptions,
+  ) -> (ListenerWorker, Arc<StringInterner>) {
+    let interner = Arc::new(StringInterner::new());
+    let options = WorkerOptions {
+      queue_capacity: options.queue_capacity.or(Some(1024)),
+      ..options
+    };
+    let worker =
+      ListenerWorker::spawn(ListenerId::next(), listener, Arc::clone(&interner), options).unwrap();
+    (worker, interner)
+  }
+
+  #[test]
+  fn options_builder_sets_every_field() {
+    let batching = Batching {
+      max_events: 8,
+      max_wait: Duration::from_millis(1),
+    };
+    let options = WorkerOptions::new()
+      .queue_capacity(32)
+      .overflow_policy(OverflowPolicy::DropOldest)
+      .batching(batching);
+
+    assert_eq!(options.queue_capacity, Some(32));
+    assert_eq!(options.overflow_policy, OverflowPolicy::DropOldest);
+    assert_eq!(options.batching, batching);
+  }
+
+  #[test]
+  fn worker_delivers_on_its_own_thread() {
+    let listener = Arc::new(Recorder::default());
+    let (worker, interner) = spawn(listener.clone(), WorkerOptions::default());
+
+    worker.send(&events(&interner, 10));
+    worker.flush();
+
+    assert_eq!(listener.batches.lock().unwrap().iter().sum::<usize>(), 10);
+    assert!(listener
+      .threads
+      .lock()
+      .unwrap()
+      .iter()
+      .all(|name| name.starts_with("ttlog-listener-")));
+
+    let health = worker.health();
+    assert!(health.dedicated);
+    assert_eq!(health.delivered, 10);
+    assert_eq!(health.status, ListenerStatus::Healthy);
+  }
+
+  #[test]
+  fn worker_batches_up_to_the_limit() {
+    let listener = Arc::new(Recorder::default());
+    let options = WorkerOptions::new().batching(Batching {
+      max_events: 4,
+      max_wait: Duration::from_millis(50),
+    });
+    let (worker, interner) = spawn(listener.clone(), options);
+
+    worker.send(&events(&interner, 10));
+    worker.flush();
+
+    let batches = listener.batches.lock().unwrap();
+    assert_eq!(batches.iter().sum::<usize>(), 10);
+    assert!(batches.iter().all(|&len| len <= 4));
+  }
+
+  #[test]
+  fn stop_drains_the_queue_and_runs_on_shutdown() {
+    let listener = Arc::new(Recorder::default());
+    let (mut worker, interner) = spawn(listener.clone(), WorkerOptions::default());
+
+    worker.send(&events(&interner, 5));
+    worker.stop();
+
+    assert_eq!(listener.batches.lock().unwrap().iter().sum::<usize>(), 5);
+    assert_eq!(*listener.shutdowns.lock().unwrap(), 1);
+    assert_eq!(worker.health().status, ListenerStatus::Stopped);
+
+    // Stopping twice (and dropping) does not run on_shutdown again.
+    worker.stop();
+    drop(worker);
+    assert_eq!(*listener.shutdowns.lock().unwrap(), 1);
+  }
+
+  #[test]
+  fn worker_survives_a_panicking_listener() {
+    let listener = Arc::new(Recorder {
+      panic_on_first: Mutex::new(true),
+      ..Default::default()
+    });
+    let (worker, interner) = spawn(listener.clone(), WorkerOptions::default());
+
+    worker.send(&events(&interner, 1));
+    worker.flush();
+    let health = worker.health();
+    assert_eq!(health.status, ListenerStatus::Panicked);
+    assert_eq!(health.panics, 1);
+
+    worker.send(&events(&interner, 1));
+    worker.flush();
+    let health = worker.health();
+    assert_eq!(health.status, ListenerStatus::Healthy);
+    assert_eq!(health.delivered, 1);
+  }
+
+  #[test]
+  fn delivery_stats_track_the_last_outcome() {
+    let stats = DeliveryStats::default();
+    stats.record(3, true);
+    stats.record(0, false);
+
+    let health = stats.health();
+    assert!(!health.dedicated);
+    assert_eq!(health.delivered, 3);
+    assert_eq!(health.panics, 1);
+    assert_eq!(health.status, ListenerStatus::Panicked);
+
+    stats.record(1, true);
+    assert_eq!(stats.health().status, ListenerStatus::Healthy);
+  }
+
+  #[test]
+  fn worker_room_is_shared_by_every_worker() {
+    let room = WorkerRoom::new(100, 64);
+    let (first, second, third) = (ListenerId::next(), ListenerId::next(), ListenerId::next());
+
+    // Without a capacity a worker takes the shared queue's, capped at what
+    // is left.
+    let options = room.reserve(first, WorkerOptions::default()).unwrap();
+    assert_eq!(options.queue_capacity, Some(64));
+    let options = room.reserve(second, WorkerOptions::default()).unwrap();
+    assert_eq!(options.queue_capacity, Some(36));
+
+    assert!(matches!(
+      room.reserve(third, WorkerOptions::new().queue_capacity(1)),
+      Err(TraceError::QueueExceedsArena {
+        queue_capacity: 1,
+        room: 0,
+      })
+    ));
+
+    room.release(first);
+    let options = room
+      .reserve(third, WorkerOptions::new().queue_capacity(64))
+      .unwrap();
+    assert_eq!(options.queue_capacity, Some(64));
+  }
+}
@@ -0,0 +1,329 @@
+mod __test__;
+
+use std::collections::HashMap;
+use std::io;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::backpressure::{Backpressure, DropStats, OverflowPolicy};
+use crate::event::LogEvent;
+use crate::kv_arena::KvPin;
+use crate::listener::LogListener;
+use crate::string_interner::StringInterner;
+use crate::trace::{Batching, ListenerId};
+use crate::trace_builder::TraceError;
+
+/// Settings of a listener that runs on its own thread (see
+/// [`crate::trace::Trace::add_listener_on_worker`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerOptions {
+  /// Events waiting for this listener before `overflow_policy` applies.
+  /// `None` takes the shared queue's capacity, or whatever is left of the KV
+  /// arena if that is less, see [`WorkerRoom`].
+  pub queue_capacity: Option<usize>,
+  pub overflow_policy: OverflowPolicy,
+  pub batching: Batching,
+}
+
+impl WorkerOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn queue_capacity(mut self, capacity: usize) -> Self {
+    self.queue_capacity = Some(capacity);
+    self
+  }
+
+  pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+    self.overflow_policy = policy;
+    self
+  }
+
+  pub fn batching(mut self, batching: Batching) -> Self {
+    self.batching = batching;
+    self
+  }
+}
+
+/// The KV arena slots a trace has left for worker queues once the ring
+/// buffer and the shared queue are accounted for, and the workers holding
+/// them. Queued events keep their payloads alive, so all worker queues
+/// together may not hold more events than that.
+#[derive(Debug)]
+pub(crate) struct WorkerRoom {
+  room: usize,
+  default_capacity: usize,
+  taken: Mutex<HashMap<ListenerId, usize>>,
+}
+
+impl WorkerRoom {
+  pub(crate) fn new(room: usize, default_capacity: usize) -> Self {
+    Self {
+      room,
+      default_capacity,
+      taken: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Give worker `id` its queue capacity, the shared queue's by default but
+  /// no more than is left, or fail if the room left cannot hold it.
+  pub(crate) fn reserve(
+    &self,
+    id: ListenerId,
+    mut options: WorkerOptions,
+  ) -> Result<WorkerOptions, TraceError> {
+    let mut taken = self.taken.lock().unwrap();
+    let left = self.room.saturating_sub(taken.values().sum());
+    let capacity = match options.queue_capacity {
+      Some(capacity) => capacity,
+      None if left > 0 => self.default_capacity.min(left),
+      None => self.default_capacity,
+    };
+    if capacity > left {
+      return Err(TraceError::QueueExceedsArena {
+        queue_capacity: capacity,
+        room: left,
+      });
+    }
+    taken.insert(id, capacity);
+    options.queue_capacity = Some(capacity);
+    Ok(options)
+  }
+
+  /// Return the room held by worker `id`, once it is removed.
+  pub(crate) fn release(&self, id: ListenerId) {
+    self.taken.lock().unwrap().remove(&id);
+  }
+}
+
+/// Overall state of a listener, see [`ListenerHealth`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenerStatus {
+  Healthy,
+  /// Its queue is at least half full.
+  Backlogged,
+  /// The last delivery to it panicked.
+  Panicked,
+  /// Its worker thread has exited.
+  Stopped,
+}
+
+/// Snapshot of how a listener is keeping up, from
+/// [`crate::trace::Trace::listener_health`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListenerHealth {
+  pub status: ListenerStatus,
+  /// Whether the listener runs on its own worker thread.
+  pub dedicated: bool,
+  /// Events waiting in its queue; always 0 on the shared thread.
+  pub queued: usize,
+  /// Events handed to it.
+  pub delivered: u64,
+  /// Deliveries that panicked.
+  pub panics: u64,
+  /// Events its own queue discarded; always empty on the shared thread.
+  pub dropped: DropStats,
+}
+
+/// Delivery counters, updated by whichever thread runs the listener.
+#[derive(Debug, Default)]
+pub(crate) struct DeliveryStats {
+  delivered: AtomicU64,
+  panics: AtomicU64,
+  last_panicked: AtomicBool,
+  stopped: AtomicBool,
+}
+
+impl DeliveryStats {
+  pub(crate) fn record(&self, events: usize, ok: bool) {
+    if ok {
+      self.delivered.fetch_add(events as u64, Ordering::Relaxed);
+    } else {
+      self.panics.fetch_add(1, Ordering::Relaxed);
+    }
+    self.last_panicked.store(!ok, Ordering::Relaxed);
+  }
+
+  pub(crate) fn health(&self) -> ListenerHealth {
+    let status = if self.stopped.load(Ordering::Relaxed) {
+      ListenerStatus::Stopped
+    } else if self.last_panicked.load(Ordering::Relaxed) {
+      ListenerStatus::Panicked
+    } else {
+      ListenerStatus::Healthy
+    };
+
+    ListenerHealth {
+      status,
+      dedicated: false,
+      queued: 0,
+      delivered: self.delivered.load(Ordering::Relaxed),
+      panics: self.panics.load(Ordering::Relaxed),
+      dropped: DropStats::default(),
+    }
+  }
+}
+
+pub(crate) enum WorkerMessage {
+  /// An event with its arena KV payload, held until the listener saw it.
+  Event(LogEvent, Option<KvPin>),
+  /// Ack once everything queued before it has been handled.
+  Flush(std::sync::mpsc::Sender<()>),
+}
+
+/// A listener with its own queue and thread, driven by the shared listener
+/// thread.
+pub(crate) struct ListenerWorker {
+  sender: Option<Sender<WorkerMessage>>,
+  backpressure: Backpressure<WorkerMessage>,
+  interner: Arc<StringInterner>,
+  stats: Arc<DeliveryStats>,
+  handle: Option<JoinHandle<()>>,
+}
+
+impl ListenerWorker {
+  pub(crate) fn spawn(
+    id: ListenerId,
+    listener: Arc<dyn LogListener + UnwindSafe + RefUnwindSafe>,
+    interner: Arc<StringInterner>,
+    options: WorkerOptions,
+  ) -> io::Result<Self> {
+    // `Trace` resolves the capacity through `WorkerRoom` before spawning.
+    let capacity = options.queue_capacity.unwrap_or(1).max(1);
+    let (sender, receiver) = crossbeam_channel::bounded(capacity);
+    let backpressure = Backpressure::new(options.overflow_policy, Some(receiver.clone()));
+    let stats = Arc::new(DeliveryStats::default());
+
+    let worker_stats = Arc::clone(&stats);
+    let worker_interner = Arc::clone(&interner);
+    let handle = thread::Builder::new()
+      .name(format!("ttlog-{}", id).replace('#', "-"))
+      .spawn(move || {
+        run(
+          listener,
+          receiver,
+          &worker_interner,
+          options.batching,
+          &worker_stats,
+        );
+        worker_stats.stopped.store(true, Ordering::Relaxed);
+      })?;
+
+    Ok(Self {
+      sender: Some(sender),
+      backpressure,
+      interner,
+      stats,
+      handle: Some(handle),
+    })
+  }
+
+  /// Queue events according to the worker's overflow policy.
+  pub(crate) fn send(&self, events: &[LogEvent]) {
+    if let Some(sender) = &self.sender {
+      for event in events {
+        let kv = event.kv_id.and_then(|id| self.interner.pin_kv(id.get()));
+        self
+          .backpressure
+          .send(sender, WorkerMessage::Event(event.clone(), kv));
+      }
+    }
+  }
+
+  /// Block until the worker has handled everything queued so far.
+  pub(crate) fn flush(&self) {
+    let Some(sender) = &self.sender else {
+      return;
+    };
+    let (ack_tx, ack_rx) = std::sync::mpsc::channel();
+    if sender.send(WorkerMessage::Flush(ack_tx)).is_ok() {
+      let _ = ack_rx.recv();
+    }
+  }
+
+  /// Let the worker handle what is queued, run `on_shutdown` and exit.
+  pub(crate) fn stop(&mut self) {
+    self.sender.take();
+    if let Some(handle) = self.handle.take() {
+      let _ = handle.join();
+    }
+  }
+
+  pub(crate) fn backpressure(&self) -> &Backpressure<WorkerMessage> {
+    &self.backpressure
+  }
+
+  pub(crate) fn health(&self) -> ListenerHealth {
+    let mut health = self.stats.health();
+    health.dedicated = true;
+    health.dropped = self.backpressure.stats();
+    if let Some(sender) = &self.sender {
+      health.queued = sender.len();
+      let backlogged = sender
+        .capacity()
+        .is_some_and(|capacity| health.queued * 2 >= capacity);
+      if backlogged && health.status == ListenerStatus::Healthy {
+        health.status = ListenerStatus::Backlogged;
+      }
+    }
+    health
+  }
+}
+
+impl Drop for ListenerWorker {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
+
+fn run(
+  listener: Arc<dyn LogListener + UnwindSafe + RefUnwindSafe>,
+  receiver: Receiver<WorkerMessage>,
+  interner: &StringInterner,
+  batching: Batching,
+  stats: &DeliveryStats,
+) {
+  let mut batch: Vec<LogEvent> = Vec::with_capacity(batching.max_events);
+  let mut payloads: Vec<KvPin> = Vec::with_capacity(batching.max_events);
+  let mut acks = Vec::new();
+
+  while let Ok(first) = receiver.recv() {
+    let deadline = std::time::Instant::now() + batching.max_wait;
+    let mut next = Some(first);
+    while let Some(message) = next.take() {
+      match message {
+        WorkerMessage::Event(event, kv) => {
+          batch.push(event);
+          payloads.extend(kv);
+        },
+        WorkerMessage::Flush(ack) => acks.push(ack),
+      }
+      if acks.is_empty() && batch.len() < batching.max_events {
+        next = receiver.recv_deadline(deadline).ok();
+      }
+    }
+
+    if !batch.is_empty() {
+      let ok = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        listener.handle_batch(&batch, interner);
+      }))
+      .is_ok();
+      if !ok {
+        eprintln!("[Trace] Listener panicked on its worker thread");
+      }
+      stats.record(batch.len(), ok);
+      batch.clear();
+      payloads.clear();
+    }
+    for ack in acks.drain(..) {
+      let _ = ack.send(());
+    }
+  }
+
+  listener.on_shutdown();
+}
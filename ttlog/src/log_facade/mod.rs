@@ -0,0 +1,156 @@
+use std::num::NonZeroU32;
+
+use log::kv::{self, Key, VisitSource};
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::event::LogLevel;
+use crate::format;
+use crate::kv_codec::FieldEncoder;
+use crate::string_interner::StringInterner;
+use crate::trace;
+use crate::utils;
+
+/// `log::Log` backend that turns `log::Record`s into ttlog events on the
+/// current [`Trace`](crate::trace::Trace): the one scoped on the logging
+/// thread, else the global one.
+///
+/// Records are filtered against `Trace::level` and the target directives
+/// (read on every call, so `Trace::set_level` and `Trace::set_directives`
+/// apply immediately) and against optional per-module maximum levels.
+/// Target and file are interned; `log` key-values are recorded as KV.
+///
+/// Static messages (`log::info!("started")`) are interned once. Formatted ones
+/// are stored in the KV payload under [`format::ARGS_KEY`] with the
+/// [`format::FORMATTED_TEMPLATE`] template, so distinct texts do not grow the
+/// interner.
+#[derive(Debug, Default, Clone)]
+pub struct TtlogLogger {
+  /// `(module prefix, max level)`, longest prefix first.
+  module_levels: Vec<(String, LevelFilter)>,
+}
+
+impl TtlogLogger {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Cap the verbosity of records whose target is `module` or one of its
+  /// submodules (`module::*`). The most specific prefix wins.
+  pub fn with_module_level(mut self, module: impl Into<String>, level: LevelFilter) -> Self {
+    let module = module.into();
+    self
+      .module_levels
+      .retain(|(existing, _)| *existing != module);
+    self.module_levels.push((module, level));
+    self
+      .module_levels
+      .sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+    self
+  }
+
+  /// Install as the process-wide `log` logger.
+  ///
+  /// The `log` max level is opened up completely; filtering happens against
+  /// the live `Trace` level so it can change at runtime.
+  pub fn init(self) -> Result<(), log::SetLoggerError> {
+    log::set_boxed_logger(Box::new(self))?;
+    log::set_max_level(LevelFilter::Trace);
+    Ok(())
+  }
+
+  fn module_level(&self, target: &str) -> LevelFilter {
+    self
+      .module_levels
+      .iter()
+      .find(|(module, _)| utils::target_matches(target, module))
+      .map(|(_, level)| *level)
+      .unwrap_or(LevelFilter::Trace)
+  }
+}
+
+struct KvVisitor<'a> {
+  interner: &'a StringInterner,
+  fields: FieldEncoder,
+}
+
+impl<'kvs> VisitSource<'kvs> for KvVisitor<'_> {
+  fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+    let key_id = self.interner.intern_key(key.as_str());
+    let fields = &mut self.fields;
+    if let Some(v) = value.to_bool() {
+      fields.field(key_id, &v);
+    } else if let Some(v) = value.to_i64() {
+      fields.field(key_id, &v);
+    } else if let Some(v) = value.to_u64() {
+      fields.field(key_id, &v);
+    } else if let Some(v) = value.to_f64() {
+      fields.field(key_id, &v);
+    } else if let Some(v) = value.to_borrowed_str() {
+      fields.field(key_id, v);
+    } else {
+      fields.field(key_id, value.to_string().as_str());
+    }
+    Ok(())
+  }
+}
+
+impl Log for TtlogLogger {
+  fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+    let Some(logger) = trace::current() else {
+      return false;
+    };
+
+    let level = LogLevel::from_log_level(metadata.level());
+    logger.is_enabled(level as u8, metadata.target())
+      && metadata.level() <= self.module_level(metadata.target())
+  }
+
+  fn log(&self, record: &Record<'_>) {
+    if !self.enabled(record.metadata()) {
+      return;
+    }
+
+    let Some(logger) = trace::current() else {
+      return;
+    };
+
+    let interner = &logger.interner;
+    let target_id = interner.intern_target(record.target());
+    let file_id = interner.intern_file(record.file().unwrap_or(""));
+
+    let mut visitor = KvVisitor {
+      interner,
+      fields: FieldEncoder::new(),
+    };
+    let _ = record.key_values().visit(&mut visitor);
+    let mut kv = visitor.fields;
+
+    let message_id = match record.args().as_str() {
+      Some(message) => interner.intern_message(&format::escape(message)),
+      None => {
+        kv.field(
+          interner.intern_key(format::ARGS_KEY),
+          record.args().to_string().as_str(),
+        );
+        interner.intern_message(format::FORMATTED_TEMPLATE)
+      },
+    };
+    let kv_id = if kv.is_empty() {
+      None
+    } else {
+      NonZeroU32::new(interner.store_kv(kv.finish()))
+    };
+
+    logger.send_event_fast(
+      LogLevel::from_log_level(record.level()) as u8,
+      target_id,
+      NonZeroU32::new(message_id),
+      crate::thread_registry::current_id(),
+      file_id,
+      (record.line().unwrap_or(0), 0),
+      kv_id,
+    );
+  }
+
+  fn flush(&self) {}
+}
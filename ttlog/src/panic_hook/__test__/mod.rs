@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
   use crate::panic_hook::PanicHook;
+  use crate::snapshot::SnapshotTrigger;
   use crate::trace::Message;
   use crossbeam_channel::{bounded, Receiver, RecvTimeoutError};
   use std::{panic, thread, time::Duration};
@@ -288,8 +289,9 @@ mod tests {
 
     // The hook should have sent a SnapshotImmediate message
     match receiver.recv_timeout(Duration::from_secs(2)) {
-      Ok(Message::SnapshotImmediate(reason, _ack)) => {
+      Ok(Message::SnapshotImmediate(reason, trigger, _ack)) => {
         assert_eq!(reason, "panic");
+        assert_eq!(trigger, SnapshotTrigger::Panic);
       },
       Ok(Message::FlushAndExit) => {
         panic!("expected SnapshotImmediate, got FlushAndExit");
@@ -1,3 +1,4 @@
+use crate::snapshot::SnapshotTrigger;
 use crate::trace::Message;
 
 use chrono::Duration;
@@ -15,7 +16,11 @@ impl PanicHook {
       let (tx, rx) = std::sync::mpsc::channel();
 
       // Try to enqueue snapshot request
-      if let Err(e) = sender.try_send(Message::SnapshotImmediate("panic".to_string(), tx)) {
+      if let Err(e) = sender.try_send(Message::SnapshotImmediate(
+        "panic".to_string(),
+        SnapshotTrigger::Panic,
+        tx,
+      )) {
         eprintln!("[Panic] Unable to enqueue snapshot request: {:?}", e);
         return;
       }
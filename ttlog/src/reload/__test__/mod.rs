@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod __test__ {
+
+  use crate::config::{ConfigError, TtlogConfig};
+  use crate::reload::{check_listeners, restart_only_changes};
+
+  fn config(json: &str) -> TtlogConfig {
+    TtlogConfig::from_json_str(json).unwrap()
+  }
+
+  #[test]
+  fn listener_filters_may_change() {
+    let applied = config(r#"{ "listeners": [{ "type": "file", "path": "a.log" }] }"#);
+    let next =
+      config(r#"{ "listeners": [{ "type": "file", "path": "a.log", "level": "error" }] }"#);
+    assert!(check_listeners(&applied, &next).is_ok());
+  }
+
+  #[test]
+  fn listener_sinks_may_not_change() {
+    let applied = config(r#"{ "listeners": [{ "type": "file", "path": "a.log" }] }"#);
+    let moved = config(r#"{ "listeners": [{ "type": "file", "path": "b.log" }] }"#);
+    let retyped = config(r#"{ "listeners": [{ "type": "stdout" }] }"#);
+    let added =
+      config(r#"{ "listeners": [{ "type": "file", "path": "a.log" }, { "type": "stdout" }] }"#);
+
+    for next in [moved, retyped, added] {
+      assert!(matches!(
+        check_listeners(&applied, &next),
+        Err(ConfigError::Invalid(_))
+      ));
+    }
+  }
+
+  #[test]
+  fn restart_only_settings_are_detected() {
+    let applied = config(r#"{ "capacity": 10, "level": "info" }"#);
+    let next = config(r#"{ "capacity": 20, "level": "debug", "snapshots": { "panic": false } }"#);
+    assert_eq!(
+      restart_only_changes(&applied, &next),
+      vec!["capacity", "snapshots.panic"]
+    );
+  }
+}
@@ -0,0 +1,305 @@
+mod __test__;
+
+use std::io;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crossbeam_channel::{bounded, RecvTimeoutError, Sender};
+
+use crate::config::{ConfigError, TtlogConfig};
+use crate::event::LogLevel;
+use crate::format;
+use crate::kv_codec::FieldEncoder;
+use crate::listener::ListenerFilter;
+use crate::snapshot::SnapshotWriter;
+use crate::trace::{ConfigUpdate, ListenerId, Trace};
+use crate::trace_builder::TraceBuilder;
+
+/// Target of the events reporting reloads.
+pub const TARGET: &str = "ttlog::reload";
+
+/// Default interval between checks of the config file's modification time.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Start the global logger from `path` (plus `TTLOG_*` overrides) and watch
+/// the file for changes, also reloading on `SIGHUP` on unix.
+pub fn init_from_file(path: impl AsRef<Path>) -> Result<(Trace, ConfigWatcher), ConfigError> {
+  let path = path.as_ref();
+  let mut config = TtlogConfig::from_file(path)?;
+  config.apply_env()?;
+
+  let (trace, listener_ids) = config
+    .clone()
+    .into_builder()?
+    .build_with_listener_ids()
+    .map_err(ConfigError::Trace)?;
+
+  let mut watcher = ConfigWatcher::new(&trace, path, config, listener_ids);
+  watcher.watch(DEFAULT_POLL_INTERVAL, true)?;
+  Ok((trace, watcher))
+}
+
+/// Shared between a [`ConfigWatcher`] and its watch thread.
+struct ReloadState {
+  trace: Trace,
+  path: PathBuf,
+  listener_ids: Vec<ListenerId>,
+  applied: Mutex<TtlogConfig>,
+}
+
+/// Re-reads a ttlog config file and applies it to a running [`Trace`].
+///
+/// Level directives, listener filters and snapshot settings (interval, modes,
+/// storage path and layout, service name) are applied to the running logger.
+/// Buffer and channel capacities and the panic/signal hooks are fixed at
+/// start. Listeners are matched by position and cannot be added, removed or
+/// re-targeted.
+///
+/// A reload is validated as a whole before anything is applied, so a broken
+/// file leaves the running configuration untouched, and is then applied in
+/// one step with [`Trace::apply_config`]. Outcomes are reported as
+/// ttlog events with target [`TARGET`]: `INFO` on success, `WARN` for
+/// settings that need a restart, `ERROR` when the file is rejected.
+pub struct ConfigWatcher {
+  state: Arc<ReloadState>,
+  stop: Option<Sender<()>>,
+  handle: Option<JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+  /// Track `path` for `trace`, which was built from `applied` and whose
+  /// configured listeners have `listener_ids` (see
+  /// [`TraceBuilder::build_with_listener_ids`]). Nothing is watched until
+  /// [`Self::watch`]; [`Self::reload`] works either way.
+  pub fn new(
+    trace: &Trace,
+    path: impl Into<PathBuf>,
+    applied: TtlogConfig,
+    listener_ids: Vec<ListenerId>,
+  ) -> Self {
+    Self {
+      state: Arc::new(ReloadState {
+        trace: trace.clone(),
+        path: path.into(),
+        listener_ids,
+        applied: Mutex::new(applied),
+      }),
+      stop: None,
+      handle: None,
+    }
+  }
+
+  /// Reload now. Errors are returned and also reported as an event.
+  pub fn reload(&self) -> Result<(), ConfigError> {
+    self.state.reload()
+  }
+
+  /// Start a background thread that reloads when the file's modification
+  /// time changes (checked every `poll_interval`) and, if `on_sighup` is set
+  /// on unix, when the process receives `SIGHUP`. Replaces a previous watch.
+  pub fn watch(&mut self, poll_interval: Duration, on_sighup: bool) -> io::Result<()> {
+    self.stop();
+
+    let sighup = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    let sighup_id = if on_sighup {
+      Some(signal_hook::flag::register(
+        signal_hook::consts::SIGHUP,
+        Arc::clone(&sighup),
+      )?)
+    } else {
+      None
+    };
+    #[cfg(not(unix))]
+    let _ = on_sighup;
+
+    let (stop_tx, stop_rx) = bounded::<()>(1);
+    let state = Arc::clone(&self.state);
+    let handle = thread::Builder::new()
+      .name("ttlog-reload".into())
+      .spawn(move || {
+        let mut last_modified = modified(&state.path);
+
+        while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(poll_interval) {
+          let current = modified(&state.path);
+          let file_changed = current.is_some() && current != last_modified;
+          if file_changed || sighup.swap(false, Ordering::Relaxed) {
+            last_modified = current;
+            let _ = state.reload();
+          }
+        }
+
+        #[cfg(unix)]
+        if let Some(id) = sighup_id {
+          signal_hook::low_level::unregister(id);
+        }
+      })?;
+
+    self.stop = Some(stop_tx);
+    self.handle = Some(handle);
+    Ok(())
+  }
+
+  /// Stop watching. Also done on drop.
+  pub fn stop(&mut self) {
+    if let Some(stop) = self.stop.take() {
+      let _ = stop.send(());
+    }
+    if let Some(handle) = self.handle.take() {
+      let _ = handle.join();
+    }
+  }
+}
+
+impl Drop for ConfigWatcher {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+  std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl ReloadState {
+  fn reload(&self) -> Result<(), ConfigError> {
+    let result = TtlogConfig::from_file(&self.path)
+      .and_then(|mut next| next.apply_env().map(|_| next))
+      .and_then(|next| self.apply(next));
+
+    if let Err(err) = &result {
+      report(
+        &self.trace,
+        LogLevel::ERROR,
+        "config reload failed",
+        &[
+          ("path", &self.path.display().to_string()),
+          ("error", &err.to_string()),
+        ],
+      );
+    }
+    result
+  }
+
+  fn apply(&self, next: TtlogConfig) -> Result<(), ConfigError> {
+    let mut applied = match self.applied.lock() {
+      Ok(applied) => applied,
+      Err(poisoned) => poisoned.into_inner(),
+    };
+
+    // Validate everything before touching the running logger.
+    let directives = next.resolved_directives()?;
+    let layout = next.storage_layout()?;
+    let modes = next.snapshot_modes()?;
+    check_listeners(&applied, &next)?;
+    let filters = next
+      .listeners
+      .iter()
+      .map(|listener| listener.filter().build())
+      .collect::<Result<Vec<ListenerFilter>, _>>()?;
+
+    let defaults = TraceBuilder::default();
+    let writer = SnapshotWriter::with_storage_path(
+      next.service_name.clone().unwrap_or(defaults.service_name),
+      next.storage.path.clone().unwrap_or(defaults.storage_path),
+    )
+    .with_layout(layout);
+    let interval = next
+      .snapshot_interval()
+      .unwrap_or(defaults.snapshot_interval);
+
+    self.trace.apply_config(ConfigUpdate {
+      directives,
+      filters: self.listener_ids.iter().copied().zip(filters).collect(),
+      snapshot_interval: interval,
+      snapshot_modes: modes,
+      snapshot_writer: writer,
+    });
+
+    for setting in restart_only_changes(&applied, &next) {
+      report(
+        &self.trace,
+        LogLevel::WARN,
+        "config setting changed; takes effect after a restart",
+        &[("setting", setting)],
+      );
+    }
+    report(
+      &self.trace,
+      LogLevel::INFO,
+      "config reloaded",
+      &[("path", &self.path.display().to_string())],
+    );
+
+    *applied = next;
+    Ok(())
+  }
+}
+
+/// Listeners are matched to the running ones by position; only their filters
+/// may change.
+fn check_listeners(applied: &TtlogConfig, next: &TtlogConfig) -> Result<(), ConfigError> {
+  if applied.listeners.len() != next.listeners.len() {
+    return Err(ConfigError::Invalid(
+      "listeners cannot be added or removed by a reload".to_string(),
+    ));
+  }
+
+  for (index, (old, new)) in applied.listeners.iter().zip(&next.listeners).enumerate() {
+    if !old.same_sink(new) {
+      return Err(ConfigError::Invalid(format!(
+        "listener {}: only filters can change on reload",
+        index
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// Settings that differ between `applied` and `next` but are fixed at start.
+fn restart_only_changes(applied: &TtlogConfig, next: &TtlogConfig) -> Vec<&'static str> {
+  let mut changed = Vec::new();
+  if applied.capacity != next.capacity {
+    changed.push("capacity");
+  }
+  if applied.channel_capacity != next.channel_capacity {
+    changed.push("channel_capacity");
+  }
+  if applied.snapshots.panic != next.snapshots.panic {
+    changed.push("snapshots.panic");
+  }
+  if applied.snapshots.signals != next.snapshots.signals {
+    changed.push("snapshots.signals");
+  }
+  changed
+}
+
+fn report(trace: &Trace, level: LogLevel, message: &str, fields: &[(&str, &str)]) {
+  if !trace.is_enabled(level as u8, TARGET) {
+    return;
+  }
+
+  let interner = &trace.interner;
+  let target_id = interner.intern_target(TARGET);
+  let message_id = interner.intern_message(&format::escape(message));
+  let file_id = interner.intern_file(file!());
+  let mut kv = FieldEncoder::new();
+  for (key, value) in fields {
+    kv.field(interner.intern_key(key), *value);
+  }
+  let kv_id = NonZeroU32::new(interner.store_kv(kv.finish()));
+
+  trace.send_event_fast(
+    level as u8,
+    target_id,
+    NonZeroU32::new(message_id),
+    crate::thread_registry::current_id(),
+    file_id,
+    (line!(), 0),
+    kv_id,
+  );
+}
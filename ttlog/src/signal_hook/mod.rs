@@ -9,18 +9,22 @@ use signal_hook::{
 };
 use std::thread;
 
+use crate::snapshot::SnapshotTrigger;
 use crate::trace::Message;
 
 pub struct SignalHook {}
 
 impl SignalHook {
   pub fn install(sender: Sender<Message>) {
-    let mut signals = match Signals::new([
+    if let Err(e) = Self::try_install(sender) {
+      panic!("Failed to install signal handler: {}", e);
+    }
+  }
+
+  pub fn try_install(sender: Sender<Message>) -> std::io::Result<()> {
+    let mut signals = Signals::new([
       SIGINT, SIGTERM, SIGQUIT, SIGHUP, SIGABRT, SIGSEGV, SIGBUS, SIGILL, SIGFPE, SIGPIPE, SIGCHLD,
-    ]) {
-      Ok(s) => s,
-      Err(e) => panic!("Failed to install signal handler: {}", e),
-    };
+    ])?;
 
     thread::spawn(move || {
       for sig in signals.forever() {
@@ -41,6 +45,7 @@ impl SignalHook {
         // do cleanup or exit here if needed
       }
     });
+    Ok(())
   }
 
   fn signal_request_snapshot(sender: &Sender<Message>, info: &str) {
@@ -50,7 +55,11 @@ impl SignalHook {
     let (tx, rx) = std::sync::mpsc::channel();
 
     // Try to send snapshot request
-    if let Err(e) = sender.try_send(Message::SnapshotImmediate(info.to_string(), tx)) {
+    if let Err(e) = sender.try_send(Message::SnapshotImmediate(
+      info.to_string(),
+      SnapshotTrigger::Signal,
+      tx,
+    )) {
       eprintln!("[{}] Failed to enqueue snapshot request: {:?}", info, e);
       return;
     }
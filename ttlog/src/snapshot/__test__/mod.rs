@@ -1,13 +1,20 @@
 #[cfg(test)]
 mod __test__ {
 
+  use serde_json::json;
   use std::sync::Arc;
 
   use crate::event::{FieldValue, LogEvent, LogLevel};
   use crate::event_builder::EventBuilder;
+  use crate::kv::IntOrDe;
+  use crate::kv_codec::FieldEncoder;
   use crate::lf_buffer::LockFreeRingBuffer;
-  use crate::snapshot::{SnapShot, SnapshotWriter};
+  use crate::snapshot::{
+    ResolvedEvent, SnapShot, SnapshotMode, SnapshotModes, SnapshotTrigger, SnapshotWriter,
+    KV_VERSION,
+  };
   use crate::string_interner::StringInterner;
+  use crate::thread_registry;
 
   fn builder_with_ring(
     capacity: usize,
@@ -43,8 +50,8 @@ mod __test__ {
 
     let event1 = builder.build_fast(1000, LogLevel::INFO, "module1", "message1");
     let event2 = builder.build_fast(2000, LogLevel::ERROR, "module2", "message2");
-    ring.push(event1).unwrap();
-    ring.push(event2).unwrap();
+    ring.push_overwrite(event1);
+    ring.push_overwrite(event2);
 
     let snapshot = writer
       .create_snapshot(&mut ring, "test_reason", interner.clone())
@@ -64,6 +71,76 @@ mod __test__ {
     assert_eq!(snapshot.events[0].target, "module1");
   }
 
+  #[test]
+  fn test_create_snapshot_drains_by_default() {
+    let writer = SnapshotWriter::new("test_service");
+    let (mut ring, interner, builder) = builder_with_ring(10);
+    ring.push_overwrite(builder.build_fast(1000, LogLevel::INFO, "module", "message"));
+
+    assert!(writer
+      .create_snapshot(&mut ring, "first", interner.clone())
+      .is_some());
+    assert!(ring.is_empty());
+    assert!(writer
+      .create_snapshot(&mut ring, "second", interner)
+      .is_none());
+  }
+
+  #[test]
+  fn test_snapshot_reports_overwritten_events() {
+    let writer = SnapshotWriter::new("test_service");
+    let (mut ring, interner, builder) = builder_with_ring(2);
+    for _ in 0..5 {
+      ring.push_overwrite(builder.build_fast(1000, LogLevel::INFO, "module", "message"));
+    }
+
+    let snapshot = writer
+      .create_snapshot(&mut ring, "test_reason", interner)
+      .unwrap();
+    assert_eq!(snapshot.events.len(), 2);
+    assert_eq!(snapshot.overwritten, 3);
+  }
+
+  #[test]
+  fn test_peek_snapshot_leaves_events_in_ring() {
+    let writer = SnapshotWriter::new("test_service");
+    let (mut ring, interner, builder) = builder_with_ring(10);
+    ring.push_overwrite(builder.build_fast(1000, LogLevel::INFO, "module", "first"));
+    ring.push_overwrite(builder.build_fast(2000, LogLevel::INFO, "module", "second"));
+
+    let peeked = writer
+      .create_snapshot_with_mode(&mut ring, "debug", interner.clone(), SnapshotMode::Peek)
+      .unwrap();
+    assert_eq!(peeked.events.len(), 2);
+    assert_eq!(ring.len(), 2);
+
+    // A later crash snapshot still sees the same history.
+    let drained = writer
+      .create_snapshot(&mut ring, "panic", interner)
+      .unwrap();
+    let messages = |snapshot: &SnapShot| {
+      snapshot
+        .events
+        .iter()
+        .map(|event| event.message.clone())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(messages(&peeked), messages(&drained));
+    assert!(ring.is_empty());
+  }
+
+  #[test]
+  fn test_snapshot_modes_per_trigger() {
+    let mut modes = SnapshotModes::default();
+    assert_eq!(modes.get(SnapshotTrigger::Manual), SnapshotMode::Drain);
+
+    modes.set(SnapshotTrigger::Manual, SnapshotMode::Peek);
+    assert_eq!(modes.get(SnapshotTrigger::Manual), SnapshotMode::Peek);
+    assert_eq!(modes.get(SnapshotTrigger::Panic), SnapshotMode::Drain);
+    assert_eq!(modes.get(SnapshotTrigger::Signal), SnapshotMode::Drain);
+    assert_eq!(modes.get(SnapshotTrigger::Periodic), SnapshotMode::Drain);
+  }
+
   #[test]
   fn test_snapshot_serialization() {
     let snapshot = SnapShot {
@@ -73,6 +150,10 @@ mod __test__ {
       created_at: "20240101120000".to_string(),
       reason: "test_reason".to_string(),
       events: vec![],
+      kv_version: KV_VERSION,
+      clock_anchor_ns: 0,
+      threads: Vec::new(),
+      overwritten: 0,
     };
 
     let json = serde_json::to_string(&snapshot).expect("serialize");
@@ -104,7 +185,7 @@ mod __test__ {
       "Authentication failed",
       &fields,
     );
-    ring.push(event).unwrap();
+    ring.push_overwrite(event);
 
     let snapshot = writer
       .create_snapshot(&mut ring, "auth_failure", interner)
@@ -127,6 +208,10 @@ mod __test__ {
       created_at: "20240101000000".to_string(),
       reason: "clone_reason".to_string(),
       events: vec![],
+      kv_version: KV_VERSION,
+      clock_anchor_ns: 0,
+      threads: Vec::new(),
+      overwritten: 0,
     };
 
     let cloned = original.clone();
@@ -147,6 +232,10 @@ mod __test__ {
       created_at: "20240101111111".to_string(),
       reason: "debug_reason".to_string(),
       events: vec![],
+      kv_version: KV_VERSION,
+      clock_anchor_ns: 0,
+      threads: Vec::new(),
+      overwritten: 0,
     };
 
     let debug_str = format!("{:?}", snapshot);
@@ -162,12 +251,8 @@ mod __test__ {
     let (mut ring1, interner1, builder1) = builder_with_ring(5);
     let (mut ring2, interner2, builder2) = builder_with_ring(5);
 
-    ring1
-      .push(builder1.build_fast(1000, LogLevel::INFO, "module1", "first"))
-      .unwrap();
-    ring2
-      .push(builder2.build_fast(2000, LogLevel::ERROR, "module2", "second"))
-      .unwrap();
+    ring1.push_overwrite(builder1.build_fast(1000, LogLevel::INFO, "module1", "first"));
+    ring2.push_overwrite(builder2.build_fast(2000, LogLevel::ERROR, "module2", "second"));
 
     let snapshot1 = writer
       .create_snapshot(&mut ring1, "reason1", interner1)
@@ -196,7 +281,7 @@ mod __test__ {
         "test_module",
         &format!("message_{}", i),
       );
-      ring.push(event).unwrap();
+      ring.push_overwrite(event);
     }
 
     let snapshot = writer
@@ -208,4 +293,247 @@ mod __test__ {
     assert_eq!(snapshot.events[0].timestamp_millis(), 0);
     assert_eq!(snapshot.events[499].timestamp_millis(), 499);
   }
+
+  #[test]
+  fn test_snapshot_renders_format_args() {
+    let writer = SnapshotWriter::new("format_service");
+    let (mut ring, interner, builder) = builder_with_ring(4);
+
+    let mut event = builder.build_fast(0, LogLevel::INFO, "module", "user {} on {shard}");
+    let mut kv = FieldEncoder::new();
+    kv.field(interner.intern_key("shard"), "s1");
+    kv.field(interner.intern_key(crate::format::ARGS_KEY), &42);
+    event.kv_id = std::num::NonZeroU32::new(interner.store_kv(kv.finish()));
+    ring.push_overwrite(event);
+
+    let snapshot = writer.create_snapshot(&mut ring, "fmt", interner).unwrap();
+    let resolved = &snapshot.events[0];
+
+    assert_eq!(resolved.message, "user {} on {shard}");
+    assert_eq!(resolved.args, vec![serde_json::json!(42)]);
+    assert_eq!(resolved.kv, serde_json::json!({ "shard": "s1" }));
+    assert_eq!(resolved.rendered_message(), "user 42 on s1");
+  }
+
+  #[test]
+  fn test_resolved_event_without_args_deserializes() {
+    let json =
+      r#"{"packed_meta":0,"message":"m","target":"t","kv":{},"file":"f","position":[1,2]}"#;
+    let resolved: crate::snapshot::ResolvedEvent = serde_json::from_str(json).unwrap();
+    assert!(resolved.args.is_empty());
+    assert_eq!(resolved.rendered_message(), "m");
+  }
+
+  // ── Spans ──────────────────────────────────────────────────────────
+
+  fn span_event(span_id: u64, message: &str, kv: serde_json::Value) -> ResolvedEvent {
+    ResolvedEvent {
+      packed_meta: 0,
+      message: message.to_string(),
+      target: "app".to_string(),
+      kv,
+      file: "main.rs".to_string(),
+      position: (1, 1),
+      args: Vec::new(),
+      span_id,
+      timestamp_ns: 0,
+      seq: 0,
+    }
+  }
+
+  #[test]
+  fn test_snapshot_spans_rebuilds_tree() {
+    let events = vec![
+      span_event(
+        1,
+        "request",
+        json!({ "span.event": "enter", "span.parent": 0, "id": 7 }),
+      ),
+      span_event(
+        2,
+        "query",
+        json!({ "span.event": "enter", "span.parent": 1 }),
+      ),
+      span_event(2, "rows fetched", json!({})),
+      span_event(
+        2,
+        "query",
+        json!({ "span.event": "exit", "span.busy_ns": 5 }),
+      ),
+      span_event(
+        2,
+        "query",
+        json!({ "span.event": "close", "span.busy_ns": 5, "span.duration_ns": 9 }),
+      ),
+      span_event(0, "outside", json!({})),
+    ];
+    let snapshot = SnapShot {
+      service: "svc".to_string(),
+      hostname: "host".to_string(),
+      pid: 1,
+      created_at: String::new(),
+      reason: "test".to_string(),
+      events,
+      kv_version: KV_VERSION,
+      clock_anchor_ns: 0,
+      threads: Vec::new(),
+      overwritten: 0,
+    };
+
+    let spans = snapshot.spans();
+    assert_eq!(spans.len(), 2);
+
+    let request = &spans[&1];
+    assert_eq!(request.name, "request");
+    assert_eq!(request.parent_id, 0);
+    assert_eq!(request.children, vec![2]);
+    assert_eq!(request.fields.get("id"), Some(&json!(7)));
+    assert!(!request.closed);
+
+    let query = &spans[&2];
+    assert_eq!(query.parent_id, 1);
+    assert_eq!(query.events, vec![2]);
+    assert!(query.closed);
+    assert_eq!(query.busy_ns, Some(5));
+    assert_eq!(query.duration_ns, Some(9));
+  }
+
+  #[test]
+  fn test_snapshot_carries_span_id() {
+    let writer = SnapshotWriter::new("test_service");
+    let (mut ring, interner, builder) = builder_with_ring(10);
+
+    let mut event = builder.build_fast(1000, LogLevel::INFO, "module", "in span");
+    event.span_id = 42;
+    ring.push_overwrite(event);
+
+    let snapshot = writer
+      .create_snapshot(&mut ring, "test_reason", interner)
+      .unwrap();
+    assert_eq!(snapshot.events[0].span_id, 42);
+  }
+
+  #[test]
+  fn test_snapshot_orders_events_by_seq() {
+    let writer = SnapshotWriter::new("test_service");
+    let (mut ring, interner, builder) = builder_with_ring(10);
+
+    let first = builder.build_fast(1000, LogLevel::INFO, "module", "first");
+    let second = builder.build_fast(1000, LogLevel::INFO, "module", "second");
+    assert!(first.seq < second.seq);
+    ring.push_overwrite(second);
+    ring.push_overwrite(first);
+
+    let snapshot = writer
+      .create_snapshot(&mut ring, "test_reason", interner)
+      .unwrap();
+    assert_eq!(snapshot.events[0].message, "first");
+    assert_eq!(snapshot.events[1].message, "second");
+    assert!(snapshot.clock_anchor_ns > 0);
+  }
+
+  #[test]
+  fn test_snapshot_resolves_monotonic_timestamps() {
+    let writer = SnapshotWriter::new("test_service");
+    let (mut ring, interner, builder) = builder_with_ring(10);
+
+    let mut stamped = builder.build_fast(0, LogLevel::INFO, "module", "stamped");
+    stamped.mono_ns = 1_500;
+    ring.push_overwrite(stamped);
+    ring.push_overwrite(builder.build_fast(1000, LogLevel::INFO, "module", "legacy"));
+
+    let snapshot = writer
+      .create_snapshot(&mut ring, "test_reason", interner)
+      .unwrap();
+    assert_eq!(
+      snapshot.events[0].timestamp_nanos(),
+      snapshot.clock_anchor_ns + 1_500
+    );
+    assert_eq!(snapshot.events[1].timestamp_ns, 0);
+    assert_eq!(snapshot.events[1].timestamp_nanos(), 1_000_000_000);
+  }
+
+  #[test]
+  fn test_snapshot_records_logging_threads() {
+    let writer = SnapshotWriter::new("test_service");
+    let (mut ring, interner, builder) = builder_with_ring(10);
+
+    let thread_id = thread_registry::current_id();
+    let mut event = builder.build_fast(1000, LogLevel::INFO, "module", "threaded");
+    event.packed_meta = LogEvent::pack_meta(1000, LogLevel::INFO, thread_id);
+    ring.push_overwrite(event);
+    ring.push_overwrite(builder.build_fast(1000, LogLevel::INFO, "module", "unregistered"));
+
+    let snapshot = writer
+      .create_snapshot(&mut ring, "test_reason", interner)
+      .unwrap();
+    assert_eq!(snapshot.threads.len(), 1);
+    assert_eq!(snapshot.threads[0].id, thread_id);
+    assert_eq!(snapshot.events[0].thread_id(), thread_id);
+
+    let name = std::thread::current().name().unwrap().to_string();
+    assert!(snapshot.thread_label(thread_id).starts_with(&name));
+    assert_eq!(snapshot.thread_label(0), "t0");
+  }
+
+  #[test]
+  fn numeric_kvs_keep_their_type_through_cbor() {
+    let writer = SnapshotWriter::new("typed");
+    let (mut ring, interner, builder) = builder_with_ring(4);
+
+    let mut kv = FieldEncoder::new();
+    kv.field(interner.intern_key("big"), &u64::MAX)
+      .field(interner.intern_key("neg"), &i64::MIN)
+      .field(interner.intern_key("wide"), &(u64::MAX as u128 + 1))
+      .field(interner.intern_key("narrow"), &7i128)
+      .field(interner.intern_key("ratio"), &0.1f32);
+    let mut event = builder.build_fast(0, LogLevel::INFO, "module", "typed");
+    event.kv_id = std::num::NonZeroU32::new(interner.store_kv(kv.finish()));
+    ring.push_overwrite(event);
+
+    let snapshot = writer
+      .create_snapshot(&mut ring, "typed", interner)
+      .unwrap();
+    assert_eq!(snapshot.kv_version, KV_VERSION);
+
+    let bytes = serde_cbor::to_vec(&snapshot).unwrap();
+    let read: SnapShot = serde_cbor::from_slice(&bytes).unwrap();
+    let kv = &read.events[0].kv;
+    assert_eq!(kv["big"], json!(u64::MAX));
+    assert_eq!(kv["neg"], json!(i64::MIN));
+    assert_eq!(kv["wide"], json!("18446744073709551616"));
+    assert_eq!(kv["narrow"], json!(7));
+    assert_eq!(kv["ratio"].as_f64(), Some(0.1f32 as f64));
+  }
+
+  #[test]
+  fn snapshots_without_kv_version_still_load() {
+    let old = json!({
+      "service": "svc",
+      "hostname": "host",
+      "pid": 1,
+      "created_at": "20240101000000",
+      "reason": "old",
+      "events": [{
+        "packed_meta": 0,
+        "message": "m",
+        "target": "t",
+        "kv": { "count": "42" },
+        "file": "f.rs",
+        "position": [1, 1],
+      }],
+    });
+    let bytes = serde_cbor::to_vec(&old).unwrap();
+
+    let snapshot: SnapShot = serde_cbor::from_slice(&bytes).unwrap();
+    assert_eq!(snapshot.kv_version, 0);
+    assert_eq!(snapshot.clock_anchor_ns, 0);
+    assert!(snapshot.threads.is_empty());
+    assert_eq!(snapshot.thread_label(5), "t5");
+    assert_eq!(snapshot.events[0].seq, 0);
+
+    let count: IntOrDe<u64> =
+      serde_json::from_value(snapshot.events[0].kv["count"].clone()).unwrap();
+    assert_eq!(count.0, 42);
+  }
 }
@@ -4,13 +4,22 @@ use chrono::Utc;
 use lz4::block::{compress, CompressionMode};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::{self, File};
 use std::io::Write;
 use std::sync::Arc;
 
+use crate::clock;
 use crate::event::{LogEvent, LogLevel};
+use crate::format;
+use crate::kv_codec;
 use crate::lf_buffer::LockFreeRingBuffer as RingBuffer;
+use crate::span;
 use crate::string_interner::StringInterner;
+use crate::thread_registry::{self, ThreadInfo};
+
+/// [`SnapShot::kv_version`] of snapshots whose KV numbers keep their type.
+pub const KV_VERSION: u32 = 1;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SnapShot {
@@ -20,33 +29,299 @@ pub struct SnapShot {
   pub created_at: String,
   pub reason: String,
   pub events: Vec<ResolvedEvent>,
+  /// How KV values were encoded. 0 (snapshots written before the field
+  /// existed): macro KVs of type `i64`, `u64`, `f32` and `f64` are strings,
+  /// readable with [`crate::kv::IntOrDe`]. [`KV_VERSION`]: numbers are JSON
+  /// numbers, see [`crate::kv_codec::FieldEncoder`] for 128-bit values.
+  #[serde(default)]
+  pub kv_version: u32,
+  /// Wall-clock time, in nanoseconds since the Unix epoch, of the monotonic
+  /// clock's zero point (see [`crate::clock`]). 0 in older snapshots.
+  #[serde(default)]
+  pub clock_anchor_ns: u64,
+  /// Threads that logged the events, by the `thread_id` in their
+  /// `packed_meta`. Empty in older snapshots.
+  #[serde(default)]
+  pub threads: Vec<ThreadInfo>,
+  /// Events the ring buffer overwrote before any snapshot took them, since
+  /// the logger started. 0 in older snapshots.
+  #[serde(default)]
+  pub overwritten: u64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ResolvedEvent {
   pub packed_meta: u64,
+  /// Message template as written at the callsite; see [`Self::rendered_message`].
   pub message: String,
   pub target: String,
   pub kv: serde_json::Value,
   pub file: String,
   pub position: (u32, u32),
+  /// Positional format arguments captured by the macros, rendered lazily.
+  #[serde(default)]
+  pub args: Vec<serde_json::Value>,
+  /// Span the event was logged in, 0 outside any span.
+  #[serde(default)]
+  pub span_id: u64,
+  /// Nanoseconds since the Unix epoch, from the monotonic clock. Events that
+  /// were not stamped by it (and older snapshots) fall back to
+  /// [`Self::timestamp_millis`]; see [`Self::timestamp_nanos`].
+  #[serde(default)]
+  pub timestamp_ns: u64,
+  /// Process-wide sequence number; [`SnapShot::events`] is sorted by it.
+  /// 0 in older snapshots.
+  #[serde(default)]
+  pub seq: u64,
+}
+
+/// A span reconstructed from the lifecycle events in a [`SnapShot`].
+///
+/// Spans whose `enter` event was evicted from the ring buffer before the
+/// snapshot keep an empty name but still collect their events and children.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpanRecord {
+  pub id: u64,
+  pub parent_id: u64,
+  pub name: String,
+  pub target: String,
+  /// Fields given at span creation.
+  pub fields: serde_json::Map<String, serde_json::Value>,
+  pub closed: bool,
+  /// Total time spent inside the span; known once it is closed.
+  pub busy_ns: Option<u64>,
+  /// Lifetime from creation to close; known once it is closed.
+  pub duration_ns: Option<u64>,
+  /// Child span IDs in order of first appearance.
+  pub children: Vec<u64>,
+  /// Indices into [`SnapShot::events`] of the non-lifecycle events logged
+  /// directly inside this span.
+  pub events: Vec<usize>,
+}
+
+impl SnapShot {
+  /// Name and OS tid of thread `id`, e.g. `worker-3 (tid 12345)`, or `t<id>`
+  /// if the snapshot does not know it.
+  pub fn thread_label(&self, id: u8) -> String {
+    self
+      .threads
+      .iter()
+      .find(|thread| thread.id == id)
+      .map(ThreadInfo::label)
+      .unwrap_or_else(|| format!("t{}", id))
+  }
+
+  /// Rebuild the span call tree from the events in this snapshot, keyed by
+  /// span ID. Roots are the records whose `parent_id` is 0 or not present.
+  pub fn spans(&self) -> BTreeMap<u64, SpanRecord> {
+    let mut spans: BTreeMap<u64, SpanRecord> = BTreeMap::new();
+    let mut order: Vec<u64> = Vec::new();
+
+    for (index, event) in self.events.iter().enumerate() {
+      if event.span_id == 0 {
+        continue;
+      }
+
+      let record = spans.entry(event.span_id).or_insert_with(|| {
+        order.push(event.span_id);
+        SpanRecord {
+          id: event.span_id,
+          ..SpanRecord::default()
+        }
+      });
+
+      match event.kv.get(span::EVENT_KEY).and_then(|v| v.as_str()) {
+        Some("enter") => {
+          record.name = event.message.clone();
+          record.target = event.target.clone();
+          record.parent_id = event
+            .kv
+            .get(span::PARENT_KEY)
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+          if let Some(fields) = event.kv.as_object() {
+            record.fields = fields
+              .iter()
+              .filter(|(key, _)| !key.starts_with("span."))
+              .map(|(key, value)| (key.clone(), value.clone()))
+              .collect();
+          }
+        },
+        Some("close") => {
+          record.closed = true;
+          record.busy_ns = event.kv.get(span::BUSY_KEY).and_then(|v| v.as_u64());
+          record.duration_ns = event.kv.get(span::DURATION_KEY).and_then(|v| v.as_u64());
+        },
+        Some(_) => {},
+        None => record.events.push(index),
+      }
+    }
+
+    for id in order {
+      let parent_id = spans[&id].parent_id;
+      if let Some(parent) = spans.get_mut(&parent_id) {
+        if !parent.children.contains(&id) {
+          parent.children.push(id);
+        }
+      }
+    }
+
+    spans
+  }
 }
 
 impl ResolvedEvent {
+  /// Look up the interned strings of `event`. Returns `None` if one of its
+  /// IDs is unknown to `interner`. Events without a message (KV only) get an
+  /// empty one.
+  pub fn resolve(event: &LogEvent, interner: &StringInterner) -> Option<Self> {
+    let message = match event.message_id {
+      None => String::new(),
+      Some(id) => match interner.get_message(id.get()) {
+        Some(m) => m.to_string(),
+        None => {
+          eprintln!("[Trace] Unknown message id: {}", id);
+          return None;
+        },
+      },
+    };
+
+    let target = match interner.get_target(event.target_id) {
+      Some(t) => t.to_string(),
+      None => {
+        eprintln!("[Trace] Unknown target id: {}", event.target_id);
+        return None;
+      },
+    };
+
+    let kv_data = event
+      .kv_id
+      .and_then(|id| interner.get_kv(id.get()))
+      .and_then(|kv_bytes| kv_codec::decode(&kv_bytes, interner))
+      .unwrap_or_else(|| serde_json::json!({}));
+    let (kv_data, args) = format::split_args(kv_data);
+
+    let file = match interner.get_file(event.file_id) {
+      Some(f) => f.to_string(),
+      None => {
+        eprintln!("[Trace] Unknown file id: {}", event.file_id);
+        return None;
+      },
+    };
+
+    Some(ResolvedEvent {
+      packed_meta: event.packed_meta,
+      position: event.position,
+      file,
+      message,
+      target,
+      kv: kv_data,
+      args,
+      span_id: event.span_id,
+      timestamp_ns: match event.mono_ns {
+        0 => 0,
+        mono_ns => clock::unix_ns(mono_ns),
+      },
+      seq: event.seq,
+    })
+  }
+
   pub fn timestamp_millis(&self) -> u64 {
     LogEvent::unpack_meta(self.packed_meta).0
   }
 
+  /// Nanoseconds since the Unix epoch, at millisecond precision for events
+  /// without a monotonic timestamp.
+  pub fn timestamp_nanos(&self) -> u64 {
+    match self.timestamp_ns {
+      0 => self.timestamp_millis().saturating_mul(1_000_000),
+      ns => ns,
+    }
+  }
+
   pub fn level(&self) -> LogLevel {
     let (_, level, _) = LogEvent::unpack_meta(self.packed_meta);
     LogLevel::from_u8(&level)
   }
+
+  /// See [`SnapShot::thread_label`] for the thread behind it.
+  pub fn thread_id(&self) -> u8 {
+    LogEvent::unpack_meta(self.packed_meta).2
+  }
+
+  /// Message with its format arguments and `{name}` KV references filled in.
+  pub fn rendered_message(&self) -> Cow<'_, str> {
+    format::render(&self.message, &self.args, &self.kv)
+  }
+}
+
+/// Where snapshot files go below the storage path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageLayout {
+  /// `<storage_path>/ttlog-<pid>-<created_at>-<reason>.bin`
+  #[default]
+  Flat,
+  /// `<storage_path>/<service>/ttlog-<pid>-<created_at>-<reason>.bin`, for
+  /// several services sharing one storage path.
+  PerService,
+}
+
+/// Whether taking a snapshot removes the events from the ring buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnapshotMode {
+  /// Take the events out, so the next snapshot only has newer ones.
+  #[default]
+  Drain,
+  /// Copy the events and leave them in the ring, so a later snapshot (after
+  /// a panic, say) still has them.
+  Peek,
+}
+
+/// What asked for a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotTrigger {
+  Panic,
+  Signal,
+  Periodic,
+  /// [`crate::trace::Trace::request_snapshot`].
+  Manual,
+}
+
+/// The [`SnapshotMode`] used for each [`SnapshotTrigger`]; all `Drain` by
+/// default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SnapshotModes {
+  pub panic: SnapshotMode,
+  pub signal: SnapshotMode,
+  pub periodic: SnapshotMode,
+  pub manual: SnapshotMode,
+}
+
+impl SnapshotModes {
+  pub fn get(&self, trigger: SnapshotTrigger) -> SnapshotMode {
+    match trigger {
+      SnapshotTrigger::Panic => self.panic,
+      SnapshotTrigger::Signal => self.signal,
+      SnapshotTrigger::Periodic => self.periodic,
+      SnapshotTrigger::Manual => self.manual,
+    }
+  }
+
+  pub fn set(&mut self, trigger: SnapshotTrigger, mode: SnapshotMode) {
+    match trigger {
+      SnapshotTrigger::Panic => self.panic = mode,
+      SnapshotTrigger::Signal => self.signal = mode,
+      SnapshotTrigger::Periodic => self.periodic = mode,
+      SnapshotTrigger::Manual => self.manual = mode,
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
 pub struct SnapshotWriter {
   service: Cow<'static, str>,
   storage_path: Cow<'static, str>,
+  layout: StorageLayout,
 }
 
 impl SnapshotWriter {
@@ -58,6 +333,28 @@ impl SnapshotWriter {
     Self {
       service: Cow::Owned(service.into()),
       storage_path: Cow::Owned(storage_path.into()),
+      layout: StorageLayout::Flat,
+    }
+  }
+
+  pub fn with_layout(mut self, layout: StorageLayout) -> Self {
+    self.layout = layout;
+    self
+  }
+
+  /// Directory snapshot files are written to.
+  pub fn directory(&self) -> String {
+    let base = if self.storage_path.is_empty() {
+      "./tmp/"
+    } else {
+      self.storage_path.as_ref()
+    };
+
+    match self.layout {
+      StorageLayout::Flat => base.to_string(),
+      StorageLayout::PerService => {
+        format!("{}/{}", base.trim_end_matches('/'), self.service)
+      },
     }
   }
 
@@ -67,68 +364,40 @@ impl SnapshotWriter {
     reason: impl Into<String>,
     interner: Arc<StringInterner>,
   ) -> Option<SnapShot> {
-    let events: Vec<ResolvedEvent> = ring
-      .take_snapshot()
+    self.create_snapshot_with_mode(ring, reason, interner, SnapshotMode::Drain)
+  }
+
+  /// Like [`Self::create_snapshot`], leaving the events in `ring` with
+  /// [`SnapshotMode::Peek`].
+  pub fn create_snapshot_with_mode(
+    &self,
+    ring: &mut Arc<RingBuffer<LogEvent>>,
+    reason: impl Into<String>,
+    interner: Arc<StringInterner>,
+    mode: SnapshotMode,
+  ) -> Option<SnapShot> {
+    let raw = match mode {
+      SnapshotMode::Drain => ring.take_snapshot(),
+      SnapshotMode::Peek => ring.peek_snapshot(),
+    };
+    let mut events: Vec<ResolvedEvent> = raw
       .iter()
-      .filter_map(|event| {
-        // Try to get all required values, early return None if missing
-        let message = match event
-          .message_id
-          .and_then(|id| interner.get_message(id.get()))
-        {
-          Some(m) => m.to_string(),
-          None => {
-            eprintln!("[Trace] Unknown message id: {:?}", event.message_id);
-            return None;
-          },
-        };
-
-        let target = match interner.get_target(event.target_id) {
-          Some(t) => t.to_string(),
-          None => {
-            eprintln!("[Trace] Unknown target id: {}", event.target_id);
-            return None;
-          },
-        };
-
-        let kv = event.kv_id.and_then(|id| interner.get_kv(id.get()));
-        let kv_data = if let Some(kv_bytes) = kv {
-          if let Ok(kv_str) = std::str::from_utf8(&kv_bytes) {
-            if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(kv_str) {
-              serde_json::json!(&parsed)
-            } else {
-              serde_json::json!({})
-            }
-          } else {
-            serde_json::json!({})
-          }
-        } else {
-          serde_json::json!({})
-        };
-
-        let file = match interner.get_file(event.file_id) {
-          Some(f) => f.to_string(),
-          None => {
-            eprintln!("[Trace] Unknown file id: {}", event.file_id);
-            return None;
-          },
-        };
-
-        Some(ResolvedEvent {
-          packed_meta: event.packed_meta,
-          position: event.position,
-          file,
-          message,
-          target,
-          kv: kv_data,
-        })
-      })
+      .filter_map(|event| ResolvedEvent::resolve(event, &interner))
       .collect();
+    // Threads can reach the ring in a different order than they took their
+    // sequence numbers.
+    events.sort_by_key(|event| event.seq);
 
     if events.is_empty() {
       return None;
     }
 
+    let thread_ids: BTreeSet<u8> = events.iter().map(ResolvedEvent::thread_id).collect();
+    let threads = thread_ids
+      .into_iter()
+      .filter_map(thread_registry::lookup)
+      .collect();
+
     let hostname = gethostname::gethostname().to_string_lossy().into_owned();
     let pid = std::process::id();
     let created_at = Utc::now().format("%Y%m%d%H%M%S").to_string();
@@ -140,6 +409,10 @@ impl SnapshotWriter {
       created_at,
       reason: reason.into(),
       events,
+      kv_version: KV_VERSION,
+      clock_anchor_ns: clock::anchor_unix_ns(),
+      threads,
+      overwritten: ring.overwritten(),
     })
   }
 
@@ -149,12 +422,10 @@ impl SnapshotWriter {
     // Compress
     let compressed = compress(&cbor_buff, Some(CompressionMode::DEFAULT), true)?;
 
-    let path = if self.storage_path.is_empty() {
+    if self.storage_path.is_empty() {
       eprintln!("[Snapshot] No storage path set");
-      "./tmp/".to_string()
-    } else {
-      self.storage_path.to_string()
-    };
+    }
+    let path = self.directory();
 
     // Build filename and write atomically
     let filename = format!(
@@ -186,10 +457,20 @@ impl SnapshotWriter {
     reason: impl Into<String>,
     interner: Arc<StringInterner>,
   ) -> Result<(), Box<dyn std::error::Error>> {
-    if let Some(snapshot) = self.create_snapshot(ring, reason, interner) {
+    self.snapshot_and_write_with_mode(ring, reason, interner, SnapshotMode::Drain)
+  }
+
+  pub fn snapshot_and_write_with_mode(
+    &self,
+    ring: &mut Arc<RingBuffer<LogEvent>>,
+    reason: impl Into<String>,
+    interner: Arc<StringInterner>,
+    mode: SnapshotMode,
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(snapshot) = self.create_snapshot_with_mode(ring, reason, interner, mode) {
       self.write_snapshot(&snapshot)
     } else {
-      println!("[Snapshot] No events to snapshot");
+      eprintln!("[Snapshot] No events to snapshot");
       Ok(())
     }
   }
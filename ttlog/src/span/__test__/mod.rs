@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod __test__ {
+
+  use std::sync::{Arc, OnceLock};
+
+  use crossbeam_channel::{bounded, unbounded, Receiver};
+
+  use crate::event::LogEvent;
+  use crate::kv_codec::FieldEncoder;
+  use crate::lf_buffer::LockFreeRingBuffer;
+  use crate::span::{current_id, Span};
+  use crate::string_interner::StringInterner;
+  use crate::trace::{EventBroadcast, Trace};
+
+  /// A logger without threads; the event receiver is kept so emitting works.
+  fn logger() -> &'static Trace {
+    static LOGGER: OnceLock<(Trace, Receiver<EventBroadcast>)> = OnceLock::new();
+    &LOGGER
+      .get_or_init(|| {
+        let (msg_tx, _) = bounded(1);
+        let (listener_tx, _) = bounded(1);
+        let (event_tx, event_rx) = unbounded();
+        let trace = Trace::new(
+          msg_tx,
+          listener_tx,
+          event_tx,
+          Arc::new(StringInterner::new()),
+          Arc::new(LockFreeRingBuffer::<LogEvent>::new(1024)),
+        );
+        (trace, event_rx)
+      })
+      .0
+  }
+
+  fn span() -> Span {
+    Span::new(logger(), 2, 0, 0, 0, (1, 1), FieldEncoder::new())
+  }
+
+  // ── Identity ───────────────────────────────────────────────────────
+
+  #[test]
+  fn span_ids_are_unique_and_non_zero() {
+    let a = span();
+    let b = span();
+    assert_ne!(a.id(), 0);
+    assert_ne!(a.id(), b.id());
+  }
+
+  #[test]
+  fn disabled_span_has_no_id() {
+    let span = Span::none();
+    assert!(span.is_disabled());
+    assert_eq!(span.id(), 0);
+    assert_eq!(span.parent_id(), 0);
+  }
+
+  // ── Nesting ────────────────────────────────────────────────────────
+
+  #[test]
+  fn enter_sets_and_restores_current_span() {
+    assert_eq!(current_id(), 0);
+    let outer = span();
+    {
+      let _outer = outer.enter();
+      assert_eq!(current_id(), outer.id());
+
+      let inner = span();
+      assert_eq!(inner.parent_id(), outer.id());
+      inner.in_scope(|| assert_eq!(current_id(), inner.id()));
+
+      assert_eq!(current_id(), outer.id());
+    }
+    assert_eq!(current_id(), 0);
+  }
+
+  #[test]
+  fn root_span_has_no_parent() {
+    assert_eq!(span().parent_id(), 0);
+  }
+
+  #[test]
+  fn entering_disabled_span_keeps_enclosing_span() {
+    let outer = span();
+    let _outer = outer.enter();
+    let disabled = Span::none();
+    disabled.in_scope(|| assert_eq!(current_id(), outer.id()));
+    assert_eq!(current_id(), outer.id());
+  }
+
+  #[test]
+  fn current_span_is_per_thread() {
+    let outer = span();
+    let _outer = outer.enter();
+    let other = std::thread::spawn(current_id).join().unwrap();
+    assert_eq!(other, 0);
+  }
+}
@@ -0,0 +1,217 @@
+mod __test__;
+
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use crate::kv_codec::FieldEncoder;
+use crate::string_interner::CachedId;
+use crate::trace::Trace;
+
+/// KV key carrying the span lifecycle step: `"enter"`, `"exit"` or `"close"`.
+pub const EVENT_KEY: &str = "span.event";
+/// KV key carrying the parent span ID on `enter` events (0 for a root span).
+pub const PARENT_KEY: &str = "span.parent";
+/// KV key carrying the time spent inside the span, in nanoseconds. On `exit`
+/// it covers the last enter/exit pair, on `close` all of them.
+pub const BUSY_KEY: &str = "span.busy_ns";
+/// KV key carrying the span lifetime from creation to close, in nanoseconds.
+pub const DURATION_KEY: &str = "span.duration_ns";
+
+static NEXT_SPAN_ID: AtomicU64 = AtomicU64::new(1);
+
+static EVENT_KEY_ID: CachedId = CachedId::new();
+static PARENT_KEY_ID: CachedId = CachedId::new();
+static BUSY_KEY_ID: CachedId = CachedId::new();
+static DURATION_KEY_ID: CachedId = CachedId::new();
+
+thread_local! {
+  static CURRENT_SPAN: Cell<u64> = const { Cell::new(0) };
+}
+
+/// ID of the innermost span entered on this thread, 0 when outside any span.
+///
+/// `Trace::send_event_fast` stamps it on every event so snapshots can group
+/// events by span.
+#[inline]
+pub fn current_id() -> u64 {
+  CURRENT_SPAN.with(|current| current.get())
+}
+
+#[derive(Debug)]
+struct SpanInner {
+  logger: Trace,
+  id: u64,
+  parent_id: u64,
+  level: u8,
+  target_id: u32,
+  name_id: u32,
+  file_id: u32,
+  position: (u32, u32),
+  fields: FieldEncoder,
+  created: Instant,
+  busy_ns: AtomicU64,
+}
+
+/// A named region of execution created by the `span!` family of macros.
+///
+/// A span is inert until entered. [`Span::enter`] makes it the current span
+/// of the thread and emits an `enter` event; dropping the returned guard
+/// emits `exit` with the time spent inside. Dropping the span itself emits
+/// `close` with its total lifetime. The parent is the span that was current
+/// on the creating thread.
+///
+/// Span events are regular log events at the span's level whose message is
+/// the span name and whose KV carries the [`EVENT_KEY`], [`PARENT_KEY`],
+/// [`BUSY_KEY`] and [`DURATION_KEY`] fields. They go to the logger the span
+/// was created with.
+#[derive(Debug)]
+pub struct Span {
+  inner: Option<SpanInner>,
+}
+
+impl Span {
+  /// Create a span from callsite data interned in `logger`. `fields` holds
+  /// the span's KV fields, keyed by `logger.interner.intern_key` IDs, and is
+  /// repeated on every `enter` event. Normally called by the macros.
+  pub fn new(
+    logger: &Trace,
+    level: u8,
+    target_id: u32,
+    name_id: u32,
+    file_id: u32,
+    position: (u32, u32),
+    fields: FieldEncoder,
+  ) -> Self {
+    Self {
+      inner: Some(SpanInner {
+        logger: logger.clone(),
+        id: NEXT_SPAN_ID.fetch_add(1, Ordering::Relaxed),
+        parent_id: current_id(),
+        level,
+        target_id,
+        name_id,
+        file_id,
+        position,
+        fields,
+        created: Instant::now(),
+        busy_ns: AtomicU64::new(0),
+      }),
+    }
+  }
+
+  /// A disabled span (e.g. filtered by level). Entering it is a no-op and
+  /// events keep being attributed to the enclosing span.
+  pub const fn none() -> Self {
+    Self { inner: None }
+  }
+
+  /// Span ID, or 0 for a disabled span.
+  pub fn id(&self) -> u64 {
+    self.inner.as_ref().map_or(0, |inner| inner.id)
+  }
+
+  /// ID of the span that was current when this one was created.
+  pub fn parent_id(&self) -> u64 {
+    self.inner.as_ref().map_or(0, |inner| inner.parent_id)
+  }
+
+  pub fn is_disabled(&self) -> bool {
+    self.inner.is_none()
+  }
+
+  /// Enter the span on the current thread until the guard is dropped.
+  pub fn enter(&self) -> Entered<'_> {
+    let previous = match &self.inner {
+      Some(inner) => {
+        let mut kv = inner.fields.clone();
+        kv.field(inner.key_id(&EVENT_KEY_ID, EVENT_KEY), "enter");
+        kv.field(inner.key_id(&PARENT_KEY_ID, PARENT_KEY), &inner.parent_id);
+        inner.emit(kv);
+        CURRENT_SPAN.with(|current| current.replace(inner.id))
+      },
+      None => current_id(),
+    };
+
+    Entered {
+      span: self,
+      previous,
+      entered: Instant::now(),
+      _not_send: PhantomData,
+    }
+  }
+
+  /// Run `f` inside the span.
+  pub fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+    let _entered = self.enter();
+    f()
+  }
+}
+
+impl Drop for Span {
+  fn drop(&mut self) {
+    if let Some(inner) = &self.inner {
+      let mut kv = FieldEncoder::new();
+      kv.field(inner.key_id(&EVENT_KEY_ID, EVENT_KEY), "close");
+      kv.field(
+        inner.key_id(&BUSY_KEY_ID, BUSY_KEY),
+        &inner.busy_ns.load(Ordering::Relaxed),
+      );
+      kv.field(
+        inner.key_id(&DURATION_KEY_ID, DURATION_KEY),
+        &(inner.created.elapsed().as_nanos() as u64),
+      );
+      inner.emit(kv);
+    }
+  }
+}
+
+/// Guard returned by [`Span::enter`]; restores the previous span on drop.
+#[derive(Debug)]
+pub struct Entered<'a> {
+  span: &'a Span,
+  previous: u64,
+  entered: Instant,
+  /// The guard restores thread-local state, so it must stay on its thread.
+  _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for Entered<'_> {
+  fn drop(&mut self) {
+    CURRENT_SPAN.with(|current| current.set(self.previous));
+
+    if let Some(inner) = &self.span.inner {
+      let busy = self.entered.elapsed().as_nanos() as u64;
+      inner.busy_ns.fetch_add(busy, Ordering::Relaxed);
+
+      let mut kv = FieldEncoder::new();
+      kv.field(inner.key_id(&EVENT_KEY_ID, EVENT_KEY), "exit");
+      kv.field(inner.key_id(&BUSY_KEY_ID, BUSY_KEY), &busy);
+      inner.emit(kv);
+    }
+  }
+}
+
+impl SpanInner {
+  fn key_id(&self, cached: &CachedId, key: &str) -> u32 {
+    cached.get_or_intern(&self.logger.interner, |interner| interner.intern_key(key))
+  }
+
+  fn emit(&self, kv: FieldEncoder) {
+    let logger = &self.logger;
+    let kv_id = NonZeroU32::new(logger.interner.store_kv(kv.finish()));
+
+    logger.send_event_in_span(
+      self.level,
+      self.target_id,
+      NonZeroU32::new(self.name_id),
+      crate::thread_registry::current_id(),
+      self.file_id,
+      self.position,
+      kv_id,
+      self.id,
+    );
+  }
+}
@@ -2,6 +2,8 @@ use chrono::{DateTime, TimeZone, Utc};
 use std::io::{self, Write};
 
 use crate::event::LogEvent;
+use crate::format;
+use crate::kv_codec;
 use crate::listener::LogListener;
 use crate::string_interner::StringInterner;
 
@@ -36,72 +38,96 @@ impl LogListener for StdoutListener {
   fn handle(&self, event: &LogEvent, interner: &StringInterner) {
     if let Ok(mut buf) = self.buffer.try_lock() {
       buf.clear();
+      write_line(&mut buf, event, interner);
+      let _ = io::stdout().write_all(buf.as_bytes());
+    }
+  }
 
-      let target: String = {
-        let this = interner.get_target(event.target_id).map(|t| t.to_string());
-        match this {
-          Some(x) => x,
-          None => "".to_string(),
-        }
-      };
+  /// Format the whole batch under one lock and write it with one call.
+  fn handle_batch(&self, events: &[LogEvent], interner: &StringInterner) {
+    if let Ok(mut buf) = self.buffer.lock() {
+      buf.clear();
+      for event in events {
+        write_line(&mut buf, event, interner);
+      }
+      let _ = io::stdout().lock().write_all(buf.as_bytes());
+    }
+  }
+}
 
-      let message: String = match event.message_id {
-        Some(id) => {
-          let this = interner.get_message(id.get()).map(|arc| arc.to_string());
-          match this {
-            Some(x) => x,
-            None => "".to_string(),
-          }
-        },
-        None => "".to_string(),
-      };
+/// Append `event` to `buf` as one colored line.
+fn write_line(buf: &mut String, event: &LogEvent, interner: &StringInterner) {
+  let target: String = {
+    let this = interner.get_target(event.target_id).map(|t| t.to_string());
+    match this {
+      Some(x) => x,
+      None => "".to_string(),
+    }
+  };
 
-      let kv: String = match event.kv_id {
-        Some(kv_id) => match interner.get_kv(kv_id.get()) {
-          Some(kv_data) => match std::str::from_utf8(kv_data.as_slice()) {
-            Ok(s) => s.to_string(),
-            Err(_) => "".to_string(),
-          },
-          None => "".to_string(),
-        },
+  let message: String = match event.message_id {
+    Some(id) => {
+      let this = interner.get_message(id.get()).map(|arc| arc.to_string());
+      match this {
+        Some(x) => x,
         None => "".to_string(),
+      }
+    },
+    None => "".to_string(),
+  };
+
+  let kv_value: Option<serde_json::Value> = match event.kv_id {
+    Some(kv_id) => match interner.get_kv(kv_id.get()) {
+      Some(kv_data) => kv_codec::decode(&kv_data, interner),
+      None => None,
+    },
+    None => None,
+  };
+
+  let (kv, message): (String, String) = match kv_value {
+    Some(kv_value) => {
+      let (kv_value, args) = format::split_args(kv_value);
+      let message = format::render(&message, &args, &kv_value).into_owned();
+      let kv = match &kv_value {
+        serde_json::Value::Object(map) if map.is_empty() => "".to_string(),
+        other => other.to_string(),
       };
-
-      let (line, col) = event.position;
-
-      let ts_ms = event.timestamps();
-      let level = event.level();
-      let thread_id = event.thread_id();
-
-      let datetime: DateTime<Utc> =
-        DateTime::from_timestamp((ts_ms / 1000) as i64, ((ts_ms % 1000) * 1_000_000) as u32)
-          .unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap());
-
-      let level_colored = color_level(level.as_str());
-      let target_colored = format!("{}{}{}", MAGENTA, target, RESET);
-      let msg_colored = format!("{}{}{}", WHITE, message, RESET);
-      let kv_colored = format!("{}{}{}", BLUE, kv, RESET);
-
-      use std::fmt::Write;
-      let _ = writeln!(
-        buf,
-        "{time_color}[{time}]{reset} {level} {thread_color}t{tid}{reset} {target}:{line}:{col} {msg} {kv}",
-        time_color = GREEN,
-        reset = RESET,
-        level = level_colored,
-        thread_color = CYAN,
-        tid = thread_id,
-        time = datetime.format("%H:%M:%S%.3f"),
-        target = target_colored,
-        line = line,
-        col = col,
-        msg = msg_colored,
-        kv = kv_colored
-      );
-
-      let _ = io::stdout().write_all(buf.as_bytes());
-    }
-  }
+      (kv, message)
+    },
+    None => ("".to_string(), message),
+  };
+
+  let (line, col) = event.position;
+
+  let ts_ms = event.timestamps();
+  let level = event.level();
+  let thread_id = event.thread_id();
+
+  let datetime: DateTime<Utc> =
+    DateTime::from_timestamp((ts_ms / 1000) as i64, ((ts_ms % 1000) * 1_000_000) as u32)
+      .unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap());
+
+  let level_colored = color_level(level.as_str());
+  let target_colored = format!("{}{}{}", MAGENTA, target, RESET);
+  let msg_colored = format!("{}{}{}", WHITE, message, RESET);
+  let kv_colored = format!("{}{}{}", BLUE, kv, RESET);
+
+  use std::fmt::Write;
+  let _ = writeln!(
+    buf,
+    "{time_color}[{time}]{reset} {level} {thread_color}t{tid}{reset} {target}:{line}:{col} {msg} {kv}",
+    time_color = GREEN,
+    reset = RESET,
+    level = level_colored,
+    thread_color = CYAN,
+    tid = thread_id,
+    time = datetime.format("%H:%M:%S%.3f"),
+    target = target_colored,
+    line = line,
+    col = col,
+    msg = msg_colored,
+    kv = kv_colored
+  );
 }
 
 fn color_level(level: &str) -> String {
@@ -1,11 +1,14 @@
 #[cfg(test)]
 mod __test__ {
 
-  use smallvec::smallvec;
+  use proptest::prelude::*;
+  use smallvec::{smallvec, SmallVec};
+  use std::collections::HashMap;
   use std::sync::Arc;
   use std::thread;
 
-  use crate::string_interner::StringInterner;
+  use crate::kv_codec;
+  use crate::string_interner::{CachedId, StringInterner, EXHAUSTED_KEY, EXHAUSTED_PLACEHOLDER};
 
   #[test]
   fn test_intern_target_and_get() {
@@ -112,4 +115,189 @@ mod __test__ {
     let retrieved = interner.get_kv(id).unwrap();
     assert_eq!(retrieved.len(), payload.len());
   }
+
+  #[test]
+  fn thread_cache_does_not_leak_ids_between_interners() {
+    let first = StringInterner::new();
+    let second = StringInterner::new();
+
+    first.intern_target("padding");
+    let in_first = first.intern_target("shared");
+    let in_second = second.intern_target("shared");
+
+    assert_ne!(in_first, in_second);
+    assert_eq!(second.get_target(in_second).unwrap().as_ref(), "shared");
+    assert_eq!(first.intern_target("shared"), in_first);
+  }
+
+  #[test]
+  fn cached_id_is_tied_to_its_interner() {
+    let first = StringInterner::new();
+    let second = StringInterner::new();
+    second.intern_message("padding");
+    let cached = CachedId::new();
+
+    let id = cached.get_or_intern(&first, |interner| interner.intern_message("hello"));
+    assert_eq!(cached.get_or_intern(&first, |_| unreachable!("cached")), id);
+
+    let other = cached.get_or_intern(&second, |interner| interner.intern_message("hello"));
+    assert_eq!(second.get_message(other).unwrap().as_ref(), "hello");
+    assert_ne!(other, id);
+  }
+
+  #[test]
+  fn stored_kvs_bypass_the_kv_table() {
+    let interner = StringInterner::with_kv_slots(4);
+    let interned = interner.intern_kv(smallvec![b'{', b'}']);
+    let (_, _, kvs_before) = interner.stats();
+
+    let mut last = 0;
+    for i in 0..100u8 {
+      last = interner.store_kv(smallvec![i]);
+    }
+
+    assert_eq!(interner.stats().2, kvs_before);
+    assert_eq!(interner.get_kv(last).unwrap().as_slice(), &[99]);
+    assert_eq!(interner.get_kv(interned).unwrap().as_slice(), b"{}");
+  }
+
+  // ── Exhaustion ─────────────────────────────────────────────────────
+
+  #[test]
+  fn full_table_maps_new_values_to_placeholder() {
+    let interner = StringInterner::with_capacity_limit(4);
+
+    // Slots: empty, exhaustion warning, one free, placeholder.
+    let kept = interner.intern_message("kept");
+    let first = interner.intern_message("overflow 1");
+    let second = interner.intern_message("overflow 2");
+
+    assert_eq!(first, second);
+    assert_ne!(first, kept);
+    assert_eq!(interner.get_message(kept).unwrap().as_ref(), "kept");
+    assert_eq!(
+      interner.get_message(first).unwrap().as_ref(),
+      EXHAUSTED_PLACEHOLDER
+    );
+    assert_eq!(interner.intern_message("kept"), kept);
+    assert_eq!(interner.exhausted(), 2);
+  }
+
+  #[test]
+  fn exhaustion_is_reported_once() {
+    let interner = StringInterner::with_capacity_limit(3);
+    assert!(!interner.take_exhaustion());
+
+    interner.intern_message("a");
+    interner.intern_message("b");
+
+    assert!(interner.take_exhaustion());
+    assert!(!interner.take_exhaustion());
+    assert_eq!(interner.exhausted(), 2);
+  }
+
+  #[test]
+  fn full_kv_table_keeps_a_placeholder() {
+    let interner = StringInterner::with_capacity_limit(3);
+
+    interner.intern_kv(SmallVec::from_slice(br#"{"a":1}"#));
+    let id = interner.intern_kv(SmallVec::from_slice(br#"{"a":2}"#));
+    let stored = interner.get_kv(id).unwrap();
+    let value = kv_codec::decode(&stored, &interner).unwrap();
+
+    assert_eq!(value[EXHAUSTED_KEY], "exhausted");
+  }
+
+  // ── Hash collisions ────────────────────────────────────────────────
+
+  /// Every value lands in the same bucket.
+  fn constant_hash(_: &[u8]) -> u64 {
+    42
+  }
+
+  /// Only four buckets, so any handful of values collides.
+  fn tiny_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().map(|&b| b as u64).sum::<u64>() % 4
+  }
+
+  #[test]
+  fn colliding_strings_get_distinct_ids() {
+    let interner = StringInterner::with_hasher(constant_hash);
+
+    let a = interner.intern_message("first");
+    let b = interner.intern_message("second");
+    assert_ne!(a, b);
+
+    // Repeated lookups go through the thread cache, then the shared maps.
+    for _ in 0..3 {
+      assert_eq!(interner.intern_message("first"), a);
+      assert_eq!(interner.intern_message("second"), b);
+    }
+    assert_eq!(interner.get_message(a).unwrap().as_ref(), "first");
+    assert_eq!(interner.get_message(b).unwrap().as_ref(), "second");
+  }
+
+  #[test]
+  fn colliding_strings_from_other_threads_are_verified() {
+    let interner = Arc::new(StringInterner::with_hasher(constant_hash));
+    let a = interner.intern_target("alpha");
+
+    // A fresh thread has an empty cache and must compare the stored strings.
+    let remote = Arc::clone(&interner);
+    let (b, a_again) =
+      thread::spawn(move || (remote.intern_target("beta"), remote.intern_target("alpha")))
+        .join()
+        .unwrap();
+
+    assert_ne!(a, b);
+    assert_eq!(a_again, a);
+  }
+
+  #[test]
+  fn colliding_kv_payloads_get_distinct_ids() {
+    let interner = StringInterner::with_hasher(constant_hash);
+    let a = interner.intern_kv(smallvec![1, 2, 3]);
+    let b = interner.intern_kv(smallvec![3, 2, 1]);
+
+    assert_ne!(a, b);
+    assert_eq!(interner.get_kv(b).unwrap().as_slice(), &[3, 2, 1]);
+  }
+
+  proptest! {
+    #[test]
+    fn ids_identify_strings_under_collisions(
+      values in prop::collection::vec("[a-d]{0,3}", 1..64),
+    ) {
+      let interner = StringInterner::with_hasher(tiny_hash);
+      let mut seen: HashMap<String, u32> = HashMap::new();
+
+      for value in &values {
+        let id = interner.intern_message(value);
+        let stored = interner.get_message(id).unwrap();
+        prop_assert_eq!(stored.as_ref(), value.as_str());
+        prop_assert_eq!(*seen.entry(value.clone()).or_insert(id), id);
+      }
+
+      let distinct: std::collections::HashSet<u32> = seen.values().copied().collect();
+      prop_assert_eq!(distinct.len(), seen.len());
+    }
+
+    #[test]
+    fn kv_ids_identify_payloads_under_collisions(
+      values in prop::collection::vec(prop::collection::vec(0u8..4, 0..4), 1..64),
+    ) {
+      let interner = StringInterner::with_hasher(tiny_hash);
+      let mut seen: HashMap<Vec<u8>, u32> = HashMap::new();
+
+      for value in &values {
+        let id = interner.intern_kv(SmallVec::from_slice(value));
+        let stored = interner.get_kv(id).unwrap();
+        prop_assert_eq!(stored.as_slice(), value.as_slice());
+        prop_assert_eq!(*seen.entry(value.clone()).or_insert(id), id);
+      }
+
+      let distinct: std::collections::HashSet<u32> = seen.values().copied().collect();
+      prop_assert_eq!(distinct.len(), seen.len());
+    }
+  }
 }
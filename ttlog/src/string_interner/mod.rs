@@ -3,170 +3,297 @@ mod __test__;
 use std::{
   cell::UnsafeCell,
   collections::HashMap,
-  sync::atomic::{AtomicU16, Ordering},
+  sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
   sync::{Arc, RwLock},
 };
 
+use smallvec::SmallVec;
+
+use crate::kv_arena::{self, KvArena, KvPin};
+use crate::kv_codec::FieldEncoder;
+
+type KvBytes = SmallVec<[u8; 128]>;
+
+/// IDs of the values sharing one hash. Almost always a single entry.
+type Bucket = SmallVec<[u32; 1]>;
+
+/// A few recently interned values of one kind, replaced round-robin.
+///
+/// Entries keep the value itself so a hit is confirmed by comparing it, not
+/// just its hash.
 #[derive(Debug)]
-struct LocalCache {
-  target_cache: [(u64, u16); 64],
-  message_cache: [(u64, u16); 64],
-  file_cache: [(u64, u16); 64],
-  kv_cache: [(u64, u16); 64],
-  target_counter: u8,
-  message_counter: u8,
-  file_counter: u8,
-  kv_counter: u8,
+struct CacheSlots<T: ?Sized, const N: usize> {
+  slots: [Option<(u64, Arc<T>, u32)>; N],
+  next: usize,
 }
 
-impl LocalCache {
+impl<T: ?Sized + PartialEq, const N: usize> CacheSlots<T, N> {
   fn new() -> Self {
     Self {
-      target_cache: [(0, 0); 64],
-      message_cache: [(0, 0); 64],
-      file_cache: [(0, 0); 64],
-      kv_cache: [(0, 0); 64],
-      target_counter: 0,
-      message_counter: 0,
-      file_counter: 0,
-      kv_counter: 0,
+      slots: std::array::from_fn(|_| None),
+      next: 0,
     }
   }
 
-  fn get_target(&self, hash: u64) -> Option<u16> {
+  fn get(&self, hash: u64, value: &T) -> Option<u32> {
     self
-      .target_cache
+      .slots
       .iter()
-      .find(|(h, _)| *h == hash)
-      .map(|(_, id)| *id)
+      .flatten()
+      .find(|(h, cached, _)| *h == hash && **cached == *value)
+      .map(|(_, _, id)| *id)
   }
 
-  fn put_target(&mut self, hash: u64, id: u16) {
-    let idx = self.target_counter as usize % 8;
-    self.target_cache[idx] = (hash, id);
-    self.target_counter = self.target_counter.wrapping_add(1);
+  fn put(&mut self, hash: u64, value: Arc<T>, id: u32) {
+    self.slots[self.next % N] = Some((hash, value, id));
+    self.next = self.next.wrapping_add(1);
   }
+}
 
-  fn get_message(&self, hash: u64) -> Option<u16> {
-    self
-      .message_cache
-      .iter()
-      .find(|(h, _)| *h == hash)
-      .map(|(_, id)| *id)
+#[derive(Debug)]
+struct LocalCache {
+  /// Instance ID of the interner the cached IDs belong to.
+  owner: u32,
+  targets: CacheSlots<str, 8>,
+  messages: CacheSlots<str, 16>,
+  files: CacheSlots<str, 8>,
+  keys: CacheSlots<str, 16>,
+  kvs: CacheSlots<KvBytes, 8>,
+}
+
+impl LocalCache {
+  fn new() -> Self {
+    Self {
+      owner: 0,
+      targets: CacheSlots::new(),
+      messages: CacheSlots::new(),
+      files: CacheSlots::new(),
+      keys: CacheSlots::new(),
+      kvs: CacheSlots::new(),
+    }
   }
 
-  fn get_file(&self, hash: u64) -> Option<u16> {
-    self
-      .file_cache
-      .iter()
-      .find(|(h, _)| *h == hash)
-      .map(|(_, id)| *id)
+  /// Start over when a different interner is used on this thread.
+  fn claim(&mut self, owner: u32) {
+    if self.owner != owner {
+      *self = Self::new();
+      self.owner = owner;
+    }
+  }
+}
+
+thread_local! {
+    static LOCAL_CACHE: UnsafeCell<LocalCache> = UnsafeCell::new(LocalCache::new());
+}
+
+static NEXT_INSTANCE_ID: AtomicU32 = AtomicU32::new(1);
+
+/// An interned ID cached at a macro callsite.
+///
+/// The ID is tagged with the instance it came from, so a callsite shared by
+/// several loggers re-interns when it sees a different interner instead of
+/// handing out a foreign ID.
+#[derive(Debug)]
+pub struct CachedId {
+  /// `instance << 32 | id`; instance IDs start at 1, so 0 is empty.
+  state: AtomicU64,
+}
+
+impl CachedId {
+  pub const fn new() -> Self {
+    Self {
+      state: AtomicU64::new(0),
+    }
   }
 
-  fn put_message(&mut self, hash: u64, id: u16) {
-    let idx = self.message_counter as usize % 16;
-    self.message_cache[idx] = (hash, id);
-    self.message_counter = self.message_counter.wrapping_add(1);
+  /// The cached ID if it came from `interner`, else `intern(interner)`.
+  #[inline(always)]
+  pub fn get_or_intern(
+    &self,
+    interner: &StringInterner,
+    intern: impl FnOnce(&StringInterner) -> u32,
+  ) -> u32 {
+    let state = self.state.load(Ordering::Relaxed);
+    if (state >> 32) as u32 == interner.instance {
+      return state as u32;
+    }
+
+    let id = intern(interner);
+    self.state.store(
+      ((interner.instance as u64) << 32) | id as u64,
+      Ordering::Relaxed,
+    );
+    id
   }
+}
 
-  fn put_file(&mut self, hash: u64, id: u16) {
-    let idx = self.file_counter as usize % 8;
-    self.file_cache[idx] = (hash, id);
-    self.file_counter = self.file_counter.wrapping_add(1);
+impl Default for CachedId {
+  fn default() -> Self {
+    Self::new()
   }
+}
 
-  fn get_kv(&self, hash: u64) -> Option<u16> {
-    self
-      .kv_cache
-      .iter()
-      .find(|(h, _)| *h == hash)
-      .map(|(_, id)| *id)
+/// Interned values of one kind. Slot 0 holds an empty value so IDs start
+/// at 1 and fit `NonZeroU32`.
+#[derive(Debug)]
+struct Table<T: ?Sized> {
+  values: RwLock<Vec<Arc<T>>>,
+  // Hash -> IDs with that hash; lookups compare the stored values, so
+  // colliding values get distinct IDs.
+  lookup: RwLock<HashMap<u64, Bucket>>,
+  count: AtomicU32,
+  /// Maximum number of slots, including slot 0.
+  limit: u32,
+  /// Marker stored in the last slot once the table is full.
+  placeholder: fn() -> Arc<T>,
+}
+
+impl<T: ?Sized> Table<T> {
+  fn new(capacity: usize, limit: u32, empty: Arc<T>, placeholder: fn() -> Arc<T>) -> Self {
+    let mut values = Vec::with_capacity(capacity);
+    values.push(empty);
+
+    Self {
+      values: RwLock::new(values),
+      lookup: RwLock::new(HashMap::with_capacity(capacity)),
+      // start counters at 1, so the next real value gets id = 1
+      count: AtomicU32::new(1),
+      limit,
+      placeholder,
+    }
   }
 
-  fn put_kv(&mut self, hash: u64, id: u16) {
-    let idx = self.kv_counter as usize % 8;
-    self.kv_cache[idx] = (hash, id);
-    self.kv_counter = self.kv_counter.wrapping_add(1);
+  fn get(&self, id: u32) -> Option<Arc<T>> {
+    self.values.read().unwrap().get(id as usize).cloned()
   }
 }
 
-thread_local! {
-    static LOCAL_CACHE: UnsafeCell<LocalCache> = UnsafeCell::new(LocalCache::new());
+/// Text stored in place of strings that arrive after a table is full.
+pub const EXHAUSTED_PLACEHOLDER: &str = "<ttlog: interner exhausted>";
+
+/// Target and message of the warning logged when a table fills up. Interned
+/// up front so the warning itself is readable.
+pub const EXHAUSTED_TARGET: &str = "ttlog::interner";
+pub const EXHAUSTED_MESSAGE: &str =
+  "string interner exhausted; new values are logged as a placeholder";
+/// KV key of the placeholder payload (`"exhausted"`) and of the warning,
+/// where it carries [`StringInterner::exhausted`]. Interned first, so its ID
+/// is [`EXHAUSTED_KEY_ID`] in every interner.
+pub const EXHAUSTED_KEY: &str = "ttlog.interner";
+pub const EXHAUSTED_KEY_ID: u32 = 1;
+
+fn str_placeholder() -> Arc<str> {
+  Arc::from(EXHAUSTED_PLACEHOLDER)
+}
+
+fn kv_placeholder() -> Arc<KvBytes> {
+  let mut kv = FieldEncoder::new();
+  kv.field(EXHAUSTED_KEY_ID, "exhausted");
+  Arc::new(kv.finish())
 }
 
+/// Deduplicating store for targets, messages, files, KV keys and KV payloads,
+/// handing out `u32` IDs.
+///
+/// Per-event KV payloads, whose values are mostly unique, go through
+/// [`Self::store_kv`] into a bounded [`KvArena`] instead of the KV table;
+/// [`Self::intern_kv`] is for payloads that repeat. [`Self::get_kv`] resolves
+/// both.
+///
+/// Each kind holds up to the limit given to [`Self::with_capacity_limit`],
+/// which defaults to `u32::MAX` values and is at most `2^31` for interned KVs.
+/// Once a kind is full, the last ID is taken by a placeholder
+/// ([`EXHAUSTED_PLACEHOLDER`], or a KV object with a `ttlog.interner` key)
+/// that every further new value maps to, so events keep a visible marker
+/// instead of losing their text. [`Self::exhausted`] counts those values, and
+/// the owning `Trace` logs a warning the first time a kind fills up.
 #[derive(Debug)]
 pub struct StringInterner {
-  targets: RwLock<Vec<Arc<str>>>,
-  messages: RwLock<Vec<Arc<str>>>,
-  files: RwLock<Vec<Arc<str>>>,
-  kvs: RwLock<Vec<Arc<smallvec::SmallVec<[u8; 128]>>>>,
-
-  target_lookup: RwLock<HashMap<u64, u16>>,
-  message_lookup: RwLock<HashMap<u64, u16>>,
-  file_lookup: RwLock<HashMap<u64, u16>>,
-  kv_lookup: RwLock<HashMap<u64, u16>>,
-
-  target_count: AtomicU16,
-  message_count: AtomicU16,
-  file_count: AtomicU16,
-  kv_count: AtomicU16,
+  /// Process-wide unique ID of this interner.
+  instance: u32,
+  hasher: fn(&[u8]) -> u64,
+
+  targets: Table<str>,
+  messages: Table<str>,
+  files: Table<str>,
+  keys: Table<str>,
+  kvs: Table<KvBytes>,
+  payloads: KvArena,
+
+  exhausted: AtomicU64,
+  exhaustion_pending: AtomicBool,
 }
 
 impl StringInterner {
   pub fn new() -> Self {
-    let mut targets = Vec::with_capacity(256);
-    let mut messages = Vec::with_capacity(4096);
-    let mut files = Vec::with_capacity(512);
-    let mut kvs = Vec::with_capacity(512);
+    Self::build(fnv1a, u32::MAX, kv_arena::DEFAULT_SLOTS)
+  }
 
-    // Fill slot 0 with dummy values
-    targets.push(Arc::from(""));
-    messages.push(Arc::from(""));
-    files.push(Arc::from(""));
-    kvs.push(Arc::from(smallvec::SmallVec::new()));
+  /// Interner whose KV arena holds `slots` per-event payloads at once.
+  pub fn with_kv_slots(slots: usize) -> Self {
+    Self::build(fnv1a, u32::MAX, slots)
+  }
 
-    Self {
-      targets: RwLock::new(targets),
-      messages: RwLock::new(messages),
-      files: RwLock::new(files),
-      kvs: RwLock::new(kvs),
-
-      target_lookup: RwLock::new(HashMap::with_capacity(256)),
-      message_lookup: RwLock::new(HashMap::with_capacity(4096)),
-      file_lookup: RwLock::new(HashMap::with_capacity(512)),
-      kv_lookup: RwLock::new(HashMap::with_capacity(512)),
-
-      // start counters at 1, so the next real string gets id = 1
-      target_count: AtomicU16::new(1),
-      message_count: AtomicU16::new(1),
-      file_count: AtomicU16::new(1),
-      kv_count: AtomicU16::new(1),
-    }
+  /// Interner holding at most `limit` values of each kind, to bound memory.
+  /// The empty slot 0, the exhaustion warning and the placeholder count
+  /// towards it, so it is raised to at least 3.
+  pub fn with_capacity_limit(limit: u32) -> Self {
+    Self::build(fnv1a, limit.max(3), kv_arena::DEFAULT_SLOTS)
+  }
+
+  /// Interner using `hasher` instead of FNV-1a, to force collisions.
+  #[cfg(test)]
+  pub(crate) fn with_hasher(hasher: fn(&[u8]) -> u64) -> Self {
+    Self::build(hasher, u32::MAX, kv_arena::DEFAULT_SLOTS)
+  }
+
+  fn build(hasher: fn(&[u8]) -> u64, limit: u32, kv_slots: usize) -> Self {
+    let interner = Self {
+      instance: NEXT_INSTANCE_ID.fetch_add(1, Ordering::Relaxed),
+      hasher,
+      targets: Table::new(256, limit, Arc::from(""), str_placeholder),
+      messages: Table::new(4096, limit, Arc::from(""), str_placeholder),
+      files: Table::new(512, limit, Arc::from(""), str_placeholder),
+      keys: Table::new(256, limit, Arc::from(""), str_placeholder),
+      // Arena IDs take the upper half.
+      kvs: Table::new(
+        512,
+        limit.min(kv_arena::ARENA_BIT),
+        Arc::new(KvBytes::new()),
+        kv_placeholder,
+      ),
+      payloads: KvArena::new(kv_slots),
+      exhausted: AtomicU64::new(0),
+      exhaustion_pending: AtomicBool::new(false),
+    };
+    interner.intern_target(EXHAUSTED_TARGET);
+    interner.intern_message(EXHAUSTED_MESSAGE);
+    let key_id = interner.intern_key(EXHAUSTED_KEY);
+    debug_assert_eq!(key_id, EXHAUSTED_KEY_ID);
+    interner
   }
 
   #[inline]
-  pub fn intern_target(&self, string: &str) -> u16 {
-    let hash = self.fast_hash(string);
+  pub fn intern_target(&self, string: &str) -> u32 {
+    let hash = (self.hasher)(string.as_bytes());
 
     // Fast path: check thread-local cache first
     LOCAL_CACHE.with(|cache| {
       let cache_ptr = cache.get();
       unsafe {
-        if let Some(id) = (*cache_ptr).get_target(hash) {
+        (*cache_ptr).claim(self.instance);
+        if let Some(id) = (*cache_ptr).targets.get(hash, string) {
           return id;
         }
       }
 
       // Cache miss - use slower interning with locks
-      let id = self.intern_string_slow(
-        string,
-        &self.targets,
-        &self.target_lookup,
-        &self.target_count,
-      );
+      let (id, value) = self.intern_slow(&self.targets, hash, string, || Arc::from(string));
 
-      unsafe {
-        (*cache_ptr).put_target(hash, id);
+      if let Some(value) = value {
+        unsafe {
+          (*cache_ptr).targets.put(hash, value, id);
+        }
       }
 
       id
@@ -174,26 +301,24 @@ impl StringInterner {
   }
 
   #[inline]
-  pub fn intern_message(&self, string: &str) -> u16 {
-    let hash = self.fast_hash(string);
+  pub fn intern_message(&self, string: &str) -> u32 {
+    let hash = (self.hasher)(string.as_bytes());
 
     LOCAL_CACHE.with(|cache| {
       let cache_ptr = cache.get();
       unsafe {
-        if let Some(id) = (*cache_ptr).get_message(hash) {
+        (*cache_ptr).claim(self.instance);
+        if let Some(id) = (*cache_ptr).messages.get(hash, string) {
           return id;
         }
       }
 
-      let id = self.intern_string_slow(
-        string,
-        &self.messages,
-        &self.message_lookup,
-        &self.message_count,
-      );
+      let (id, value) = self.intern_slow(&self.messages, hash, string, || Arc::from(string));
 
-      unsafe {
-        (*cache_ptr).put_message(hash, id);
+      if let Some(value) = value {
+        unsafe {
+          (*cache_ptr).messages.put(hash, value, id);
+        }
       }
 
       id
@@ -201,220 +326,261 @@ impl StringInterner {
   }
 
   #[inline]
-  pub fn intern_file(&self, string: &str) -> u16 {
-    let hash = self.fast_hash(string);
+  pub fn intern_file(&self, string: &str) -> u32 {
+    let hash = (self.hasher)(string.as_bytes());
 
     LOCAL_CACHE.with(|cache| {
       let cache_ptr = cache.get();
       unsafe {
-        if let Some(id) = (*cache_ptr).get_file(hash) {
+        (*cache_ptr).claim(self.instance);
+        if let Some(id) = (*cache_ptr).files.get(hash, string) {
           return id;
         }
       }
 
-      let id = self.intern_string_slow(string, &self.files, &self.file_lookup, &self.file_count);
+      let (id, value) = self.intern_slow(&self.files, hash, string, || Arc::from(string));
 
-      unsafe {
-        (*cache_ptr).put_file(hash, id);
+      if let Some(value) = value {
+        unsafe {
+          (*cache_ptr).files.put(hash, value, id);
+        }
       }
 
       id
     })
   }
 
+  /// Intern a KV key (or another low-cardinality string value) for
+  /// [`crate::kv_codec::FieldEncoder`].
   #[inline]
-  pub fn intern_kv(&self, buf: smallvec::SmallVec<[u8; 128]>) -> u16 {
-    let hash = self.fast_hash_smallvec(&buf);
+  pub fn intern_key(&self, string: &str) -> u32 {
+    let hash = (self.hasher)(string.as_bytes());
 
     LOCAL_CACHE.with(|cache| {
       let cache_ptr = cache.get();
       unsafe {
-        if let Some(id) = (*cache_ptr).get_kv(hash) {
+        (*cache_ptr).claim(self.instance);
+        if let Some(id) = (*cache_ptr).keys.get(hash, string) {
           return id;
         }
       }
 
-      let id = self.intern_string_slow_smallvec(buf, &self.kvs, &self.kv_lookup, &self.kv_count);
+      let (id, value) = self.intern_slow(&self.keys, hash, string, || Arc::from(string));
 
-      unsafe {
-        (*cache_ptr).put_kv(hash, id);
+      if let Some(value) = value {
+        unsafe {
+          (*cache_ptr).keys.put(hash, value, id);
+        }
       }
 
       id
     })
   }
 
-  #[cold]
-  fn intern_string_slow_smallvec(
-    &self,
-    string: smallvec::SmallVec<[u8; 128]>,
-    storage: &RwLock<Vec<Arc<smallvec::SmallVec<[u8; 128]>>>>,
-    lookup: &RwLock<HashMap<u64, u16>>,
-    counter: &AtomicU16,
-  ) -> u16 {
-    let hash = self.fast_hash_smallvec(&string);
-
-    // Try read lock first - allows concurrent reads
-    if let Ok(lookup_guard) = lookup.read() {
-      if let Some(&id) = lookup_guard.get(&hash) {
-        return id;
-      }
-    }
-
-    // Need write lock for insertion
-    let mut lookup_guard = lookup.write().unwrap();
+  #[inline]
+  pub fn intern_kv(&self, buf: KvBytes) -> u32 {
+    let hash = (self.hasher)(&buf);
 
-    // Double-check after acquiring write lock (race condition protection)
-    if let Some(&id) = lookup_guard.get(&hash) {
-      return id;
-    }
+    LOCAL_CACHE.with(|cache| {
+      let cache_ptr = cache.get();
+      unsafe {
+        (*cache_ptr).claim(self.instance);
+        if let Some(id) = (*cache_ptr).kvs.get(hash, &buf) {
+          return id;
+        }
+      }
 
-    let mut storage_guard = storage.write().unwrap();
-    let id = storage_guard.len() as u16;
+      let (id, value) = self.intern_slow(&self.kvs, hash, &buf, || Arc::new(buf.clone()));
 
-    // Handle overflow case (extremely rare)
-    if id == u16::MAX {
-      return 0;
-    }
+      if let Some(value) = value {
+        unsafe {
+          (*cache_ptr).kvs.put(hash, value, id);
+        }
+      }
 
-    // Insert new string
-    storage_guard.push(Arc::from(string));
-    lookup_guard.insert(hash, id);
-    counter.store(id + 1, Ordering::Relaxed);
+      id
+    })
+  }
 
-    id
+  /// Store a per-event KV payload in the arena. Unlike [`Self::intern_kv`],
+  /// equal payloads get distinct IDs and old payloads are dropped once the
+  /// arena wraps around, so unique values do not accumulate. Returns 0, for
+  /// no KVs, if the arena slot it would take is pinned.
+  #[inline]
+  pub fn store_kv(&self, buf: KvBytes) -> u32 {
+    self.payloads.store(buf)
   }
 
+  /// Find `value` among the IDs stored under `hash`, or append it. Returns
+  /// the stored value for the thread cache, or `None` when the table is full
+  /// and the ID is the placeholder's.
   #[cold]
-  fn intern_string_slow(
+  fn intern_slow<T: ?Sized + PartialEq>(
     &self,
-    string: &str,
-    storage: &RwLock<Vec<Arc<str>>>,
-    lookup: &RwLock<HashMap<u64, u16>>,
-    counter: &AtomicU16,
-  ) -> u16 {
-    let hash = self.fast_hash(string);
-
-    // Try read lock first - allows concurrent reads
-    if let Ok(lookup_guard) = lookup.read() {
-      if let Some(&id) = lookup_guard.get(&hash) {
-        return id;
+    table: &Table<T>,
+    hash: u64,
+    value: &T,
+    to_arc: impl FnOnce() -> Arc<T>,
+  ) -> (u32, Option<Arc<T>>) {
+    fn find<T: ?Sized + PartialEq>(
+      bucket: &Bucket,
+      values: &[Arc<T>],
+      value: &T,
+    ) -> Option<(u32, Option<Arc<T>>)> {
+      bucket.iter().find_map(|&id| {
+        let stored = &values[id as usize];
+        (**stored == *value).then(|| (id, Some(Arc::clone(stored))))
+      })
+    }
+
+    // Try read locks first - allows concurrent reads
+    if let Ok(lookup_guard) = table.lookup.read() {
+      if let Some(bucket) = lookup_guard.get(&hash) {
+        if let Some(found) = find(bucket, &table.values.read().unwrap(), value) {
+          return found;
+        }
       }
     }
 
-    // Need write lock for insertion
-    let mut lookup_guard = lookup.write().unwrap();
+    // Need write locks for insertion, always taken lookup first
+    let mut lookup_guard = table.lookup.write().unwrap();
+    let mut values_guard = table.values.write().unwrap();
 
     // Double-check after acquiring write lock (race condition protection)
-    if let Some(&id) = lookup_guard.get(&hash) {
-      return id;
+    if let Some(found) = lookup_guard
+      .get(&hash)
+      .and_then(|bucket| find(bucket, &values_guard, value))
+    {
+      return found;
     }
 
-    let mut storage_guard = storage.write().unwrap();
-    let id = storage_guard.len() as u16;
-
-    // Handle overflow case (extremely rare)
-    if id == u16::MAX {
-      return 0;
+    // The last slot is reserved for the placeholder.
+    let id = values_guard.len() as u32;
+    if id >= table.limit - 1 {
+      let placeholder_id = table.limit - 1;
+      if id == placeholder_id {
+        values_guard.push((table.placeholder)());
+        table.count.store(table.limit, Ordering::Relaxed);
+        self.exhaustion_pending.store(true, Ordering::Relaxed);
+        eprintln!(
+          "[Trace] String interner exhausted after {} values; new values are stored as a placeholder",
+          placeholder_id - 1
+        );
+      }
+      self.exhausted.fetch_add(1, Ordering::Relaxed);
+      return (placeholder_id, None);
     }
 
-    // Insert new string
-    storage_guard.push(Arc::from(string));
-    lookup_guard.insert(hash, id);
-    counter.store(id + 1, Ordering::Relaxed);
+    // Insert new value
+    let stored = to_arc();
+    values_guard.push(Arc::clone(&stored));
+    lookup_guard.entry(hash).or_default().push(id);
+    table.count.store(id + 1, Ordering::Relaxed);
 
-    id
+    (id, Some(stored))
   }
 
-  pub fn get_file(&self, id: u16) -> Option<Arc<str>> {
-    self.files.read().unwrap().get(id as usize).cloned()
+  /// Process-wide unique ID of this interner.
+  pub fn instance_id(&self) -> u32 {
+    self.instance
   }
 
-  pub fn get_target(&self, id: u16) -> Option<Arc<str>> {
-    self.targets.read().unwrap().get(id as usize).cloned()
+  pub fn get_file(&self, id: u32) -> Option<Arc<str>> {
+    self.files.get(id)
   }
 
-  pub fn get_message(&self, id: u16) -> Option<Arc<str>> {
-    self.messages.read().unwrap().get(id as usize).cloned()
+  pub fn get_target(&self, id: u32) -> Option<Arc<str>> {
+    self.targets.get(id)
   }
 
-  pub fn get_kv(&self, id: u16) -> Option<Arc<smallvec::SmallVec<[u8; 128]>>> {
-    self.kvs.read().unwrap().get(id as usize).cloned()
+  pub fn get_message(&self, id: u32) -> Option<Arc<str>> {
+    self.messages.get(id)
   }
 
-  pub fn stats(&self) -> (usize, usize, usize) {
-    (
-      self.target_count.load(Ordering::Relaxed) as usize,
-      self.message_count.load(Ordering::Relaxed) as usize,
-      self.kv_count.load(Ordering::Relaxed) as usize,
-    )
+  pub fn get_key(&self, id: u32) -> Option<Arc<str>> {
+    self.keys.get(id)
   }
 
-  /// Optimized FNV-1a hash function.
-  ///
-  /// Processes input strings 8 bytes at a time for improved performance
-  /// over byte-by-byte hashing. Uses unaligned reads to handle arbitrary
-  /// string lengths efficiently.
-  ///
-  /// ## Algorithm
-  /// - Starts with FNV-1a offset basis: 0xcbf29ce484222325
-  /// - For each 8-byte chunk: hash ^= chunk; hash *= FNV_PRIME
-  /// - For remaining bytes: hash ^= byte; hash *= FNV_PRIME
-  ///
-  /// ## Safety
-  /// Uses `unsafe` for unaligned u64 reads, but this is safe because:
-  /// - Input comes from valid string slices
-  /// - `read_unaligned` handles arbitrary alignment
-  /// - Chunk size is validated by `chunks_exact(8)`
-  #[inline]
-  fn fast_hash_smallvec(&self, s: &smallvec::SmallVec<[u8; 128]>) -> u64 {
-    let mut hash = 0xcbf29ce484222325u64; // FNV offset basis
-    let bytes: &[u8] = s.as_slice();
-
-    // Process 8 bytes at a time
-    let chunks = bytes.chunks_exact(8);
-    let remainder = chunks.remainder();
-
-    for chunk in chunks {
-      // Convert 8-byte chunk into u64 (little endian for consistency)
-      let chunk_u64 = u64::from_le_bytes(chunk.try_into().unwrap());
-      hash ^= chunk_u64;
-      hash = hash.wrapping_mul(0x100000001b3);
+  /// Payload of an interned KV or, for arena IDs, of a stored one.
+  pub fn get_kv(&self, id: u32) -> Option<Arc<KvBytes>> {
+    if id & kv_arena::ARENA_BIT != 0 {
+      self.payloads.get(id)
+    } else {
+      self.kvs.get(id)
     }
+  }
 
-    // Process remaining bytes (0–7)
-    for &byte in remainder {
-      hash ^= byte as u64;
-      hash = hash.wrapping_mul(0x100000001b3);
-    }
+  /// The arena payload behind `id`, for an event still on its way to the
+  /// listeners: while the returned pin lives, the arena does not reuse its
+  /// slot. `None` for interned KVs, which are never evicted.
+  pub fn pin_kv(&self, id: u32) -> Option<KvPin> {
+    self.payloads.pin(id)
+  }
 
-    hash
+  /// Per-event KV payloads dropped because their arena slot was pinned.
+  pub fn kv_dropped(&self) -> u64 {
+    self.payloads.dropped()
   }
 
+  /// Number of per-event KV payloads the arena holds at once.
+  pub fn kv_slots(&self) -> usize {
+    self.payloads.capacity()
+  }
+
+  pub fn stats(&self) -> (usize, usize, usize) {
+    (
+      self.targets.count.load(Ordering::Relaxed) as usize,
+      self.messages.count.load(Ordering::Relaxed) as usize,
+      self.kvs.count.load(Ordering::Relaxed) as usize,
+    )
+  }
+
+  /// Number of values that were mapped to a placeholder because their kind
+  /// was full.
+  pub fn exhausted(&self) -> u64 {
+    self.exhausted.load(Ordering::Relaxed)
+  }
+
+  /// Whether a kind filled up since the last call. Used by `Trace` to log a
+  /// single warning per kind.
   #[inline]
-  fn fast_hash(&self, s: &str) -> u64 {
-    let mut hash = 0xcbf29ce484222325u64; // FNV offset basis
-    let bytes = s.as_bytes();
-
-    // Process 8 bytes at a time for better performance
-    let chunks = bytes.chunks_exact(8);
-    let remainder = chunks.remainder();
-
-    for chunk in chunks {
-      // SAFETY: chunk is guaranteed to be exactly 8 bytes by chunks_exact(8)
-      let chunk_u64 = unsafe { std::ptr::read_unaligned(chunk.as_ptr() as *const u64) };
-      hash ^= chunk_u64;
-      hash = hash.wrapping_mul(0x100000001b3); // FNV prime
-    }
+  pub fn take_exhaustion(&self) -> bool {
+    self.exhaustion_pending.load(Ordering::Relaxed)
+      && self.exhaustion_pending.swap(false, Ordering::Relaxed)
+  }
+}
 
-    // Process remaining bytes (0-7 bytes)
-    for &byte in remainder {
-      hash ^= byte as u64;
-      hash = hash.wrapping_mul(0x100000001b3);
-    }
+/// Optimized FNV-1a hash function.
+///
+/// Processes input 8 bytes at a time for improved performance over
+/// byte-by-byte hashing.
+///
+/// ## Algorithm
+/// - Starts with FNV-1a offset basis: 0xcbf29ce484222325
+/// - For each 8-byte chunk: hash ^= chunk; hash *= FNV_PRIME
+/// - For remaining bytes: hash ^= byte; hash *= FNV_PRIME
+#[inline]
+fn fnv1a(bytes: &[u8]) -> u64 {
+  let mut hash = 0xcbf29ce484222325u64; // FNV offset basis
+
+  // Process 8 bytes at a time for better performance
+  let chunks = bytes.chunks_exact(8);
+  let remainder = chunks.remainder();
+
+  for chunk in chunks {
+    // Convert 8-byte chunk into u64 (little endian for consistency)
+    let chunk_u64 = u64::from_le_bytes(chunk.try_into().unwrap());
+    hash ^= chunk_u64;
+    hash = hash.wrapping_mul(0x100000001b3); // FNV prime
+  }
 
-    hash
+  // Process remaining bytes (0-7 bytes)
+  for &byte in remainder {
+    hash ^= byte as u64;
+    hash = hash.wrapping_mul(0x100000001b3);
   }
+
+  hash
 }
 
 impl Default for StringInterner {
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod __test__ {
+
+  use serde_json::json;
+
+  use crate::event::{LogEvent, LogLevel};
+  use crate::snapshot::ResolvedEvent;
+  use crate::testing::{Captured, EventMatcher};
+
+  fn event(level: LogLevel, target: &str, message: &str, kv: serde_json::Value) -> ResolvedEvent {
+    ResolvedEvent {
+      packed_meta: LogEvent::pack_meta(0, level, 0),
+      message: message.to_string(),
+      target: target.to_string(),
+      kv,
+      file: "main.rs".to_string(),
+      position: (1, 1),
+      args: Vec::new(),
+      span_id: 0,
+      timestamp_ns: 0,
+      seq: 0,
+    }
+  }
+
+  // ── EventMatcher ───────────────────────────────────────────────────
+
+  #[test]
+  fn empty_matcher_matches_everything() {
+    let event = event(LogLevel::INFO, "app", "hello", json!({}));
+    assert!(EventMatcher::new().matches(&event));
+  }
+
+  #[test]
+  fn matcher_checks_level_target_and_kv() {
+    let event = event(
+      LogLevel::ERROR,
+      "app::db",
+      "query failed",
+      json!({ "user_id": 5, "table": "users" }),
+    );
+
+    let matcher = EventMatcher::new()
+      .level(LogLevel::ERROR)
+      .target("app")
+      .kv("user_id", 5)
+      .kv("table", "users");
+    assert!(matcher.matches(&event));
+
+    assert!(!matcher.clone().level(LogLevel::WARN).matches(&event));
+    assert!(!EventMatcher::new().target("app::http").matches(&event));
+    assert!(!EventMatcher::new().kv("user_id", "5").matches(&event));
+    assert!(!EventMatcher::new().kv("missing", 1).matches(&event));
+  }
+
+  #[test]
+  fn matcher_accepts_template_or_rendered_message() {
+    let mut event = event(
+      LogLevel::INFO,
+      "app",
+      "user {id} logged in",
+      json!({ "id": 7 }),
+    );
+    event.kv = json!({ "id": 7 });
+
+    assert!(EventMatcher::new()
+      .message("user {id} logged in")
+      .matches(&event));
+    assert!(EventMatcher::new()
+      .message("user 7 logged in")
+      .matches(&event));
+    assert!(!EventMatcher::new().message("user").matches(&event));
+  }
+
+  #[test]
+  fn matcher_display_lists_conditions() {
+    let matcher = EventMatcher::new()
+      .level(LogLevel::ERROR)
+      .message("boom")
+      .kv("code", 3);
+    assert_eq!(
+      matcher.to_string(),
+      r#"{level=ERROR, message="boom", code=3}"#
+    );
+    assert_eq!(EventMatcher::new().to_string(), "{any}");
+  }
+
+  // ── Captured ───────────────────────────────────────────────────────
+
+  #[test]
+  #[should_panic(expected = "expected an event matching {level=ERROR}")]
+  fn assert_logged_panics_when_missing() {
+    let captured = Captured {
+      events: vec![event(LogLevel::INFO, "app", "hello", json!({}))],
+    };
+    captured.assert_logged(&EventMatcher::new().level(LogLevel::ERROR));
+  }
+}
@@ -0,0 +1,301 @@
+mod __test__;
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::backpressure::OverflowPolicy;
+use crate::event::{LogEvent, LogLevel};
+use crate::listener::LogListener;
+use crate::snapshot::ResolvedEvent;
+use crate::string_interner::StringInterner;
+use crate::trace_builder::TraceBuilder;
+use crate::utils;
+
+/// Ring buffer and listener queue size of the logger behind [`capture`].
+const CAPTURE_CAPACITY: usize = 1024;
+
+/// Run `f` and return every event the macros logged on this thread meanwhile.
+///
+/// `f` runs with a private standalone [`Trace`](crate::trace::Trace) scoped
+/// to the current thread at `TRACE` level, so concurrent tests never see
+/// each other's events and the global logger is left alone. Events logged
+/// from threads spawned inside `f` go to those threads' current logger
+/// instead. Nothing is written to disk, and logging blocks rather than drop
+/// events while the listener catches up, so every event is captured with
+/// its KV fields.
+///
+/// ```ignore
+/// let logs = ttlog::testing::capture(|| charge(user));
+/// logs.assert_logged(&EventMatcher::new().level(LogLevel::ERROR).kv("user_id", 5));
+/// ```
+pub fn capture(f: impl FnOnce()) -> Captured {
+  let listener = Arc::new(CaptureListener::new());
+  let mut trace = match TraceBuilder::new()
+    .service_name("ttlog-capture")
+    .capacity(CAPTURE_CAPACITY)
+    .listener_queue_capacity(CAPTURE_CAPACITY)
+    .overflow_policy(OverflowPolicy::Block(Duration::from_secs(60)))
+    .level(LogLevel::TRACE)
+    .snapshot_interval(None)
+    .listener(listener.clone())
+    .build_standalone()
+  {
+    Ok(trace) => trace,
+    Err(e) => panic!("{}", e),
+  };
+
+  let result = panic::catch_unwind(AssertUnwindSafe(|| trace.in_scope(f)));
+
+  // An empty buffer keeps the shutdown from writing a final snapshot.
+  trace.snapshot_buffer.take_snapshot();
+  trace.shutdown();
+
+  if let Err(payload) = result {
+    panic::resume_unwind(payload);
+  }
+  listener.take()
+}
+
+/// Listener that keeps every event it receives, resolved, in memory.
+///
+/// Delivery is asynchronous; call
+/// [`Trace::flush_listeners`](crate::trace::Trace::flush_listeners) before
+/// inspecting the events of a running logger.
+#[derive(Debug, Default)]
+pub struct CaptureListener {
+  events: Mutex<Vec<ResolvedEvent>>,
+}
+
+impl CaptureListener {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Copy of the events received so far.
+  pub fn captured(&self) -> Captured {
+    Captured {
+      events: self.lock().clone(),
+    }
+  }
+
+  /// Remove and return the events received so far.
+  pub fn take(&self) -> Captured {
+    Captured {
+      events: std::mem::take(&mut *self.lock()),
+    }
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, Vec<ResolvedEvent>> {
+    match self.events.lock() {
+      Ok(events) => events,
+      Err(poisoned) => poisoned.into_inner(),
+    }
+  }
+}
+
+impl LogListener for CaptureListener {
+  fn handle(&self, event: &LogEvent, interner: &StringInterner) {
+    if let Some(event) = ResolvedEvent::resolve(event, interner) {
+      self.lock().push(event);
+    }
+  }
+}
+
+/// Events collected by [`capture`] or a [`CaptureListener`], in logging order.
+#[derive(Debug, Clone, Default)]
+pub struct Captured {
+  events: Vec<ResolvedEvent>,
+}
+
+impl Captured {
+  pub fn events(&self) -> &[ResolvedEvent] {
+    &self.events
+  }
+
+  pub fn len(&self) -> usize {
+    self.events.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.events.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &ResolvedEvent> {
+    self.events.iter()
+  }
+
+  /// Rendered messages, for quick equality checks.
+  pub fn messages(&self) -> Vec<String> {
+    self
+      .events
+      .iter()
+      .map(|event| event.rendered_message().into_owned())
+      .collect()
+  }
+
+  /// First event accepted by `matcher`.
+  pub fn find(&self, matcher: &EventMatcher) -> Option<&ResolvedEvent> {
+    self.events.iter().find(|event| matcher.matches(event))
+  }
+
+  /// Number of events accepted by `matcher`.
+  pub fn count(&self, matcher: &EventMatcher) -> usize {
+    self
+      .events
+      .iter()
+      .filter(|event| matcher.matches(event))
+      .count()
+  }
+
+  /// Panic, listing the captured events, unless an event matches.
+  #[track_caller]
+  pub fn assert_logged(&self, matcher: &EventMatcher) -> &ResolvedEvent {
+    match self.find(matcher) {
+      Some(event) => event,
+      None => panic!(
+        "expected an event matching {}, captured:\n{}",
+        matcher, self
+      ),
+    }
+  }
+
+  /// Panic if any event matches.
+  #[track_caller]
+  pub fn assert_not_logged(&self, matcher: &EventMatcher) {
+    if let Some(event) = self.find(matcher) {
+      panic!(
+        "expected no event matching {}, found: {}",
+        matcher,
+        DisplayEvent(event)
+      );
+    }
+  }
+}
+
+impl fmt::Display for Captured {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.events.is_empty() {
+      return write!(f, "  (no events)");
+    }
+    for event in &self.events {
+      writeln!(f, "  {}", DisplayEvent(event))?;
+    }
+    Ok(())
+  }
+}
+
+struct DisplayEvent<'a>(&'a ResolvedEvent);
+
+impl fmt::Display for DisplayEvent<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let event = self.0;
+    write!(
+      f,
+      "[{}] {}: {}",
+      event.level().as_str(),
+      event.target,
+      event.rendered_message()
+    )?;
+    if event.kv.as_object().is_some_and(|kv| !kv.is_empty()) {
+      write!(f, " {}", event.kv)?;
+    }
+    Ok(())
+  }
+}
+
+/// Conditions an event must meet, all optional: level, message, target and
+/// KV fields.
+///
+/// ```ignore
+/// EventMatcher::new()
+///   .level(LogLevel::ERROR)
+///   .message("payment failed")
+///   .kv("user_id", 5)
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EventMatcher {
+  level: Option<LogLevel>,
+  message: Option<String>,
+  target: Option<String>,
+  kv: Vec<(String, Value)>,
+}
+
+impl EventMatcher {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn level(mut self, level: LogLevel) -> Self {
+    self.level = Some(level);
+    self
+  }
+
+  /// Match the message template as written, or the rendered message.
+  pub fn message(mut self, message: impl Into<String>) -> Self {
+    self.message = Some(message.into());
+    self
+  }
+
+  /// Match `target` and its submodules, as with listener filters.
+  pub fn target(mut self, target: impl Into<String>) -> Self {
+    self.target = Some(target.into());
+    self
+  }
+
+  /// Require a KV field equal to `value`.
+  pub fn kv(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+    self.kv.push((key.into(), value.into()));
+    self
+  }
+
+  pub fn matches(&self, event: &ResolvedEvent) -> bool {
+    if self.level.is_some_and(|level| event.level() != level) {
+      return false;
+    }
+
+    if let Some(message) = &self.message {
+      if event.message != *message && event.rendered_message() != message.as_str() {
+        return false;
+      }
+    }
+
+    if let Some(target) = &self.target {
+      if !utils::target_matches(&event.target, target) {
+        return false;
+      }
+    }
+
+    self
+      .kv
+      .iter()
+      .all(|(key, value)| event.kv.get(key) == Some(value))
+  }
+}
+
+impl fmt::Display for EventMatcher {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut parts = Vec::new();
+    if let Some(level) = self.level {
+      parts.push(format!("level={}", level.as_str()));
+    }
+    if let Some(message) = &self.message {
+      parts.push(format!("message={:?}", message));
+    }
+    if let Some(target) = &self.target {
+      parts.push(format!("target={}", target));
+    }
+    for (key, value) in &self.kv {
+      parts.push(format!("{}={}", key, value));
+    }
+
+    if parts.is_empty() {
+      write!(f, "{{any}}")
+    } else {
+      write!(f, "{{{}}}", parts.join(", "))
+    }
+  }
+}
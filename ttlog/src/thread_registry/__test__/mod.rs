@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod __test__ {
+
+  use std::thread;
+
+  use crate::thread_registry::{self, Registry, ThreadInfo, MAX_THREADS, UNREGISTERED};
+
+  #[test]
+  fn ids_are_assigned_in_order() {
+    let mut registry = Registry::new();
+    assert_eq!(registry.register(Some("main".to_string()), Some(10)), 1);
+    assert_eq!(registry.register(None, None), 2);
+
+    let main = registry.get(1).unwrap();
+    assert_eq!(main.name.as_deref(), Some("main"));
+    assert_eq!(main.os_tid, Some(10));
+    assert!(registry.get(UNREGISTERED).is_none());
+    assert!(registry.get(3).is_none());
+  }
+
+  #[test]
+  fn released_ids_are_reused_only_when_the_table_is_full() {
+    let mut registry = Registry::new();
+    let first = registry.register(Some("first".to_string()), None);
+    registry.release(first);
+
+    for _ in 1..MAX_THREADS {
+      assert_ne!(registry.register(None, None), first);
+    }
+    assert_eq!(registry.get(first).unwrap().name.as_deref(), Some("first"));
+
+    assert_eq!(registry.register(Some("reuse".to_string()), None), first);
+    assert_eq!(registry.get(first).unwrap().name.as_deref(), Some("reuse"));
+    assert_eq!(registry.register(None, None), UNREGISTERED);
+  }
+
+  #[test]
+  fn releasing_twice_queues_the_id_once() {
+    let mut registry = Registry::new();
+    let id = registry.register(None, None);
+    registry.release(id);
+    registry.release(id);
+    registry.release(UNREGISTERED);
+    assert_eq!(registry.free.len(), 1);
+
+    for _ in 1..MAX_THREADS {
+      registry.register(None, None);
+    }
+    assert_eq!(registry.register(None, None), id);
+    registry.release(id);
+    assert_eq!(registry.free.len(), 1);
+  }
+
+  #[test]
+  fn running_out_of_ids_is_reported_once() {
+    let mut registry = Registry::new();
+    for _ in 0..MAX_THREADS {
+      assert_ne!(registry.register(None, None), UNREGISTERED);
+    }
+    assert!(!registry.exhausted);
+
+    assert_eq!(registry.register(None, None), UNREGISTERED);
+    assert!(registry.exhausted);
+    assert_eq!(registry.register(None, None), UNREGISTERED);
+  }
+
+  #[test]
+  fn current_id_is_stable_per_thread() {
+    let id = thread_registry::current_id();
+    assert_ne!(id, UNREGISTERED);
+    assert_eq!(thread_registry::current_id(), id);
+
+    let other = thread::Builder::new()
+      .name("registry-test".to_string())
+      .spawn(|| {
+        let id = thread_registry::current_id();
+        (id, thread_registry::lookup(id).unwrap())
+      })
+      .unwrap()
+      .join()
+      .unwrap();
+    assert_ne!(other.0, id);
+    assert_eq!(other.1.name.as_deref(), Some("registry-test"));
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn os_tid_is_recorded() {
+    let info = thread_registry::lookup(thread_registry::current_id()).unwrap();
+    assert!(info.os_tid.is_some_and(|tid| tid > 0));
+  }
+
+  #[test]
+  fn label_formats() {
+    let named = ThreadInfo {
+      id: 3,
+      name: Some("worker-3".to_string()),
+      os_tid: Some(12345),
+    };
+    assert_eq!(named.label(), "worker-3 (tid 12345)");
+
+    let unnamed = ThreadInfo {
+      id: 17,
+      name: None,
+      os_tid: None,
+    };
+    assert_eq!(unnamed.label(), "t17");
+  }
+}
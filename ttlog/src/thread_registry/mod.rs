@@ -0,0 +1,170 @@
+mod __test__;
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+/// Thread ID of events from threads that could not get one: all
+/// [`MAX_THREADS`] IDs were in use, or the thread was shutting down.
+pub const UNREGISTERED: u8 = 0;
+
+/// Number of live threads that can hold an ID at once.
+pub const MAX_THREADS: usize = u8::MAX as usize;
+
+/// What is known about the thread behind an event's `thread_id`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadInfo {
+  pub id: u8,
+  /// `std::thread::Thread::name`, if the thread was given one.
+  pub name: Option<String>,
+  /// Kernel thread ID, where the platform exposes it (Linux).
+  pub os_tid: Option<u64>,
+}
+
+impl ThreadInfo {
+  /// Display form used by the viewer and listeners, e.g. `worker-3 (tid
+  /// 12345)`, `t4 (tid 12345)` for unnamed threads or just `t4`.
+  pub fn label(&self) -> String {
+    let name = match &self.name {
+      Some(name) => name.clone(),
+      None => format!("t{}", self.id),
+    };
+    match self.os_tid {
+      Some(tid) => format!("{} (tid {})", name, tid),
+      None => name,
+    }
+  }
+}
+
+/// Table of small thread IDs.
+///
+/// IDs are handed out in order; the ID of an exited thread goes to the back
+/// of a free list and is only reused once every unused ID is taken, so its
+/// entry keeps describing the exited thread for as long as possible (events
+/// it logged may still sit in the ring buffer).
+#[derive(Debug)]
+pub struct Registry {
+  threads: Vec<Option<ThreadInfo>>,
+  free: VecDeque<u8>,
+  /// Bit `id` is set while `id` is in `free`.
+  queued: [u64; 4],
+  /// Whether running out of IDs has been reported.
+  exhausted: bool,
+}
+
+impl Default for Registry {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Registry {
+  pub fn new() -> Self {
+    Self {
+      threads: vec![None],
+      free: VecDeque::new(),
+      queued: [0; 4],
+      exhausted: false,
+    }
+  }
+
+  /// Assign an ID to a thread, [`UNREGISTERED`] if none is left.
+  pub fn register(&mut self, name: Option<String>, os_tid: Option<u64>) -> u8 {
+    let id = if self.threads.len() <= MAX_THREADS {
+      self.threads.push(None);
+      (self.threads.len() - 1) as u8
+    } else {
+      match self.free.pop_front() {
+        Some(id) => {
+          self.queued[id as usize / 64] &= !(1 << (id % 64));
+          id
+        },
+        None => {
+          if !std::mem::replace(&mut self.exhausted, true) {
+            eprintln!(
+              "[Trace] All {} thread IDs are in use; events from new threads are logged as thread {}",
+              MAX_THREADS, UNREGISTERED
+            );
+          }
+          return UNREGISTERED;
+        },
+      }
+    };
+
+    self.threads[id as usize] = Some(ThreadInfo { id, name, os_tid });
+    id
+  }
+
+  /// Make `id` available again. Its entry stays until the ID is reused.
+  pub fn release(&mut self, id: u8) {
+    let (word, bit) = (id as usize / 64, 1 << (id % 64));
+    if id != UNREGISTERED && self.queued[word] & bit == 0 {
+      self.queued[word] |= bit;
+      self.free.push_back(id);
+    }
+  }
+
+  pub fn get(&self, id: u8) -> Option<&ThreadInfo> {
+    self.threads.get(id as usize)?.as_ref()
+  }
+}
+
+fn registry() -> &'static Mutex<Registry> {
+  static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+  REGISTRY.get_or_init(|| Mutex::new(Registry::new()))
+}
+
+/// Holds the calling thread's ID and gives it back when the thread exits.
+struct Registration(u8);
+
+impl Registration {
+  fn new() -> Self {
+    let name = std::thread::current().name().map(str::to_string);
+    let id = registry()
+      .lock()
+      .map(|mut registry| registry.register(name, os_tid()))
+      .unwrap_or(UNREGISTERED);
+    Self(id)
+  }
+}
+
+impl Drop for Registration {
+  fn drop(&mut self) {
+    if let Ok(mut registry) = registry().lock() {
+      registry.release(self.0);
+    }
+  }
+}
+
+thread_local! {
+  static CURRENT: Registration = Registration::new();
+}
+
+/// Small, stable ID of the calling thread, registering it on first use.
+#[inline]
+pub fn current_id() -> u8 {
+  CURRENT
+    .try_with(|registration| registration.0)
+    .unwrap_or(UNREGISTERED)
+}
+
+/// Look up the thread currently (or most recently) holding `id`.
+pub fn lookup(id: u8) -> Option<ThreadInfo> {
+  registry().lock().ok()?.get(id).cloned()
+}
+
+#[cfg(target_os = "linux")]
+fn os_tid() -> Option<u64> {
+  // `/proc/thread-self` links to `<pid>/task/<tid>`.
+  std::fs::read_link("/proc/thread-self")
+    .ok()?
+    .file_name()?
+    .to_str()?
+    .parse()
+    .ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn os_tid() -> Option<u64> {
+  None
+}
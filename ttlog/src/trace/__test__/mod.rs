@@ -1,13 +1,17 @@
 #[cfg(test)]
 mod __test__ {
+  use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
   use std::sync::Arc;
 
   use crossbeam_channel::{bounded, unbounded};
 
+  use crate::directive::Directives;
   use crate::event::{LogEvent, LogLevel};
   use crate::lf_buffer::LockFreeRingBuffer;
+  use crate::listener::{ListenerFilter, LogListener};
+  use crate::snapshot::{SnapshotModes, SnapshotTrigger, SnapshotWriter};
   use crate::string_interner::StringInterner;
-  use crate::trace::{EventBroadcast, ListenerMessage, Message, Trace};
+  use crate::trace::{self, ConfigUpdate, EventBroadcast, ListenerMessage, Message, Trace};
 
   fn build_trace() -> Trace {
     let (msg_tx, _msg_rx) = bounded::<Message>(10);
@@ -51,10 +55,243 @@ mod __test__ {
   #[test]
   fn message_display_formats() {
     let (tx, _rx) = std::sync::mpsc::channel();
-    let d1 = format!("{}", Message::SnapshotImmediate("why".to_string(), tx));
+    let d1 = format!(
+      "{}",
+      Message::SnapshotImmediate("why".to_string(), SnapshotTrigger::Manual, tx,)
+    );
     assert_eq!(d1, "SnapshotImmediate: why");
 
     let d2 = format!("{}", Message::FlushAndExit);
     assert_eq!(d2, "FlushAndExit");
   }
+
+  // ── Listener registry ──────────────────────────────────────────────
+
+  #[derive(Default)]
+  struct CountingListener {
+    handled: AtomicUsize,
+    shut_down: AtomicBool,
+  }
+
+  impl LogListener for CountingListener {
+    fn handle(&self, _event: &LogEvent, _interner: &StringInterner) {
+      self.handled.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_shutdown(&self) {
+      self.shut_down.store(true, Ordering::SeqCst);
+    }
+  }
+
+  fn spawn_listener_loop() -> (Trace, std::thread::JoinHandle<()>) {
+    let (msg_tx, msg_rx) = bounded::<Message>(10);
+    let (listener_tx, listener_rx) = bounded::<ListenerMessage>(10);
+    let (event_tx, event_rx) = unbounded::<EventBroadcast>();
+    let interner = Arc::new(StringInterner::new());
+    let buffer = Arc::new(LockFreeRingBuffer::<LogEvent>::new(16));
+    let trace = Trace::new(
+      msg_tx,
+      listener_tx,
+      event_tx,
+      interner.clone(),
+      buffer.clone(),
+    );
+    let backpressure = trace.backpressure.clone();
+    let shared = trace::SharedConfig {
+      level: trace.level.clone(),
+      directives: trace.directives.clone(),
+      writer: trace.sender.clone(),
+    };
+    let handle = std::thread::spawn(move || {
+      // Snapshot updates from `apply_config` land here.
+      let _msg_rx = msg_rx;
+      Trace::listener_loop(
+        listener_rx,
+        event_rx,
+        interner,
+        buffer,
+        backpressure,
+        trace::Batching::default(),
+        shared,
+      )
+    });
+    (trace, handle)
+  }
+
+  fn log_one(trace: &Trace) {
+    trace.send_event_fast(LogLevel::ERROR as u8, 0, None, 0, 0, (0, 0), None);
+  }
+
+  #[test]
+  fn add_listener_returns_distinct_ids() {
+    let (trace, handle) = spawn_listener_loop();
+    let a = trace.add_listener(Arc::new(CountingListener::default()));
+    let b = trace.add_listener(Arc::new(CountingListener::default()));
+    assert_ne!(a, b);
+
+    let _ = trace.listener_sender.send(ListenerMessage::Shutdown);
+    handle.join().unwrap();
+  }
+
+  #[test]
+  fn remove_listener_delivers_pending_then_detaches() {
+    let (trace, handle) = spawn_listener_loop();
+    let listener = Arc::new(CountingListener::default());
+    let id = trace.add_listener(listener.clone());
+
+    for _ in 0..10 {
+      log_one(&trace);
+    }
+    assert!(trace.remove_listener(id));
+    assert_eq!(listener.handled.load(Ordering::SeqCst), 10);
+    assert!(listener.shut_down.load(Ordering::SeqCst));
+
+    log_one(&trace);
+    assert!(!trace.remove_listener(id));
+    assert_eq!(listener.handled.load(Ordering::SeqCst), 10);
+
+    let _ = trace.listener_sender.send(ListenerMessage::Shutdown);
+    handle.join().unwrap();
+  }
+
+  #[test]
+  fn replace_listener_swaps_listeners() {
+    let (trace, handle) = spawn_listener_loop();
+    let old = Arc::new(CountingListener::default());
+    let new = Arc::new(CountingListener::default());
+
+    let old_id = trace.add_listener(old.clone());
+    let new_id = trace.replace_listener(old_id, new.clone());
+    assert_ne!(old_id, new_id);
+    assert!(old.shut_down.load(Ordering::SeqCst));
+
+    log_one(&trace);
+    assert!(trace.remove_listener(new_id));
+    assert_eq!(old.handled.load(Ordering::SeqCst), 0);
+    assert_eq!(new.handled.load(Ordering::SeqCst), 1);
+
+    let _ = trace.listener_sender.send(ListenerMessage::Shutdown);
+    handle.join().unwrap();
+  }
+
+  #[test]
+  fn filtered_listener_only_sees_accepted_events() {
+    let (trace, handle) = spawn_listener_loop();
+    let all = Arc::new(CountingListener::default());
+    let errors = Arc::new(CountingListener::default());
+    let all_id = trace.add_listener(all.clone());
+    let errors_id = trace.add_listener_with_filter(
+      errors.clone(),
+      ListenerFilter::new().min_level(LogLevel::ERROR),
+    );
+
+    trace.send_event_fast(LogLevel::INFO as u8, 0, None, 0, 0, (0, 0), None);
+    log_one(&trace);
+
+    assert!(trace.remove_listener(all_id));
+    assert!(trace.remove_listener(errors_id));
+    assert_eq!(all.handled.load(Ordering::SeqCst), 2);
+    assert_eq!(errors.handled.load(Ordering::SeqCst), 1);
+
+    let _ = trace.listener_sender.send(ListenerMessage::Shutdown);
+    handle.join().unwrap();
+  }
+
+  #[test]
+  fn apply_config_keeps_queued_events_on_the_old_config() {
+    let (trace, handle) = spawn_listener_loop();
+    let listener = Arc::new(CountingListener::default());
+    let id = trace.add_listener_with_filter(
+      listener.clone(),
+      ListenerFilter::new().min_level(LogLevel::ERROR),
+    );
+
+    // Queued before the update, so still filtered out.
+    trace.send_event_fast(LogLevel::INFO as u8, 0, None, 0, 0, (0, 0), None);
+    let applied = trace.apply_config(ConfigUpdate {
+      directives: Directives::parse("info").unwrap(),
+      filters: vec![(id, ListenerFilter::new())],
+      snapshot_interval: None,
+      snapshot_modes: SnapshotModes::default(),
+      snapshot_writer: SnapshotWriter::new("apply_config"),
+    });
+    trace.send_event_fast(LogLevel::INFO as u8, 0, None, 0, 0, (0, 0), None);
+
+    assert!(applied);
+    assert_eq!(trace.get_level(), LogLevel::INFO);
+    assert!(trace.remove_listener(id));
+    assert_eq!(listener.handled.load(Ordering::SeqCst), 1);
+
+    let unknown = trace.apply_config(ConfigUpdate {
+      directives: Directives::parse("info").unwrap(),
+      filters: vec![(id, ListenerFilter::new())],
+      snapshot_interval: None,
+      snapshot_modes: SnapshotModes::default(),
+      snapshot_writer: SnapshotWriter::new("apply_config"),
+    });
+    assert!(!unknown);
+
+    let _ = trace.listener_sender.send(ListenerMessage::Shutdown);
+    handle.join().unwrap();
+  }
+
+  // ── Instances ──────────────────────────────────────────────────────
+
+  fn is_current(trace: &Trace) -> bool {
+    trace::current().is_some_and(|current| Arc::ptr_eq(&current.level, &trace.level))
+  }
+
+  #[test]
+  fn clones_share_the_level() {
+    let trace = build_trace();
+    let clone = trace.clone();
+    clone.set_level(LogLevel::ERROR);
+    assert_eq!(trace.get_level(), LogLevel::ERROR);
+  }
+
+  #[test]
+  fn scoped_loggers_nest_and_restore() {
+    let outer = build_trace();
+    let inner = build_trace();
+
+    {
+      let _outer = outer.scoped();
+      assert!(is_current(&outer));
+
+      inner.in_scope(|| {
+        assert!(is_current(&inner));
+        assert!(!is_current(&outer));
+      });
+      assert!(is_current(&outer));
+    }
+    assert!(!is_current(&outer));
+  }
+
+  #[test]
+  fn scoped_logger_is_thread_local() {
+    let trace = build_trace();
+    let _scoped = trace.scoped();
+    let level = Arc::clone(&trace.level);
+
+    let seen_elsewhere = std::thread::spawn(move || {
+      trace::current().is_some_and(|current| Arc::ptr_eq(&current.level, &level))
+    })
+    .join()
+    .unwrap();
+    assert!(!seen_elsewhere);
+  }
+
+  #[test]
+  fn dropping_a_clone_keeps_the_listener_thread_running() {
+    let (trace, handle) = spawn_listener_loop();
+    drop(trace.clone());
+
+    let listener = Arc::new(CountingListener::default());
+    trace.add_listener(listener.clone());
+    log_one(&trace);
+
+    let _ = trace.listener_sender.send(ListenerMessage::Shutdown);
+    handle.join().unwrap();
+    assert_eq!(listener.handled.load(Ordering::SeqCst), 1);
+  }
 }
@@ -1,44 +1,248 @@
 mod __test__;
 
-use chrono::Duration;
+use std::cell::RefCell;
+use std::marker::PhantomData;
 use std::num;
-use std::sync::OnceLock;
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::{OnceLock, RwLock};
+use std::time::Instant;
 use std::{sync::Arc, thread};
 
+use crate::backpressure::{self, Backpressure, DropStats, OverflowPolicy};
+use crate::clock;
+use crate::directive::{self, DirectiveParseError, Directives};
 use crate::event::{LogEvent, LogLevel};
+use crate::kv_arena::KvPin;
+use crate::kv_codec::FieldEncoder;
 use crate::lf_buffer::LockFreeRingBuffer;
-use crate::listener::LogListener;
+use crate::listener::{ListenerFilter, LogListener};
+use crate::listener_worker::{
+  DeliveryStats, ListenerHealth, ListenerWorker, WorkerOptions, WorkerRoom,
+};
 use crate::panic_hook::PanicHook;
-use crate::snapshot::SnapshotWriter;
-use crate::string_interner::StringInterner;
-use crossbeam_channel::{unbounded, Receiver, Sender};
-use std::sync::atomic::{self, AtomicU8, Ordering};
+use crate::snapshot::{SnapshotModes, SnapshotTrigger, SnapshotWriter};
+use crate::span;
+use crate::string_interner::{self, StringInterner};
+use crate::thread_registry;
+use crate::trace_builder::{TraceBuilder, TraceError};
+use crossbeam_channel::{Receiver, Sender};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
 
 #[derive(Debug)]
 pub enum Message {
-  SnapshotImmediate(String, std::sync::mpsc::Sender<()>),
+  /// Write a snapshot for `reason`, then ack.
+  SnapshotImmediate(String, SnapshotTrigger, std::sync::mpsc::Sender<()>),
+  /// Replace the periodic snapshot interval and the snapshot writer.
+  UpdateSnapshots(Option<std::time::Duration>, SnapshotModes, SnapshotWriter),
   FlushAndExit,
 }
 
+/// Runtime settings replaced together by [`Trace::apply_config`].
+pub struct ConfigUpdate {
+  pub directives: Directives,
+  /// New filters of running listeners.
+  pub filters: Vec<(ListenerId, ListenerFilter)>,
+  pub snapshot_interval: Option<std::time::Duration>,
+  pub snapshot_modes: SnapshotModes,
+  pub snapshot_writer: SnapshotWriter,
+}
+
+/// Handle returned by [`Trace::add_listener`], used to remove the listener.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ListenerId(u64);
+
+impl ListenerId {
+  /// Allocate a process-wide unique ID.
+  pub fn next() -> Self {
+    static NEXT_LISTENER_ID: AtomicU64 = AtomicU64::new(1);
+    Self(NEXT_LISTENER_ID.fetch_add(1, Ordering::Relaxed))
+  }
+
+  pub fn as_u64(self) -> u64 {
+    self.0
+  }
+}
+
+impl std::fmt::Display for ListenerId {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "listener#{}", self.0)
+  }
+}
+
 pub enum ListenerMessage {
+  /// Register a listener, on its own worker thread if options are given.
   Add(
+    ListenerId,
     Arc<dyn LogListener + std::panic::UnwindSafe + std::panic::RefUnwindSafe>,
+    ListenerFilter,
+    Option<WorkerOptions>,
     std::sync::mpsc::Sender<()>,
   ),
+  /// Deliver pending events, then call `on_shutdown` on the listener and drop
+  /// it. The ack carries whether the ID was registered.
+  Remove(ListenerId, std::sync::mpsc::Sender<bool>),
+  /// Replace the filter of a listener. The ack carries whether the ID was
+  /// registered.
+  SetFilter(ListenerId, ListenerFilter, std::sync::mpsc::Sender<bool>),
+  /// Deliver pending events, then ack.
+  Flush(std::sync::mpsc::Sender<()>),
+  /// Report how a listener is doing; `None` if the ID is not registered.
+  Health(ListenerId, std::sync::mpsc::Sender<Option<ListenerHealth>>),
+  /// Deliver pending events, then swap in the whole update. The ack carries
+  /// whether every listener ID was registered.
+  ApplyConfig(ConfigUpdate, std::sync::mpsc::Sender<bool>),
   Shutdown,
 }
 
+/// A listener as held by `listener_loop`.
+struct RegisteredListener {
+  id: ListenerId,
+  filter: ListenerFilter,
+  listener: Arc<dyn LogListener + std::panic::UnwindSafe + std::panic::RefUnwindSafe>,
+  /// Set for listeners running on their own thread.
+  worker: Option<ListenerWorker>,
+  /// Delivery counters of a listener on the shared thread.
+  stats: DeliveryStats,
+}
+
+impl RegisteredListener {
+  /// Filter a batch and hand what passes to the listener's `handle_batch`,
+  /// or to its worker queue, isolating panics from the listener and its
+  /// filter. `accepted` is scratch space for the events that pass the
+  /// filter. Returns `false` if either panicked.
+  fn dispatch_batch(
+    &mut self,
+    events: &[LogEvent],
+    interner: &StringInterner,
+    accepted: &mut Vec<LogEvent>,
+  ) -> bool {
+    let filter = &mut self.filter;
+    let filtered = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+      if filter.is_pass_all() {
+        return false;
+      }
+      accepted.clear();
+      accepted.extend(
+        events
+          .iter()
+          .filter(|event| filter.accepts(event, interner))
+          .cloned(),
+      );
+      true
+    }));
+    let events = match filtered {
+      Ok(false) => events,
+      Ok(true) => accepted.as_slice(),
+      Err(_) => {
+        self.stats.record(0, false);
+        return false;
+      },
+    };
+    if events.is_empty() {
+      return true;
+    }
+
+    if let Some(worker) = &self.worker {
+      worker.send(events);
+      return true;
+    }
+
+    let ok = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+      self.listener.handle_batch(events, interner);
+    }))
+    .is_ok();
+    self.stats.record(events.len(), ok);
+    ok
+  }
+
+  fn health(&self) -> ListenerHealth {
+    match &self.worker {
+      Some(worker) => worker.health(),
+      None => self.stats.health(),
+    }
+  }
+
+  /// Wait until a worker has handled everything sent to it so far.
+  fn flush(&self) {
+    if let Some(worker) = &self.worker {
+      worker.flush();
+    }
+  }
+
+  /// Run `on_shutdown`, on the worker thread after it drained its queue if
+  /// the listener has one.
+  fn shutdown(&mut self) {
+    match &mut self.worker {
+      Some(worker) => worker.stop(),
+      None => self.listener.on_shutdown(),
+    }
+  }
+}
+
+/// How the listener thread groups events for `LogListener::handle_batch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Batching {
+  /// Most events per batch.
+  pub max_events: usize,
+  /// How long to wait for more events after the first one of a batch.
+  pub max_wait: std::time::Duration,
+}
+
+impl Default for Batching {
+  fn default() -> Self {
+    Self {
+      max_events: 256,
+      max_wait: std::time::Duration::from_micros(100),
+    }
+  }
+}
+
 // New message type for direct event broadcasting
 #[derive(Debug, Clone)]
 pub struct EventBroadcast {
   pub event: LogEvent,
+  /// The event's arena KV payload. Holding it keeps the arena from reusing
+  /// the slot until every listener has seen the event.
+  pub kv: Option<KvPin>,
+}
+
+/// Events taken off the listener queue, with the KV payloads they hold.
+struct PendingBatch {
+  events: Vec<LogEvent>,
+  payloads: Vec<KvPin>,
+}
+
+impl PendingBatch {
+  fn with_capacity(capacity: usize) -> Self {
+    Self {
+      events: Vec::with_capacity(capacity),
+      payloads: Vec::with_capacity(capacity),
+    }
+  }
+
+  fn push(&mut self, broadcast: EventBroadcast) {
+    self.events.push(broadcast.event);
+    self.payloads.extend(broadcast.kv);
+  }
+
+  fn len(&self) -> usize {
+    self.events.len()
+  }
+
+  fn clear(&mut self) {
+    self.events.clear();
+    self.payloads.clear();
+  }
 }
 
 impl std::fmt::Display for Message {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
-      Message::SnapshotImmediate(reason, _) => write!(f, "SnapshotImmediate: {}", reason),
+      Message::SnapshotImmediate(reason, _, _) => write!(f, "SnapshotImmediate: {}", reason),
+      Message::UpdateSnapshots(interval, modes, _) => {
+        write!(f, "UpdateSnapshots: {:?} {:?}", interval, modes)
+      },
       Message::FlushAndExit => write!(f, "FlushAndExit"),
     }
   }
@@ -49,18 +253,129 @@ pub struct Trace {
   pub snapshot_buffer: Arc<LockFreeRingBuffer<LogEvent>>,
   /// Channel sender for communicating with the writer thread
   pub sender: Sender<Message>,
-  /// Direct event broadcasting channel - unbounded to ensure no events are lost
+  /// Queue feeding the listener thread; bounded, see [`Self::overflow_policy`].
   pub event_broadcast_sender: Sender<EventBroadcast>,
-  /// Atomic log level for runtime filtering
-  pub level: atomic::AtomicU8,
+  /// Atomic log level for runtime filtering, shared between clones
+  pub level: Arc<AtomicU8>,
   pub interner: Arc<StringInterner>,
   pub listener_sender: Sender<ListenerMessage>,
   pub write_thread: Option<thread::JoinHandle<()>>,
   pub listener_thread: Option<thread::JoinHandle<()>>,
+  /// Per-target level directives, shared between clones.
+  directives: Arc<DirectiveState>,
+  /// What to do when the listener queue is full, shared between clones.
+  backpressure: Arc<Backpressure<EventBroadcast>>,
+  /// KV arena room left for worker queues, shared between clones.
+  worker_room: Arc<WorkerRoom>,
+}
+
+/// Target directives plus the generation counter that invalidates the
+/// per-callsite caches whenever they change.
+#[derive(Debug)]
+struct DirectiveState {
+  directives: RwLock<Directives>,
+  generation: AtomicU64,
+  has_targets: AtomicBool,
+}
+
+/// Generations are drawn from one counter for all loggers, so a callsite
+/// used with several loggers never mistakes one's cache for another's.
+/// Callsites start at generation 0, so their first call resolves.
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+impl DirectiveState {
+  fn new() -> Self {
+    Self {
+      directives: RwLock::new(Directives::new()),
+      generation: AtomicU64::new(NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)),
+      has_targets: AtomicBool::new(false),
+    }
+  }
+
+  /// Replace the directives, storing a default level in `level`.
+  fn set(&self, level: &AtomicU8, directives: Directives) {
+    if let Some(default) = directives.default_level() {
+      level.store(default, Ordering::Relaxed);
+    }
+
+    let has_targets = directives.has_targets();
+    if let Ok(mut current) = self.directives.write() {
+      *current = directives;
+    }
+    self.has_targets.store(has_targets, Ordering::Relaxed);
+    self.generation.store(
+      NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
+      Ordering::Release,
+    );
+  }
+}
+
+/// What the listener thread swaps on [`ListenerMessage::ApplyConfig`] besides
+/// the listener filters.
+struct SharedConfig {
+  level: Arc<AtomicU8>,
+  directives: Arc<DirectiveState>,
+  writer: Sender<Message>,
 }
 
 pub static GLOBAL_LOGGER: OnceLock<Trace> = OnceLock::new();
 
+thread_local! {
+  static SCOPED_LOGGERS: RefCell<Vec<Rc<Trace>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Number of scoped loggers active on any thread; lets [`current`] skip the
+/// thread-local lookup in the common case.
+static SCOPED_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The logger the macros write to: see [`current`].
+pub enum CurrentLogger {
+  Global(&'static Trace),
+  Scoped(Rc<Trace>),
+}
+
+impl Deref for CurrentLogger {
+  type Target = Trace;
+
+  fn deref(&self) -> &Trace {
+    match self {
+      CurrentLogger::Global(logger) => logger,
+      CurrentLogger::Scoped(logger) => logger,
+    }
+  }
+}
+
+/// The innermost logger set with [`Trace::scoped`] on this thread, else
+/// `GLOBAL_LOGGER`.
+#[inline]
+pub fn current() -> Option<CurrentLogger> {
+  if SCOPED_COUNT.load(Ordering::Relaxed) != 0 {
+    if let Some(logger) = SCOPED_LOGGERS.with(|stack| stack.borrow().last().cloned()) {
+      return Some(CurrentLogger::Scoped(logger));
+    }
+  }
+  GLOBAL_LOGGER.get().map(CurrentLogger::Global)
+}
+
+/// Guard returned by [`Trace::scoped`]; restores the previous logger on drop.
+pub struct ScopedLogger {
+  depth: usize,
+  /// The guard restores thread-local state, so it must stay on its thread.
+  _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ScopedLogger {
+  fn drop(&mut self) {
+    let removed = SCOPED_LOGGERS.with(|stack| {
+      let mut stack = stack.borrow_mut();
+      let removed = stack.len().saturating_sub(self.depth);
+      stack.truncate(self.depth);
+      removed
+    });
+    SCOPED_COUNT.fetch_sub(removed, Ordering::Relaxed);
+  }
+}
+
 impl Trace {
   pub fn new(
     sender: Sender<Message>,
@@ -69,15 +384,29 @@ impl Trace {
     interner: Arc<StringInterner>,
     snapshot_buffer: Arc<LockFreeRingBuffer<LogEvent>>,
   ) -> Self {
+    // Events in the ring and the shared queue hold arena slots; worker
+    // queues share the rest.
+    let shared_queue = event_broadcast_sender
+      .capacity()
+      .unwrap_or(snapshot_buffer.capacity());
+    let worker_room = WorkerRoom::new(
+      interner
+        .kv_slots()
+        .saturating_sub(snapshot_buffer.capacity() + shared_queue),
+      shared_queue,
+    );
     Self {
       sender,
       event_broadcast_sender,
       snapshot_buffer,
       interner,
       listener_sender,
-      level: AtomicU8::new(LogLevel::WARN as u8),
+      level: Arc::new(AtomicU8::new(LogLevel::WARN as u8)),
       write_thread: None,
       listener_thread: None,
+      directives: Arc::new(DirectiveState::new()),
+      backpressure: Arc::new(Backpressure::new(OverflowPolicy::default(), None)),
+      worker_room: Arc::new(worker_room),
     }
   }
 
@@ -90,92 +419,215 @@ impl Trace {
     self.listener_thread = listener_thread;
   }
 
+  /// Initialise the global logger with the default configuration.
+  ///
+  /// Panics if `GLOBAL_LOGGER` is already set; use [`TraceBuilder`] for
+  /// the full set of options and a `Result` instead.
   pub fn init(
     capacity: usize,
     channel_capacity: usize,
     service_name: &str,
     storage_path: Option<&str>,
   ) -> Self {
-    let (sender, receiver) = crossbeam_channel::bounded::<Message>(channel_capacity);
+    let mut builder = TraceBuilder::new()
+      .capacity(capacity)
+      .channel_capacity(channel_capacity)
+      .service_name(service_name);
+    if let Some(path) = storage_path {
+      builder = builder.storage_path(path);
+    }
+
+    match builder.build() {
+      Ok(trace) => trace,
+      Err(e) => panic!("{}", e),
+    }
+  }
+
+  /// Spawn the writer and listener threads for a configured builder. With
+  /// `global`, the result is installed as `GLOBAL_LOGGER` together with the
+  /// panic and signal hooks; otherwise it is a standalone instance.
+  pub(crate) fn start(
+    config: TraceBuilder,
+    global: bool,
+  ) -> Result<(Self, Vec<ListenerId>), TraceError> {
+    if global && GLOBAL_LOGGER.get().is_some() {
+      return Err(TraceError::AlreadyInitialized);
+    }
+    config.validate()?;
+
+    let (sender, receiver) = crossbeam_channel::bounded::<Message>(config.channel_capacity);
     let (listener_sender, listener_receiver) = crossbeam_channel::bounded::<ListenerMessage>(16);
 
-    // Use unbounded channel for event broadcasting to ensure no events are lost
-    let (event_broadcast_sender, event_broadcast_receiver) = unbounded::<EventBroadcast>();
+    let (event_broadcast_sender, event_broadcast_receiver) =
+      crossbeam_channel::bounded::<EventBroadcast>(config.queue_capacity().max(1));
+    let backpressure = Arc::new(Backpressure::new(
+      config.overflow_policy,
+      Some(event_broadcast_receiver.clone()),
+    ));
 
-    let interner = Arc::new(StringInterner::new());
+    // Room for the payloads of every event the ring and the listener queue
+    // can hold. Queued events also hold their payloads, see `KvArena`.
+    let interner = Arc::new(StringInterner::with_kv_slots(config.arena_slots()));
 
     // Only need snapshot buffer now - listeners get events directly
-    let snapshot_buffer = Arc::new(LockFreeRingBuffer::new(capacity));
+    let snapshot_buffer = Arc::new(LockFreeRingBuffer::new(config.capacity));
     let snapshot_buffer_clone = Arc::clone(&snapshot_buffer);
     let interner_clone = Arc::clone(&interner);
 
-    // Install panic hook before spawning writer thread
-    PanicHook::install(sender.clone());
-
-    let service_name = service_name.to_string();
-    let storage_path: String = match storage_path {
-      Some(path) => path.to_string(),
-      None => "./tmp/".to_string(),
-    };
-
-    // Create the trace instance first
     let mut trace = Trace::new(
-      sender,
+      sender.clone(),
       listener_sender,
       event_broadcast_sender,
       interner,
       snapshot_buffer,
     );
+    trace.level.store(config.level as u8, Ordering::Relaxed);
+    trace.backpressure = Arc::clone(&backpressure);
+    if let Some(directives) = config.directives {
+      trace.set_directives(directives);
+    }
 
-    // Set the global logger BEFORE spawning the writer thread
-    match GLOBAL_LOGGER.set(trace.clone()) {
-      Ok(_) => {
-        println!("GLOBAL_LOGGER initialized");
-      },
-      Err(_) => panic!("GLOBAL_LOGGER already initialized"),
-    };
-
-    let write_thread_handle = thread::spawn(move || {
-      Self::writer_loop(
-        receiver,
-        capacity,
-        service_name,
-        storage_path,
-        snapshot_buffer_clone,
-        interner_clone,
-      );
-    });
+    let writer = SnapshotWriter::with_storage_path(config.service_name, config.storage_path)
+      .with_layout(config.storage_layout);
+    let capacity = config.capacity;
+    let snapshot_interval = config.snapshot_interval;
+    let snapshot_modes = config.snapshot_modes;
+    let write_thread_handle = thread::Builder::new()
+      .name("ttlog-writer".into())
+      .spawn(move || {
+        Self::writer_loop(
+          receiver,
+          capacity,
+          writer,
+          snapshot_interval,
+          snapshot_modes,
+          snapshot_buffer_clone,
+          interner_clone,
+        );
+      })
+      .map_err(TraceError::Spawn)?;
 
     // Spawn separate listener management thread
     let interner_listener = Arc::clone(&trace.interner);
-    let listener_thread_handle = thread::spawn(move || {
-      Self::listener_loop(
-        listener_receiver,
-        event_broadcast_receiver,
-        interner_listener,
-      );
-    });
+    let snapshot_buffer_listener = Arc::clone(&trace.snapshot_buffer);
+    let batching = config.batching;
+    let shared = SharedConfig {
+      level: Arc::clone(&trace.level),
+      directives: Arc::clone(&trace.directives),
+      writer: sender.clone(),
+    };
+    let listener_thread_handle = thread::Builder::new()
+      .name("ttlog-listener".into())
+      .spawn(move || {
+        Self::listener_loop(
+          listener_receiver,
+          event_broadcast_receiver,
+          interner_listener,
+          snapshot_buffer_listener,
+          backpressure,
+          batching,
+          shared,
+        );
+      })
+      .map_err(TraceError::Spawn)?;
 
     trace.set_handler(Some(write_thread_handle), Some(listener_thread_handle));
 
-    // Wait for writer and listener threads to start
-    // write_thread_handle.join().unwrap();
-    // listener_thread_handle.join().unwrap();
+    let mut listener_ids = Vec::with_capacity(config.listeners.len());
+    for (listener, filter, worker) in config.listeners {
+      let id = match worker {
+        Some(options) => trace.add_listener_on_worker(listener, filter, options),
+        None => Ok(trace.add_listener_with_filter(listener, filter)),
+      };
+      match id {
+        Ok(id) => listener_ids.push(id),
+        Err(e) => {
+          trace.shutdown();
+          return Err(e);
+        },
+      }
+    }
+
+    // Until here an error drops every sender and the threads exit on their
+    // own. The signal hook and the global come last so a failure never leaves
+    // the global pointing at a logger that is not running, and the panic hook
+    // replaces the process-wide one, so it waits until the global is ours.
+    if global {
+      #[cfg(unix)]
+      if config.signal_hook {
+        if let Err(e) = crate::signal_hook::SignalHook::try_install(sender.clone()) {
+          trace.shutdown();
+          return Err(TraceError::SignalHook(e));
+        }
+      }
+      #[cfg(not(unix))]
+      let _ = config.signal_hook;
+
+      if GLOBAL_LOGGER.set(trace.clone()).is_err() {
+        trace.shutdown();
+        return Err(TraceError::AlreadyInitialized);
+      }
+      if config.panic_hook {
+        PanicHook::install(sender);
+      }
+    }
 
-    trace
+    Ok((trace, listener_ids))
   }
 
+  /// Register a listener that receives every event and return the ID needed
+  /// to remove it later.
   pub fn add_listener(
     &self,
     listener: Arc<dyn LogListener + std::panic::UnwindSafe + std::panic::RefUnwindSafe>,
-  ) {
+  ) -> ListenerId {
+    self.add_listener_with_filter(listener, ListenerFilter::default())
+  }
+
+  /// Register a listener that only receives the events accepted by `filter`.
+  pub fn add_listener_with_filter(
+    &self,
+    listener: Arc<dyn LogListener + std::panic::UnwindSafe + std::panic::RefUnwindSafe>,
+    filter: ListenerFilter,
+  ) -> ListenerId {
+    self.register_listener(ListenerId::next(), listener, filter, None)
+  }
+
+  /// Register a listener that runs on its own thread with its own queue, so
+  /// a slow or blocking sink cannot hold up the other listeners. Events that
+  /// pass `filter` are queued for it according to `options`.
+  ///
+  /// Queued events keep their KV payloads, so all worker queues together
+  /// share the KV arena slots left beyond the ring buffer and the shared
+  /// queue. Without a `queue_capacity` the worker gets the shared queue's, or
+  /// what is left if that is less; a queue the room left cannot hold fails
+  /// with [`TraceError::QueueExceedsArena`]. Set room aside for them with
+  /// [`TraceBuilder::worker_queue_room`].
+  pub fn add_listener_on_worker(
+    &self,
+    listener: Arc<dyn LogListener + std::panic::UnwindSafe + std::panic::RefUnwindSafe>,
+    filter: ListenerFilter,
+    options: WorkerOptions,
+  ) -> Result<ListenerId, TraceError> {
+    let id = ListenerId::next();
+    let options = self.worker_room.reserve(id, options)?;
+    Ok(self.register_listener(id, listener, filter, Some(options)))
+  }
+
+  fn register_listener(
+    &self,
+    id: ListenerId,
+    listener: Arc<dyn LogListener + std::panic::UnwindSafe + std::panic::RefUnwindSafe>,
+    filter: ListenerFilter,
+    worker: Option<WorkerOptions>,
+  ) -> ListenerId {
     let (ack_tx, ack_rx) = std::sync::mpsc::channel();
     match self
       .listener_sender
-      .send(ListenerMessage::Add(listener, ack_tx))
+      .send(ListenerMessage::Add(id, listener, filter, worker, ack_tx))
     {
       Ok(_) => {
-        println!("[Trace] Listener addition request sent");
+        eprintln!("[Trace] Listener addition request sent");
         if ack_rx.recv().is_err() {
           eprintln!("[Trace] Listener addition ack channel dropped");
         }
@@ -184,6 +636,126 @@ impl Trace {
         eprintln!("[Trace] Failed to add listener: {:?}", e);
       },
     }
+    id
+  }
+
+  /// Detach a listener. Events logged before the call are still delivered to
+  /// it, then its `on_shutdown` runs. Returns `false` if `id` is unknown.
+  pub fn remove_listener(&self, id: ListenerId) -> bool {
+    let (ack_tx, ack_rx) = std::sync::mpsc::channel();
+    match self
+      .listener_sender
+      .send(ListenerMessage::Remove(id, ack_tx))
+    {
+      Ok(_) => {
+        let removed = ack_rx.recv().unwrap_or(false);
+        if removed {
+          self.worker_room.release(id);
+        }
+        removed
+      },
+      Err(e) => {
+        eprintln!("[Trace] Failed to remove {}: {:?}", id, e);
+        false
+      },
+    }
+  }
+
+  /// Replace the filter of a running listener. Returns `false` if `id` is
+  /// unknown.
+  pub fn set_listener_filter(&self, id: ListenerId, filter: ListenerFilter) -> bool {
+    let (ack_tx, ack_rx) = std::sync::mpsc::channel();
+    match self
+      .listener_sender
+      .send(ListenerMessage::SetFilter(id, filter, ack_tx))
+    {
+      Ok(_) => ack_rx.recv().unwrap_or(false),
+      Err(e) => {
+        eprintln!("[Trace] Failed to set filter of {}: {:?}", id, e);
+        false
+      },
+    }
+  }
+
+  /// How the listener registered as `id` is keeping up; `None` if `id` is
+  /// unknown.
+  pub fn listener_health(&self, id: ListenerId) -> Option<ListenerHealth> {
+    let (ack_tx, ack_rx) = std::sync::mpsc::channel();
+    match self
+      .listener_sender
+      .send(ListenerMessage::Health(id, ack_tx))
+    {
+      Ok(_) => ack_rx.recv().unwrap_or(None),
+      Err(e) => {
+        eprintln!("[Trace] Failed to query {}: {:?}", id, e);
+        None
+      },
+    }
+  }
+
+  /// Change the periodic snapshot interval (`None` disables it), the mode of
+  /// each trigger and where snapshots are written, without restarting the
+  /// writer thread.
+  pub fn update_snapshots(
+    &self,
+    interval: Option<std::time::Duration>,
+    modes: SnapshotModes,
+    writer: SnapshotWriter,
+  ) -> bool {
+    match self
+      .sender
+      .send(Message::UpdateSnapshots(interval, modes, writer))
+    {
+      Ok(_) => true,
+      Err(e) => {
+        eprintln!("[Trace] Failed to update snapshot settings: {:?}", e);
+        false
+      },
+    }
+  }
+
+  /// Replace the directives, listener filters and snapshot settings in one
+  /// step. The listener thread delivers the events queued before the update
+  /// under the old settings, then swaps in all of them before it takes the
+  /// next event, so an event is not filtered by a mix of old and new ones.
+  /// Returns `false` if a listener ID is unknown or the logger has stopped.
+  pub fn apply_config(&self, update: ConfigUpdate) -> bool {
+    let (ack_tx, ack_rx) = std::sync::mpsc::channel();
+    match self
+      .listener_sender
+      .send(ListenerMessage::ApplyConfig(update, ack_tx))
+    {
+      Ok(_) => ack_rx.recv().unwrap_or(false),
+      Err(e) => {
+        eprintln!("[Trace] Failed to apply config: {:?}", e);
+        false
+      },
+    }
+  }
+
+  /// Block until every event logged before the call has reached the
+  /// listeners.
+  pub fn flush_listeners(&self) {
+    let (ack_tx, ack_rx) = std::sync::mpsc::channel();
+    match self.listener_sender.send(ListenerMessage::Flush(ack_tx)) {
+      Ok(_) => {
+        let _ = ack_rx.recv();
+      },
+      Err(e) => eprintln!("[Trace] Failed to flush listeners: {:?}", e),
+    }
+  }
+
+  /// Swap the listener registered as `id` for `listener`, which receives every
+  /// event. The new listener is attached before the old one is removed, so no
+  /// event is missed in between.
+  pub fn replace_listener(
+    &self,
+    id: ListenerId,
+    listener: Arc<dyn LogListener + std::panic::UnwindSafe + std::panic::RefUnwindSafe>,
+  ) -> ListenerId {
+    let new_id = self.add_listener(listener);
+    self.remove_listener(id);
+    new_id
   }
 
   pub fn shutdown(&mut self) {
@@ -210,10 +782,11 @@ impl Trace {
   pub fn request_snapshot(&self, reason: impl Into<String>) {
     let (tx, rx) = std::sync::mpsc::channel();
 
-    if let Err(e) = self
-      .sender
-      .try_send(Message::SnapshotImmediate(reason.into(), tx))
-    {
+    if let Err(e) = self.sender.try_send(Message::SnapshotImmediate(
+      reason.into(),
+      SnapshotTrigger::Manual,
+      tx,
+    )) {
       eprintln!("[Snapshot Request] Failed to enqueue: {:?}", e);
       return;
     }
@@ -224,37 +797,161 @@ impl Trace {
     }
   }
 
+  /// What happens to events when the listener queue is full.
+  pub fn overflow_policy(&self) -> OverflowPolicy {
+    self.backpressure.policy()
+  }
+
+  /// Events the listeners did not receive because their queue was full, since
+  /// the logger started. They are still in the snapshot ring buffer.
+  /// `kv_dropped` counts events logged without their KVs instead, see
+  /// [`crate::kv_arena::KvArena`].
+  pub fn dropped_events(&self) -> DropStats {
+    DropStats {
+      kv_dropped: self.interner.kv_dropped(),
+      ..self.backpressure.stats()
+    }
+  }
+
+  /// Events the snapshot ring buffer overwrote before a snapshot took them,
+  /// since the logger started.
+  pub fn overwritten_events(&self) -> u64 {
+    self.snapshot_buffer.overwritten()
+  }
+
   pub fn set_level(&self, level: LogLevel) {
     self.level.store(level as u8, Ordering::Relaxed);
-    if let Some(logger) = GLOBAL_LOGGER.get() {
-      logger.level.store(level as u8, Ordering::Relaxed);
-    }
   }
 
+  /// Current default level. Reports `FATAL` when logging is turned off.
   pub fn get_level(&self) -> LogLevel {
     let level_u8 = self.level.load(Ordering::Relaxed);
-    unsafe { std::mem::transmute(level_u8) }
+    LogLevel::from_u8(&level_u8.min(LogLevel::FATAL as u8))
+  }
+
+  /// Replace the level directives. A default level in `directives` is
+  /// applied as with [`Self::set_level`]; target directives take effect at
+  /// every callsite on its next call.
+  pub fn set_directives(&self, directives: Directives) {
+    self.directives.set(&self.level, directives);
+  }
+
+  /// Parse `input` (e.g. `info,my_crate::db=trace`) and apply it.
+  pub fn set_directives_str(&self, input: &str) -> Result<(), DirectiveParseError> {
+    self.set_directives(Directives::parse(input)?);
+    Ok(())
+  }
+
+  /// The directives currently in effect.
+  pub fn directives(&self) -> Directives {
+    self
+      .directives
+      .directives
+      .read()
+      .map(|directives| directives.clone())
+      .unwrap_or_default()
+  }
+
+  /// Generation of the directives, bumped on every change.
+  #[inline(always)]
+  pub fn directives_generation(&self) -> u64 {
+    self.directives.generation.load(Ordering::Acquire)
+  }
+
+  /// Level threshold for `target`, or [`directive::USE_GLOBAL`] if no target
+  /// directive matches.
+  pub fn threshold_for(&self, target: &str) -> u8 {
+    if !self.directives.has_targets.load(Ordering::Relaxed) {
+      return directive::USE_GLOBAL;
+    }
+
+    self
+      .directives
+      .directives
+      .read()
+      .map(|directives| directives.threshold_for(target))
+      .unwrap_or(directive::USE_GLOBAL)
+  }
+
+  /// Make this logger the one the macros on the current thread write to
+  /// until the guard is dropped. Scopes nest; the innermost wins.
+  pub fn scoped(&self) -> ScopedLogger {
+    let depth = SCOPED_LOGGERS.with(|stack| {
+      let mut stack = stack.borrow_mut();
+      stack.push(Rc::new(self.clone()));
+      stack.len() - 1
+    });
+    SCOPED_COUNT.fetch_add(1, Ordering::Relaxed);
+
+    ScopedLogger {
+      depth,
+      _not_send: PhantomData,
+    }
+  }
+
+  /// Run `f` with this logger as the current one (see [`Self::scoped`]).
+  pub fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+    let _scoped = self.scoped();
+    f()
+  }
+
+  /// Whether an event at `level` from `target` passes the level and the
+  /// directives. Callers without a [`directive::Callsite`] (e.g. the `log`
+  /// and `tracing` adapters) use this.
+  pub fn is_enabled(&self, level: u8, target: &str) -> bool {
+    match self.threshold_for(target) {
+      directive::USE_GLOBAL => level >= self.level.load(Ordering::Relaxed),
+      threshold => level >= threshold,
+    }
   }
 
   #[inline(always)]
   pub fn send_event_fast(
     &self,
     log_level: u8,
-    target_id: u16,
-    message_id: Option<num::NonZeroU16>,
+    target_id: u32,
+    message_id: Option<num::NonZeroU32>,
     thread_id: u8,
-    file_id: u16,
+    file_id: u32,
     position: (u32, u32),
-    kv_id: Option<num::NonZeroU16>,
+    kv_id: Option<num::NonZeroU32>,
   ) {
-    let timestamp = SystemTime::now()
-      .duration_since(UNIX_EPOCH)
-      .unwrap_or_default()
-      .as_millis() as u64;
+    self.send_event_in_span(
+      log_level,
+      target_id,
+      message_id,
+      thread_id,
+      file_id,
+      position,
+      kv_id,
+      span::current_id(),
+    );
+  }
+
+  /// Like [`Self::send_event_fast`] but attributes the event to `span_id`
+  /// instead of the thread's current span. Used for span lifecycle events.
+  #[inline(always)]
+  pub fn send_event_in_span(
+    &self,
+    log_level: u8,
+    target_id: u32,
+    message_id: Option<num::NonZeroU32>,
+    thread_id: u8,
+    file_id: u32,
+    position: (u32, u32),
+    kv_id: Option<num::NonZeroU32>,
+    span_id: u64,
+  ) {
+    if self.interner.take_exhaustion() {
+      self.report_interner_exhaustion();
+    }
+
+    // One clock read; the packed milliseconds are derived from it.
+    let mono_ns = clock::now_ns();
 
     let event = LogEvent {
       packed_meta: LogEvent::pack_meta(
-        timestamp,
+        clock::unix_millis(mono_ns),
         unsafe { std::mem::transmute::<u8, LogLevel>(log_level) },
         thread_id,
       ),
@@ -263,36 +960,52 @@ impl Trace {
       position,
       file_id,
       kv_id,
+      span_id,
+      mono_ns,
+      seq: clock::next_seq(),
     };
 
     // Add to snapshot buffer for periodic snapshots
     self.snapshot_buffer.push_overwrite(event.clone());
 
-    // Broadcast to all listeners immediately - no buffering, no limits
-    // Using unbounded channel ensures no events are lost
-    if self
-      .event_broadcast_sender
-      .try_send(EventBroadcast { event })
-      .is_err()
-    {
-      // If the channel is somehow full or closed, we could optionally log this
-      // but we don't want to block the critical logging path
-      eprintln!("[Trace] Warning: Failed to broadcast event to listeners");
-    }
+    // Discarded events are counted and reported by the listener thread.
+    let kv = kv_id.and_then(|id| self.interner.pin_kv(id.get()));
+    self
+      .backpressure
+      .send(&self.event_broadcast_sender, EventBroadcast { event, kv });
+  }
+
+  /// Log a warning that a kind of interned value ran out of IDs.
+  #[cold]
+  fn report_interner_exhaustion(&self) {
+    let interner = &self.interner;
+    let mut kv = FieldEncoder::new();
+    kv.field(string_interner::EXHAUSTED_KEY_ID, &interner.exhausted());
+    let kv_id = num::NonZeroU32::new(interner.store_kv(kv.finish()));
+
+    self.send_event_in_span(
+      LogLevel::WARN as u8,
+      interner.intern_target(string_interner::EXHAUSTED_TARGET),
+      num::NonZeroU32::new(interner.intern_message(string_interner::EXHAUSTED_MESSAGE)),
+      crate::thread_registry::current_id(),
+      0,
+      (0, 0),
+      kv_id,
+      0,
+    );
   }
 
   // Separate writer loop focused only on snapshots and control messages
   fn writer_loop(
     receiver: Receiver<Message>,
     capacity: usize,
-    service_name: String,
-    storage_path: String,
+    mut service: SnapshotWriter,
+    mut snapshot_interval: Option<std::time::Duration>,
+    mut snapshot_modes: SnapshotModes,
     mut snapshot_buffer: Arc<LockFreeRingBuffer<LogEvent>>,
     interner: Arc<StringInterner>,
   ) {
     let mut last_periodic = Instant::now();
-    let periodic_flush_interval = Duration::seconds(60).to_std().unwrap();
-    let service = SnapshotWriter::with_storage_path(service_name, storage_path);
 
     eprintln!(
       "[Trace] Writer thread started with buffer capacity: {}",
@@ -301,9 +1014,16 @@ impl Trace {
 
     loop {
       // Handle control messages with timeout to allow periodic snapshots
-      match receiver.recv_timeout(periodic_flush_interval) {
+      let received = match snapshot_interval {
+        Some(interval) => receiver.recv_timeout(interval),
+        None => receiver
+          .recv()
+          .map_err(|_| crossbeam_channel::RecvTimeoutError::Disconnected),
+      };
+
+      match received {
         Ok(msg) => match msg {
-          Message::SnapshotImmediate(reason, ack) => {
+          Message::SnapshotImmediate(reason, trigger, ack) => {
             eprintln!(
               "[Snapshot] Requested: {} (buffer has {} events)",
               reason,
@@ -311,9 +1031,12 @@ impl Trace {
             );
 
             if !snapshot_buffer.is_empty() {
-              if let Err(e) =
-                service.snapshot_and_write(&mut snapshot_buffer, reason.clone(), interner.clone())
-              {
+              if let Err(e) = service.snapshot_and_write_with_mode(
+                &mut snapshot_buffer,
+                reason.clone(),
+                interner.clone(),
+                snapshot_modes.get(trigger),
+              ) {
                 eprintln!("[Snapshot] failed: {}", e);
               } else {
                 eprintln!("[Snapshot] completed successfully");
@@ -323,6 +1046,16 @@ impl Trace {
             }
             let _ = ack.send(());
           },
+          Message::UpdateSnapshots(interval, modes, writer) => {
+            eprintln!(
+              "[Trace] Snapshot settings updated (interval: {:?}, modes: {:?})",
+              interval, modes
+            );
+            snapshot_interval = interval;
+            snapshot_modes = modes;
+            service = writer;
+            last_periodic = Instant::now();
+          },
           Message::FlushAndExit => {
             eprintln!("[Trace] Received shutdown signal");
 
@@ -335,15 +1068,24 @@ impl Trace {
             return;
           },
         },
-        Err(_) => {
+        Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
+          eprintln!("[Trace] Writer channel closed");
+          return;
+        },
+        Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
           // Timeout occurred - check for periodic snapshot
-          if last_periodic.elapsed() >= periodic_flush_interval && !snapshot_buffer.is_empty() {
+          let due = snapshot_interval.is_some_and(|interval| last_periodic.elapsed() >= interval);
+          if due && !snapshot_buffer.is_empty() {
             eprintln!(
               "[Snapshot] Periodic snapshot triggered ({} events)",
               snapshot_buffer.len()
             );
-            let _result =
-              service.snapshot_and_write(&mut snapshot_buffer, "periodic", interner.clone());
+            let _result = service.snapshot_and_write_with_mode(
+              &mut snapshot_buffer,
+              "periodic",
+              interner.clone(),
+              snapshot_modes.periodic,
+            );
             last_periodic = Instant::now();
           }
         },
@@ -351,43 +1093,241 @@ impl Trace {
     }
   }
 
-  // Dedicated listener loop - handles all listener events without limits
+  /// Log how many events the listener queue, and the queue of each worker,
+  /// dropped since the last report, if any. Reports bypass the shared queue
+  /// so they cannot be dropped there themselves.
+  fn report_drops(
+    listeners: &mut [RegisteredListener],
+    interner: &StringInterner,
+    snapshot_buffer: &LockFreeRingBuffer<LogEvent>,
+    backpressure: &Backpressure<EventBroadcast>,
+  ) {
+    let mut reports = Vec::new();
+    if let Some((dropped, stats)) = backpressure.take_unreported() {
+      reports.push(Self::drop_report(
+        interner,
+        dropped,
+        stats,
+        backpressure.policy(),
+        None,
+      ));
+    }
+    for registered in listeners.iter() {
+      if let Some(worker) = &registered.worker {
+        if let Some((dropped, stats)) = worker.backpressure().take_unreported() {
+          reports.push(Self::drop_report(
+            interner,
+            dropped,
+            stats,
+            worker.backpressure().policy(),
+            Some(registered.id),
+          ));
+        }
+      }
+    }
+    if reports.is_empty() {
+      return;
+    }
+
+    for event in &reports {
+      snapshot_buffer.push_overwrite(event.clone());
+    }
+    let mut accepted = Vec::new();
+    for registered in listeners {
+      registered.dispatch_batch(&reports, interner, &mut accepted);
+    }
+  }
+
+  /// The "dropped N events" event; `listener` is set for a worker's queue.
+  fn drop_report(
+    interner: &StringInterner,
+    dropped: u64,
+    stats: DropStats,
+    policy: OverflowPolicy,
+    listener: Option<ListenerId>,
+  ) -> LogEvent {
+    let mut kv = FieldEncoder::new();
+    kv.field(interner.intern_key("dropped"), &dropped)
+      .field(interner.intern_key("total"), &stats.total())
+      .field(interner.intern_key("policy"), policy.as_str());
+    if let Some(id) = listener {
+      kv.field(interner.intern_key("listener"), &id.as_u64());
+    }
+
+    let mono_ns = clock::now_ns();
+    LogEvent {
+      packed_meta: LogEvent::pack_meta(
+        clock::unix_millis(mono_ns),
+        LogLevel::WARN,
+        thread_registry::current_id(),
+      ),
+      target_id: interner.intern_target(backpressure::DROP_REPORT_TARGET),
+      message_id: num::NonZeroU32::new(interner.intern_message(backpressure::DROP_REPORT_MESSAGE)),
+      kv_id: num::NonZeroU32::new(interner.store_kv(kv.finish())),
+      file_id: 0,
+      position: (0, 0),
+      span_id: 0,
+      mono_ns,
+      seq: clock::next_seq(),
+    }
+  }
+
+  /// Hand `batch` to every listener, `max_events` at a time, and clear it.
+  fn deliver(
+    listeners: &mut [RegisteredListener],
+    batch: &mut PendingBatch,
+    accepted: &mut Vec<LogEvent>,
+    interner: &StringInterner,
+    max_events: usize,
+  ) {
+    for chunk in batch.events.chunks(max_events.max(1)) {
+      for registered in listeners.iter_mut() {
+        if !registered.dispatch_batch(chunk, interner, accepted) {
+          eprintln!("[Trace] Listener panicked, continuing with others");
+        }
+      }
+    }
+    batch.clear();
+  }
+
+  // Dedicated listener loop - delivers queued events to every listener
   fn listener_loop(
     listener_receiver: Receiver<ListenerMessage>,
     event_receiver: Receiver<EventBroadcast>,
     interner: Arc<StringInterner>,
+    snapshot_buffer: Arc<LockFreeRingBuffer<LogEvent>>,
+    backpressure: Arc<Backpressure<EventBroadcast>>,
+    batching: Batching,
+    shared: SharedConfig,
   ) {
-    let mut listeners: Vec<
-      Arc<dyn LogListener + std::panic::UnwindSafe + std::panic::RefUnwindSafe>,
-    > = Vec::new();
+    let mut listeners: Vec<RegisteredListener> = Vec::new();
+    let mut last_drop_report = Instant::now();
+    let mut batch = PendingBatch::with_capacity(batching.max_events);
+    let mut accepted: Vec<LogEvent> = Vec::new();
 
-    println!("[Trace] Listener thread started");
+    eprintln!("[Trace] Listener thread started");
 
     loop {
       crossbeam_channel::select! {
         recv(listener_receiver) -> msg => {
           match msg {
-            Ok(ListenerMessage::Add(listener, ack)) => {
+            Ok(ListenerMessage::Add(id, listener, filter, worker, ack)) => {
               listener.on_start();
-              listeners.push(listener);
-              eprintln!("[Trace] Added new listener, total: {}", listeners.len());
+              let worker = worker.and_then(|options| {
+                ListenerWorker::spawn(id, Arc::clone(&listener), Arc::clone(&interner), options)
+                  .map_err(|e| {
+                    eprintln!("[Trace] Failed to spawn worker for {}, sharing: {}", id, e);
+                  })
+                  .ok()
+              });
+              listeners.push(RegisteredListener {
+                id,
+                filter,
+                listener,
+                worker,
+                stats: DeliveryStats::default(),
+              });
+              eprintln!("[Trace] Added {}, total: {}", id, listeners.len());
               let _ = ack.send(());
             },
+            Ok(ListenerMessage::Remove(id, ack)) => {
+              // Events queued before the request still belong to this listener.
+              event_receiver.try_iter().for_each(|broadcast| batch.push(broadcast));
+              Self::deliver(
+                &mut listeners,
+                &mut batch,
+                &mut accepted,
+                &interner,
+                batching.max_events,
+              );
+
+              let removed = match listeners.iter().position(|registered| registered.id == id) {
+                Some(index) => {
+                  // A worker still holds events sent before the request.
+                  listeners.remove(index).shutdown();
+                  eprintln!("[Trace] Removed {}, total: {}", id, listeners.len());
+                  true
+                },
+                None => false,
+              };
+              let _ = ack.send(removed);
+            },
+            Ok(ListenerMessage::SetFilter(id, filter, ack)) => {
+              let updated = match listeners.iter_mut().find(|registered| registered.id == id) {
+                Some(registered) => {
+                  registered.filter = filter;
+                  true
+                },
+                None => false,
+              };
+              let _ = ack.send(updated);
+            },
+            Ok(ListenerMessage::Flush(ack)) => {
+              event_receiver.try_iter().for_each(|broadcast| batch.push(broadcast));
+              Self::deliver(
+                &mut listeners,
+                &mut batch,
+                &mut accepted,
+                &interner,
+                batching.max_events,
+              );
+              for registered in &listeners {
+                registered.flush();
+              }
+              let _ = ack.send(());
+            },
+            Ok(ListenerMessage::Health(id, ack)) => {
+              let health = listeners
+                .iter()
+                .find(|registered| registered.id == id)
+                .map(RegisteredListener::health);
+              let _ = ack.send(health);
+            },
+            Ok(ListenerMessage::ApplyConfig(update, ack)) => {
+              // Events queued before the update were logged under the old config.
+              event_receiver.try_iter().for_each(|broadcast| batch.push(broadcast));
+              Self::deliver(
+                &mut listeners,
+                &mut batch,
+                &mut accepted,
+                &interner,
+                batching.max_events,
+              );
+
+              let mut applied = true;
+              for (id, filter) in update.filters {
+                match listeners.iter_mut().find(|registered| registered.id == id) {
+                  Some(registered) => registered.filter = filter,
+                  None => applied = false,
+                }
+              }
+              shared.directives.set(&shared.level, update.directives);
+              applied &= shared
+                .writer
+                .send(Message::UpdateSnapshots(
+                  update.snapshot_interval,
+                  update.snapshot_modes,
+                  update.snapshot_writer,
+                ))
+                .is_ok();
+              let _ = ack.send(applied);
+            },
             Ok(ListenerMessage::Shutdown) => {
               eprintln!("[Trace] Listener thread received shutdown signal");
 
               // Process any remaining events
-              while let Ok(event_broadcast) = event_receiver.try_recv() {
-                for listener in &listeners {
-                  let _ = std::panic::catch_unwind(|| {
-                    listener.handle(&event_broadcast.event, &interner);
-                  });
-                }
-              }
+              event_receiver.try_iter().for_each(|broadcast| batch.push(broadcast));
+              Self::deliver(
+                &mut listeners,
+                &mut batch,
+                &mut accepted,
+                &interner,
+                batching.max_events,
+              );
 
               // Cleanup listeners
-              for listener in &listeners {
-                listener.on_shutdown();
+              for registered in &mut listeners {
+                registered.shutdown();
               }
 
               eprintln!("[Trace] Listener thread shutting down");
@@ -403,16 +1343,21 @@ impl Trace {
         recv(event_receiver) -> event_msg => {
           match event_msg {
             Ok(event_broadcast) => {
-              // Broadcast to ALL listeners - no limits, no batching
-              for listener in &listeners {
-                let result = std::panic::catch_unwind(|| {
-                  listener.handle(&event_broadcast.event, &interner);
-                });
-
-                if result.is_err() {
-                  eprintln!("[Trace] Listener panicked, continuing with others");
+              batch.push(event_broadcast);
+              let deadline = Instant::now() + batching.max_wait;
+              while batch.len() < batching.max_events {
+                match event_receiver.recv_deadline(deadline) {
+                  Ok(event_broadcast) => batch.push(event_broadcast),
+                  Err(_) => break,
                 }
               }
+              Self::deliver(
+                &mut listeners,
+                &mut batch,
+                &mut accepted,
+                &interner,
+                batching.max_events,
+              );
             },
             Err(_) => {
               // Event channel closed
@@ -420,7 +1365,13 @@ impl Trace {
               return;
             }
           }
-        }
+        },
+        default(backpressure::DROP_REPORT_INTERVAL) => {},
+      }
+
+      if last_drop_report.elapsed() >= backpressure::DROP_REPORT_INTERVAL {
+        Self::report_drops(&mut listeners, &interner, &snapshot_buffer, &backpressure);
+        last_drop_report = Instant::now();
       }
     }
   }
@@ -432,17 +1383,35 @@ impl Clone for Trace {
       snapshot_buffer: Arc::clone(&self.snapshot_buffer),
       sender: self.sender.clone(),
       event_broadcast_sender: self.event_broadcast_sender.clone(),
-      level: AtomicU8::new(self.level.load(Ordering::Relaxed)),
+      level: Arc::clone(&self.level),
       interner: Arc::clone(&self.interner),
       listener_sender: self.listener_sender.clone(),
       write_thread: None,
       listener_thread: None,
+      directives: Arc::clone(&self.directives),
+      backpressure: Arc::clone(&self.backpressure),
+      worker_room: Arc::clone(&self.worker_room),
     }
   }
 }
 
+impl std::fmt::Debug for Trace {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Trace")
+      .field("level", &self.get_level())
+      .field("directives", &self.directives().to_string())
+      .finish_non_exhaustive()
+  }
+}
+
 impl Drop for Trace {
   fn drop(&mut self) {
+    // Clones (the global logger, scoped loggers, spans) share the threads of
+    // the instance that started them; only that one shuts them down.
+    if self.write_thread.is_none() && self.listener_thread.is_none() {
+      return;
+    }
+
     // If shutdown wasn't called explicitly
     let _ = self.listener_sender.try_send(ListenerMessage::Shutdown);
     let _ = self.sender.try_send(Message::FlushAndExit);
@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod __test__ {
+
+  use std::error::Error;
+  use std::sync::Arc;
+  use std::time::Duration;
+
+  use crate::event::LogLevel;
+  use crate::listener::ListenerFilter;
+  use crate::listener_worker::WorkerOptions;
+  use crate::snapshot::StorageLayout;
+  use crate::stdout_listener::StdoutListener;
+  use crate::trace_builder::{TraceBuilder, TraceError};
+
+  #[test]
+  fn builder_defaults_match_init() {
+    let builder = TraceBuilder::new();
+    assert_eq!(builder.level, LogLevel::WARN);
+    assert_eq!(builder.storage_path, "./tmp/");
+    assert_eq!(builder.snapshot_interval, Some(Duration::from_secs(60)));
+    assert!(builder.panic_hook);
+    assert!(!builder.signal_hook);
+  }
+
+  #[test]
+  fn builder_setters_apply() {
+    let builder = TraceBuilder::new()
+      .capacity(16)
+      .channel_capacity(4)
+      .service_name("svc")
+      .storage_path("/var/log/svc")
+      .storage_layout(StorageLayout::PerService)
+      .snapshot_interval(None)
+      .level(LogLevel::DEBUG)
+      .panic_hook(false);
+
+    assert_eq!(builder.capacity, 16);
+    assert_eq!(builder.channel_capacity, 4);
+    assert_eq!(builder.service_name, "svc");
+    assert_eq!(builder.storage_layout, StorageLayout::PerService);
+    assert_eq!(builder.snapshot_interval, None);
+    assert_eq!(builder.level, LogLevel::DEBUG);
+    assert!(!builder.panic_hook);
+  }
+
+  #[test]
+  fn listener_queue_and_arena_derive_from_each_other() {
+    let builder = TraceBuilder::new().capacity(100);
+    assert_eq!(builder.queue_capacity(), 100);
+    assert_eq!(builder.arena_slots(), 200);
+
+    let builder = TraceBuilder::new()
+      .capacity(100)
+      .listener_queue_capacity(300);
+    assert_eq!(builder.arena_slots(), 400);
+
+    let builder = TraceBuilder::new().capacity(100).kv_arena_slots(1000);
+    assert_eq!(builder.queue_capacity(), 900);
+    assert!(builder.validate().is_ok());
+
+    // Worker queues count towards the arena too.
+    let builder = TraceBuilder::new()
+      .capacity(100)
+      .listener_on_worker(
+        Arc::new(StdoutListener::new()),
+        ListenerFilter::new(),
+        WorkerOptions::default(),
+      )
+      .listener_on_worker(
+        Arc::new(StdoutListener::new()),
+        ListenerFilter::new(),
+        WorkerOptions::new().queue_capacity(50),
+      );
+    assert_eq!(builder.arena_slots(), 350);
+
+    // With a fixed arena, the shared queue splits what the sized workers
+    // leave with the defaulted ones.
+    let builder = builder.kv_arena_slots(1150);
+    assert_eq!(builder.queue_capacity(), 500);
+    assert!(builder.validate().is_ok());
+  }
+
+  #[test]
+  fn queues_larger_than_the_arena_are_rejected() {
+    let shared = TraceBuilder::new()
+      .capacity(100)
+      .listener_queue_capacity(200)
+      .kv_arena_slots(250);
+    assert!(matches!(
+      shared.validate(),
+      Err(TraceError::QueueExceedsArena {
+        queue_capacity: 200,
+        room: 150,
+      })
+    ));
+
+    // Each worker fits on its own, but not together with the others.
+    let workers = (0..2).fold(
+      TraceBuilder::new()
+        .capacity(100)
+        .listener_queue_capacity(50)
+        .kv_arena_slots(300),
+      |builder, _| {
+        builder.listener_on_worker(
+          Arc::new(StdoutListener::new()),
+          ListenerFilter::new(),
+          WorkerOptions::new().queue_capacity(100),
+        )
+      },
+    );
+    assert!(matches!(
+      workers.validate(),
+      Err(TraceError::QueueExceedsArena {
+        queue_capacity: 250,
+        room: 200,
+      })
+    ));
+  }
+
+  #[test]
+  fn trace_error_display() {
+    assert_eq!(
+      TraceError::AlreadyInitialized.to_string(),
+      "GLOBAL_LOGGER already initialized"
+    );
+    let spawn = TraceError::Spawn(std::io::Error::other("boom"));
+    assert!(spawn.to_string().contains("boom"));
+    assert!(spawn.source().is_some());
+    assert_eq!(
+      TraceError::QueueExceedsArena {
+        queue_capacity: 8,
+        room: 4,
+      }
+      .to_string(),
+      "listener queues of 8 events exceed the 4 KV arena slots left beyond the ring buffer"
+    );
+  }
+}
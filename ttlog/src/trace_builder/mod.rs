@@ -0,0 +1,364 @@
+mod __test__;
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::backpressure::OverflowPolicy;
+use crate::directive::Directives;
+use crate::event::LogLevel;
+use crate::listener::{ListenerFilter, LogListener};
+use crate::listener_worker::WorkerOptions;
+use crate::snapshot::{SnapshotMode, SnapshotModes, SnapshotTrigger, StorageLayout};
+use crate::trace::{Batching, ListenerId, Trace};
+
+/// Why [`TraceBuilder::build`] failed.
+#[derive(Debug)]
+pub enum TraceError {
+  /// `GLOBAL_LOGGER` was already set by an earlier `init`/`build`.
+  AlreadyInitialized,
+  /// The writer or listener thread could not be spawned.
+  Spawn(io::Error),
+  /// The signal handler could not be registered.
+  SignalHook(io::Error),
+  /// The listener queues together hold more events than the KV arena has
+  /// slots beyond the ring buffer, so queued events could outlive their
+  /// payloads. `queue_capacity` is what the queues need, or what a worker
+  /// added at runtime asked for, and `room` what the arena has for them.
+  QueueExceedsArena { queue_capacity: usize, room: usize },
+}
+
+impl fmt::Display for TraceError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TraceError::AlreadyInitialized => write!(f, "GLOBAL_LOGGER already initialized"),
+      TraceError::Spawn(e) => write!(f, "failed to spawn trace thread: {}", e),
+      TraceError::SignalHook(e) => write!(f, "failed to install signal handler: {}", e),
+      TraceError::QueueExceedsArena {
+        queue_capacity,
+        room,
+      } => write!(
+        f,
+        "listener queues of {} events exceed the {} KV arena slots left beyond the ring buffer",
+        queue_capacity, room
+      ),
+    }
+  }
+}
+
+impl Error for TraceError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      TraceError::AlreadyInitialized | TraceError::QueueExceedsArena { .. } => None,
+      TraceError::Spawn(e) | TraceError::SignalHook(e) => Some(e),
+    }
+  }
+}
+
+/// Configuration for a [`Trace`], replacing the positional arguments of
+/// [`Trace::init`].
+///
+/// ```ignore
+/// let trace = TraceBuilder::new()
+///   .service_name("checkout")
+///   .capacity(10_000)
+///   .level(LogLevel::INFO)
+///   .snapshot_interval(Some(Duration::from_secs(300)))
+///   .signal_hook(true)
+///   .listener(Arc::new(StdoutListener::new()))
+///   .build()?;
+/// ```
+pub struct TraceBuilder {
+  pub(crate) capacity: usize,
+  pub(crate) channel_capacity: usize,
+  pub(crate) listener_queue_capacity: Option<usize>,
+  pub(crate) kv_slots: Option<usize>,
+  pub(crate) worker_queue_room: usize,
+  pub(crate) overflow_policy: OverflowPolicy,
+  pub(crate) batching: Batching,
+  pub(crate) service_name: String,
+  pub(crate) storage_path: String,
+  pub(crate) storage_layout: StorageLayout,
+  pub(crate) snapshot_interval: Option<Duration>,
+  pub(crate) snapshot_modes: SnapshotModes,
+  pub(crate) level: LogLevel,
+  pub(crate) directives: Option<Directives>,
+  pub(crate) panic_hook: bool,
+  pub(crate) signal_hook: bool,
+  pub(crate) listeners: Vec<(
+    Arc<dyn LogListener + UnwindSafe + RefUnwindSafe>,
+    ListenerFilter,
+    Option<WorkerOptions>,
+  )>,
+}
+
+impl Default for TraceBuilder {
+  fn default() -> Self {
+    Self {
+      capacity: 1024,
+      channel_capacity: 64,
+      listener_queue_capacity: None,
+      kv_slots: None,
+      worker_queue_room: 0,
+      overflow_policy: OverflowPolicy::default(),
+      batching: Batching::default(),
+      service_name: "ttlog".to_string(),
+      storage_path: "./tmp/".to_string(),
+      storage_layout: StorageLayout::Flat,
+      snapshot_interval: Some(Duration::from_secs(60)),
+      snapshot_modes: SnapshotModes::default(),
+      level: LogLevel::WARN,
+      directives: None,
+      panic_hook: true,
+      signal_hook: false,
+      listeners: Vec::new(),
+    }
+  }
+}
+
+impl fmt::Debug for TraceBuilder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("TraceBuilder")
+      .field("capacity", &self.capacity)
+      .field("channel_capacity", &self.channel_capacity)
+      .field("listener_queue_capacity", &self.listener_queue_capacity)
+      .field("kv_slots", &self.kv_slots)
+      .field("worker_queue_room", &self.worker_queue_room)
+      .field("overflow_policy", &self.overflow_policy)
+      .field("batching", &self.batching)
+      .field("service_name", &self.service_name)
+      .field("storage_path", &self.storage_path)
+      .field("storage_layout", &self.storage_layout)
+      .field("snapshot_interval", &self.snapshot_interval)
+      .field("snapshot_modes", &self.snapshot_modes)
+      .field("level", &self.level)
+      .field("directives", &self.directives)
+      .field("panic_hook", &self.panic_hook)
+      .field("signal_hook", &self.signal_hook)
+      .field("listeners", &self.listeners.len())
+      .finish()
+  }
+}
+
+impl TraceBuilder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Number of events kept in the snapshot ring buffer.
+  pub fn capacity(mut self, capacity: usize) -> Self {
+    self.capacity = capacity;
+    self
+  }
+
+  /// Capacity of the control message channel (snapshot requests, shutdown).
+  pub fn channel_capacity(mut self, channel_capacity: usize) -> Self {
+    self.channel_capacity = channel_capacity;
+    self
+  }
+
+  /// Number of events waiting for the listeners before `overflow_policy`
+  /// applies. Defaults to the ring buffer capacity or, when
+  /// [`Self::kv_arena_slots`] is set, to an even share of the slots beyond it
+  /// with the worker queues that have no capacity of their own.
+  pub fn listener_queue_capacity(mut self, capacity: usize) -> Self {
+    self.listener_queue_capacity = Some(capacity);
+    self
+  }
+
+  /// Number of per-event KV payloads kept at once, see
+  /// [`crate::kv_arena::KvArena`]. Defaults to the ring buffer capacity plus
+  /// the capacity of the shared listener queue and of every worker queue,
+  /// plus [`Self::worker_queue_room`]. [`Self::build`] fails if the slots
+  /// beyond the ring buffer cannot hold all of those at once.
+  pub fn kv_arena_slots(mut self, slots: usize) -> Self {
+    self.kv_slots = Some(slots);
+    self
+  }
+
+  /// Events the queues of workers added later with
+  /// [`Trace::add_listener_on_worker`] may hold together, set aside in the
+  /// KV arena. Defaults to none, so such workers fail unless the arena was
+  /// sized with room to spare.
+  pub fn worker_queue_room(mut self, events: usize) -> Self {
+    self.worker_queue_room = events;
+    self
+  }
+
+  pub(crate) fn queue_capacity(&self) -> usize {
+    match (self.listener_queue_capacity, self.kv_slots) {
+      (Some(capacity), _) => capacity,
+      (None, Some(slots)) => {
+        let workers = self.worker_queues();
+        let sized: usize = workers.clone().flatten().sum();
+        let defaulted = workers.filter(Option::is_none).count();
+        slots.saturating_sub(self.capacity + sized + self.worker_queue_room) / (1 + defaulted)
+      },
+      (None, None) => self.capacity,
+    }
+  }
+
+  /// The queue capacity of each worker listener, `None` where it defaults to
+  /// the shared queue's.
+  fn worker_queues(&self) -> impl Iterator<Item = Option<usize>> + Clone + '_ {
+    self
+      .listeners
+      .iter()
+      .filter_map(|(_, _, options)| options.map(|options| options.queue_capacity))
+  }
+
+  /// Events the shared queue and every worker queue, including those added
+  /// later, hold together.
+  fn queued_events(&self) -> usize {
+    let shared = self.queue_capacity();
+    shared
+      + self.worker_queue_room
+      + self
+        .worker_queues()
+        .map(|capacity| capacity.unwrap_or(shared))
+        .sum::<usize>()
+  }
+
+  pub(crate) fn arena_slots(&self) -> usize {
+    self
+      .kv_slots
+      .unwrap_or(self.capacity + self.queued_events())
+  }
+
+  /// Reject listener queues whose events could outlive their KV payloads.
+  pub(crate) fn validate(&self) -> Result<(), TraceError> {
+    let room = self.arena_slots().saturating_sub(self.capacity);
+    let queue_capacity = self.queued_events();
+    if queue_capacity > room {
+      return Err(TraceError::QueueExceedsArena {
+        queue_capacity,
+        room,
+      });
+    }
+    Ok(())
+  }
+
+  /// What happens to events when the listener queue is full. Defaults to
+  /// [`OverflowPolicy::DropNewest`]; see [`Trace::dropped_events`].
+  pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+    self.overflow_policy = policy;
+    self
+  }
+
+  /// How listeners receive events through `LogListener::handle_batch`: at
+  /// most `max_events` at a time, waiting up to `max_wait` after the first
+  /// event of a batch for more. Defaults to 256 events and 100µs.
+  pub fn listener_batching(mut self, max_events: usize, max_wait: Duration) -> Self {
+    self.batching = Batching {
+      max_events: max_events.max(1),
+      max_wait,
+    };
+    self
+  }
+
+  /// Service name embedded in snapshots.
+  pub fn service_name(mut self, service_name: impl Into<String>) -> Self {
+    self.service_name = service_name.into();
+    self
+  }
+
+  /// Directory snapshots are written to. Defaults to `./tmp/`.
+  pub fn storage_path(mut self, storage_path: impl Into<String>) -> Self {
+    self.storage_path = storage_path.into();
+    self
+  }
+
+  pub fn storage_layout(mut self, layout: StorageLayout) -> Self {
+    self.storage_layout = layout;
+    self
+  }
+
+  /// Interval of the periodic snapshot, `None` to only snapshot on request,
+  /// panic, signal and shutdown. Defaults to 60 seconds.
+  pub fn snapshot_interval(mut self, interval: Option<Duration>) -> Self {
+    self.snapshot_interval = interval;
+    self
+  }
+
+  /// Whether snapshots taken for `trigger` drain the ring buffer or leave
+  /// the events in it. Every trigger drains by default.
+  pub fn snapshot_mode(mut self, trigger: SnapshotTrigger, mode: SnapshotMode) -> Self {
+    self.snapshot_modes.set(trigger, mode);
+    self
+  }
+
+  /// Initial default level. Defaults to `WARN`.
+  pub fn level(mut self, level: LogLevel) -> Self {
+    self.level = level;
+    self
+  }
+
+  /// Initial level directives; a default level in them overrides [`Self::level`].
+  pub fn directives(mut self, directives: Directives) -> Self {
+    self.directives = Some(directives);
+    self
+  }
+
+  /// Install the panic hook that snapshots on panic. Defaults to `true`.
+  pub fn panic_hook(mut self, install: bool) -> Self {
+    self.panic_hook = install;
+    self
+  }
+
+  /// Install the signal handler that snapshots on `SIGINT`, `SIGTERM` and
+  /// friends. Unix only; ignored elsewhere. Defaults to `false`.
+  pub fn signal_hook(mut self, install: bool) -> Self {
+    self.signal_hook = install;
+    self
+  }
+
+  /// Attach a listener as soon as the listener thread runs.
+  pub fn listener(self, listener: Arc<dyn LogListener + UnwindSafe + RefUnwindSafe>) -> Self {
+    self.listener_with_filter(listener, ListenerFilter::default())
+  }
+
+  pub fn listener_with_filter(
+    mut self,
+    listener: Arc<dyn LogListener + UnwindSafe + RefUnwindSafe>,
+    filter: ListenerFilter,
+  ) -> Self {
+    self.listeners.push((listener, filter, None));
+    self
+  }
+
+  /// Attach a listener on its own worker thread, see
+  /// [`Trace::add_listener_on_worker`].
+  pub fn listener_on_worker(
+    mut self,
+    listener: Arc<dyn LogListener + UnwindSafe + RefUnwindSafe>,
+    filter: ListenerFilter,
+    options: WorkerOptions,
+  ) -> Self {
+    self.listeners.push((listener, filter, Some(options)));
+    self
+  }
+
+  /// Start the logger and install it as `GLOBAL_LOGGER`.
+  pub fn build(self) -> Result<Trace, TraceError> {
+    Trace::start(self, true).map(|(trace, _)| trace)
+  }
+
+  /// Like [`Self::build`], also returning the IDs of the configured
+  /// listeners in the order they were added.
+  pub fn build_with_listener_ids(self) -> Result<(Trace, Vec<ListenerId>), TraceError> {
+    Trace::start(self, true)
+  }
+
+  /// Start a logger that is not registered as `GLOBAL_LOGGER`, for tests or
+  /// per-tenant logging. Any number can coexist. The macros reach it through
+  /// `info!(logger: &trace, ...)` or [`Trace::scoped`].
+  ///
+  /// The panic and signal hooks are process-wide and stay with the global
+  /// logger, so they are not installed; use [`Trace::request_snapshot`].
+  pub fn build_standalone(self) -> Result<Trace, TraceError> {
+    Trace::start(self, false).map(|(trace, _)| trace)
+  }
+}
@@ -0,0 +1,236 @@
+use std::fmt;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::event::LogLevel;
+use crate::format;
+use crate::kv_codec::{self, FieldEncoder};
+use crate::string_interner::StringInterner;
+use crate::trace;
+
+/// `tracing_subscriber::Layer` that forwards `tracing` events into the current
+/// ttlog [`Trace`](crate::trace::Trace) (see [`crate::trace::current`]).
+///
+/// Target and file are interned through the logger's `StringInterner`; event
+/// fields, together with the fields of every enclosing span (outermost first,
+/// inner values win), are recorded as KV. Events are filtered against
+/// `Trace::level` and the target directives, and dropped silently until
+/// `Trace::init` has run.
+///
+/// Unlike the ttlog macros, `tracing` hands over an already formatted message
+/// and cannot tell a static one apart, so the text is stored in the KV payload
+/// under [`format::ARGS_KEY`] with the [`format::FORMATTED_TEMPLATE`] template
+/// instead of being interned.
+///
+/// ```ignore
+/// use tracing_subscriber::prelude::*;
+///
+/// tracing_subscriber::registry().with(ttlog::tracing_layer::TtlogLayer::new()).init();
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TtlogLayer {
+  _private: (),
+}
+
+impl TtlogLayer {
+  pub fn new() -> Self {
+    Self { _private: () }
+  }
+}
+
+/// Fields recorded on a span, stored in the span's extensions. Keys are IDs
+/// from `interner`, the interner of the logger current when the span was
+/// created.
+struct SpanFields {
+  interner: Arc<StringInterner>,
+  fields: FieldEncoder,
+}
+
+impl SpanFields {
+  /// Append the fields to `kv`, whose keys come from `interner`.
+  fn encode_into(&self, kv: &mut FieldEncoder, interner: &StringInterner) {
+    if std::ptr::eq(&*self.interner, interner) {
+      kv.append(&self.fields);
+      return;
+    }
+
+    // Recorded under another logger: re-intern the keys.
+    if let Some(Value::Object(fields)) = kv_codec::decode(self.fields.as_bytes(), &self.interner) {
+      for (key, value) in &fields {
+        kv.field(interner.intern_key(key), value);
+      }
+    }
+  }
+}
+
+struct FieldVisitor<'a> {
+  interner: &'a StringInterner,
+  message: Option<String>,
+  fields: FieldEncoder,
+}
+
+impl<'a> FieldVisitor<'a> {
+  fn new(interner: &'a StringInterner) -> Self {
+    Self {
+      interner,
+      message: None,
+      fields: FieldEncoder::new(),
+    }
+  }
+
+  fn record<T: Serialize + ToString + ?Sized>(&mut self, field: &Field, value: &T) {
+    if field.name() == "message" {
+      self.message = Some(value.to_string());
+    } else {
+      self
+        .fields
+        .field(self.interner.intern_key(field.name()), value);
+    }
+  }
+}
+
+impl Visit for FieldVisitor<'_> {
+  fn record_f64(&mut self, field: &Field, value: f64) {
+    self.record(field, &value);
+  }
+
+  fn record_i64(&mut self, field: &Field, value: i64) {
+    self.record(field, &value);
+  }
+
+  fn record_u64(&mut self, field: &Field, value: u64) {
+    self.record(field, &value);
+  }
+
+  fn record_i128(&mut self, field: &Field, value: i128) {
+    self.record(field, &value);
+  }
+
+  fn record_u128(&mut self, field: &Field, value: u128) {
+    self.record(field, &value);
+  }
+
+  fn record_bool(&mut self, field: &Field, value: bool) {
+    self.record(field, &value);
+  }
+
+  fn record_str(&mut self, field: &Field, value: &str) {
+    self.record(field, value);
+  }
+
+  fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+    self.record(field, value.to_string().as_str());
+  }
+
+  fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+    self.record(field, format!("{:?}", value).as_str());
+  }
+}
+
+impl<S> Layer<S> for TtlogLayer
+where
+  S: Subscriber + for<'a> LookupSpan<'a>,
+{
+  fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+    let (Some(span), Some(logger)) = (ctx.span(id), trace::current()) else {
+      return;
+    };
+
+    let mut visitor = FieldVisitor::new(&logger.interner);
+    attrs.record(&mut visitor);
+    let fields = visitor.fields;
+    span.extensions_mut().insert(SpanFields {
+      interner: logger.interner.clone(),
+      fields,
+    });
+  }
+
+  fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+    let Some(span) = ctx.span(id) else {
+      return;
+    };
+
+    let mut extensions = span.extensions_mut();
+    match extensions.get_mut::<SpanFields>() {
+      Some(span_fields) => {
+        let mut visitor = FieldVisitor::new(&span_fields.interner);
+        values.record(&mut visitor);
+        let fields = visitor.fields;
+        span_fields.fields.append(&fields);
+      },
+      None => {
+        let Some(logger) = trace::current() else {
+          return;
+        };
+        let mut visitor = FieldVisitor::new(&logger.interner);
+        values.record(&mut visitor);
+        let fields = visitor.fields;
+        extensions.insert(SpanFields {
+          interner: logger.interner.clone(),
+          fields,
+        });
+      },
+    }
+  }
+
+  fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+    let Some(logger) = trace::current() else {
+      return;
+    };
+
+    let metadata = event.metadata();
+    let level = LogLevel::from_tracing_level(metadata.level());
+    if !logger.is_enabled(level as u8, metadata.target()) {
+      return;
+    }
+
+    let interner = &logger.interner;
+    let mut kv = FieldEncoder::new();
+    if let Some(scope) = ctx.event_scope(event) {
+      for span in scope.from_root() {
+        if let Some(fields) = span.extensions().get::<SpanFields>() {
+          fields.encode_into(&mut kv, interner);
+        }
+      }
+    }
+
+    let mut visitor = FieldVisitor::new(interner);
+    event.record(&mut visitor);
+    kv.append(&visitor.fields);
+
+    let target_id = interner.intern_target(metadata.target());
+    let file_id = interner.intern_file(metadata.file().unwrap_or(""));
+    let message_id = match visitor.message.filter(|message| !message.is_empty()) {
+      Some(message) => {
+        kv.field(interner.intern_key(format::ARGS_KEY), message.as_str());
+        NonZeroU32::new(interner.intern_message(format::FORMATTED_TEMPLATE))
+      },
+      None => None,
+    };
+
+    let kv_id = if kv.is_empty() {
+      None
+    } else {
+      NonZeroU32::new(interner.store_kv(kv.finish()))
+    };
+
+    logger.send_event_fast(
+      level as u8,
+      target_id,
+      message_id,
+      crate::thread_registry::current_id(),
+      file_id,
+      (metadata.line().unwrap_or(0), 0),
+      kv_id,
+    );
+  }
+}
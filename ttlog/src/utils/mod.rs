@@ -5,3 +5,10 @@ pub fn current_thread_id_u32() -> u32 {
   std::thread::current().id().hash(&mut hasher);
   hasher.finish() as u32
 }
+
+/// Whether `target` is the module `prefix` or one of its submodules. A
+/// trailing `::*` on the prefix is accepted and means the same thing.
+pub fn target_matches(target: &str, prefix: &str) -> bool {
+  let prefix = prefix.strip_suffix("::*").unwrap_or(prefix);
+  target == prefix || (target.starts_with(prefix) && target[prefix.len()..].starts_with("::"))
+}
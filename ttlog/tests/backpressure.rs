@@ -0,0 +1,127 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ttlog::backpressure::{OverflowPolicy, DROP_REPORT_TARGET};
+use ttlog::event::{LogEvent, LogLevel};
+use ttlog::listener::LogListener;
+use ttlog::snapshot::ResolvedEvent;
+use ttlog::string_interner::StringInterner;
+use ttlog::trace::Trace;
+use ttlog::trace_builder::TraceBuilder;
+use ttlog::ttlog_macros::info;
+
+/// Holds the listener thread on its first event until the gate opens.
+struct GatedListener {
+  gate: Mutex<Option<std::sync::mpsc::Receiver<()>>>,
+  events: Mutex<Vec<ResolvedEvent>>,
+}
+
+impl LogListener for GatedListener {
+  fn handle(&self, event: &LogEvent, interner: &StringInterner) {
+    if let Some(gate) = self.gate.lock().unwrap().take() {
+      let _ = gate.recv();
+    }
+    if let Some(event) = ResolvedEvent::resolve(event, interner) {
+      self.events.lock().unwrap().push(event);
+    }
+  }
+}
+
+fn gated(
+  policy: OverflowPolicy,
+  dir: &tempfile::TempDir,
+) -> (Trace, Arc<GatedListener>, std::sync::mpsc::Sender<()>) {
+  let (open, gate) = std::sync::mpsc::channel();
+  let listener = Arc::new(GatedListener {
+    gate: Mutex::new(Some(gate)),
+    events: Mutex::new(Vec::new()),
+  });
+  let trace = TraceBuilder::new()
+    .service_name("backpressure")
+    .storage_path(dir.path().to_str().unwrap())
+    .snapshot_interval(None)
+    .level(LogLevel::INFO)
+    .listener_queue_capacity(4)
+    .overflow_policy(policy)
+    .listener(listener.clone())
+    .build_standalone()
+    .unwrap();
+  (trace, listener, open)
+}
+
+fn messages(listener: &GatedListener) -> Vec<String> {
+  listener
+    .events
+    .lock()
+    .unwrap()
+    .iter()
+    .filter(|event| event.target != DROP_REPORT_TARGET)
+    .map(|event| event.rendered_message().into_owned())
+    .collect()
+}
+
+#[test]
+fn full_queue_drops_and_reports() {
+  let dir = tempfile::tempdir().unwrap();
+  let (mut trace, listener, open) = gated(OverflowPolicy::DropNewest, &dir);
+  assert_eq!(trace.overflow_policy(), OverflowPolicy::DropNewest);
+
+  for i in 0..50 {
+    info!(logger: &trace, "event {}", i);
+  }
+  assert!(trace.dropped_events().dropped_newest > 0);
+  assert_eq!(
+    trace.snapshot_buffer.len(),
+    50,
+    "the ring keeps every event"
+  );
+
+  open.send(()).unwrap();
+  trace.flush_listeners();
+  let delivered = messages(&listener);
+  assert_eq!(delivered.len() as u64 + trace.dropped_events().total(), 50);
+  assert_eq!(delivered[0], "event 0");
+
+  let deadline = Instant::now() + Duration::from_secs(5);
+  let report = loop {
+    let report = listener
+      .events
+      .lock()
+      .unwrap()
+      .iter()
+      .find(|event| event.target == DROP_REPORT_TARGET)
+      .cloned();
+    if let Some(report) = report {
+      break report;
+    }
+    assert!(Instant::now() < deadline, "no drop report");
+    std::thread::sleep(Duration::from_millis(50));
+  };
+  assert_eq!(report.level(), LogLevel::WARN);
+  assert_eq!(report.kv["dropped"], trace.dropped_events().total());
+  assert_eq!(report.kv["policy"], "drop_newest");
+  assert_eq!(
+    report.rendered_message(),
+    format!("dropped {} events", trace.dropped_events().total())
+  );
+
+  trace.shutdown();
+}
+
+#[test]
+fn drop_oldest_delivers_the_latest_events() {
+  let dir = tempfile::tempdir().unwrap();
+  let (mut trace, listener, open) = gated(OverflowPolicy::DropOldest, &dir);
+
+  for i in 0..50 {
+    info!(logger: &trace, "event {}", i);
+  }
+  assert!(trace.dropped_events().dropped_oldest > 0);
+
+  open.send(()).unwrap();
+  trace.flush_listeners();
+  let delivered = messages(&listener);
+  assert_eq!(delivered.last().unwrap(), "event 49");
+
+  trace.shutdown();
+}
@@ -0,0 +1,89 @@
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use ttlog::event::LogLevel;
+use ttlog::reload;
+use ttlog::trace::GLOBAL_LOGGER;
+use ttlog::ttlog_macros::{debug, error, info};
+
+fn write_config(path: &Path, log: &Path, level: &str, listener_level: &str) {
+  let config = format!(
+    r#"{{
+      "service_name": "reload_test",
+      "capacity": 256,
+      "level": "{level}",
+      "storage": {{ "path": "{storage}" }},
+      "snapshots": {{ "interval_secs": 0, "panic": false }},
+      "listeners": [{{ "type": "file", "path": "{log}", "level": "{listener_level}" }}]
+    }}"#,
+    storage = path.parent().unwrap().display(),
+    log = log.display(),
+  );
+  fs::write(path, config).unwrap();
+}
+
+fn reload_errors() -> usize {
+  let logger = GLOBAL_LOGGER.get().unwrap();
+  logger
+    .snapshot_buffer
+    .take_snapshot()
+    .iter()
+    .filter(|event| {
+      event.level() == LogLevel::ERROR
+        && logger.interner.get_target(event.target_id).as_deref() == Some(reload::TARGET)
+    })
+    .count()
+}
+
+// GLOBAL_LOGGER can only be set once per process, so the whole lifecycle is
+// exercised in a single test.
+#[test]
+fn reload_applies_new_config_and_rejects_broken_files() {
+  let dir = tempfile::tempdir().unwrap();
+  let config_path = dir.path().join("ttlog.json");
+  let log_path = dir.path().join("app.log");
+  write_config(&config_path, &log_path, "info", "info");
+
+  let (mut trace, mut watcher) = reload::init_from_file(&config_path).unwrap();
+  // Reload by hand first so the watcher cannot race the assertions below.
+  watcher.stop();
+  assert_eq!(trace.get_level(), LogLevel::INFO);
+
+  info!("before reload");
+  debug!("debug before reload");
+
+  // Lower the global level but keep only errors in the file.
+  write_config(&config_path, &log_path, "debug", "error");
+  watcher.reload().unwrap();
+  assert_eq!(GLOBAL_LOGGER.get().unwrap().get_level(), LogLevel::DEBUG);
+
+  debug!("debug after reload");
+  error!("error after reload");
+
+  // A broken file is reported and leaves the running config alone.
+  GLOBAL_LOGGER.get().unwrap().snapshot_buffer.take_snapshot();
+  fs::write(&config_path, r#"{ "level": "loud" }"#).unwrap();
+  assert!(watcher.reload().is_err());
+  assert_eq!(GLOBAL_LOGGER.get().unwrap().get_level(), LogLevel::DEBUG);
+  assert_eq!(reload_errors(), 1);
+
+  // The watcher picks up file changes on its own.
+  watcher.watch(Duration::from_millis(20), false).unwrap();
+  std::thread::sleep(Duration::from_millis(50));
+  write_config(&config_path, &log_path, "warn", "error");
+  let deadline = Instant::now() + Duration::from_secs(5);
+  while GLOBAL_LOGGER.get().unwrap().get_level() != LogLevel::WARN {
+    assert!(Instant::now() < deadline, "watcher did not reload the file");
+    std::thread::sleep(Duration::from_millis(10));
+  }
+
+  watcher.stop();
+  trace.shutdown();
+
+  let log = fs::read_to_string(&log_path).unwrap();
+  assert!(log.contains("before reload"));
+  assert!(!log.contains("debug before reload"));
+  assert!(!log.contains("debug after reload"));
+  assert!(log.contains("error after reload"));
+}
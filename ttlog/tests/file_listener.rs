@@ -0,0 +1,247 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ttlog::event::{FieldValue, LogLevel};
+use ttlog::event_builder::EventBuilder;
+use ttlog::file_listener::{Compression, FileListener, RotationPolicy};
+use ttlog::listener::LogListener;
+use ttlog::string_interner::StringInterner;
+
+fn write_events(listener: &FileListener, count: usize) {
+  let interner = Arc::new(StringInterner::new());
+  let builder = EventBuilder::new(interner.clone());
+  for i in 0..count {
+    let event = builder.build_fast(i as u64, LogLevel::INFO, "rotation", "a line of log output");
+    listener.handle(&event, &interner);
+  }
+}
+
+fn rotated_segments(dir: &Path) -> Vec<String> {
+  let mut names: Vec<String> = fs::read_dir(dir)
+    .unwrap()
+    .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+    .filter(|name| name.starts_with("app.log."))
+    .collect();
+  names.sort();
+  names
+}
+
+#[test]
+fn file_listener_without_rotation_appends() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("app.log");
+
+  let listener = FileListener::new(path.to_str().unwrap()).unwrap();
+  write_events(&listener, 10);
+  listener.on_shutdown();
+
+  let contents = fs::read_to_string(&path).unwrap();
+  assert_eq!(contents.lines().count(), 10);
+  assert!(rotated_segments(dir.path()).is_empty());
+}
+
+#[test]
+fn file_listener_rotates_by_size_and_prunes() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("app.log");
+
+  let policy = RotationPolicy::new().max_size(64 * 1024).keep(2);
+  let listener = FileListener::with_rotation(path.to_str().unwrap(), policy).unwrap();
+  write_events(&listener, 5_000);
+  listener.on_shutdown();
+
+  let segments = rotated_segments(dir.path());
+  assert_eq!(segments.len(), 2);
+  for segment in &segments {
+    let size = fs::metadata(dir.path().join(segment)).unwrap().len();
+    assert!(size <= 64 * 1024, "{segment} is {size} bytes");
+  }
+  assert!(fs::metadata(&path).unwrap().len() > 0);
+}
+
+#[test]
+fn file_listener_prunes_only_rotated_segments() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("app.log");
+  let unrelated = [
+    "app.log.bak",
+    "app.log.old.txt",
+    "app.log.20240101T000000.000Z.bak",
+    "app.log.20240101T000000Z",
+  ];
+  for name in unrelated {
+    fs::write(dir.path().join(name), "keep me").unwrap();
+  }
+  fs::write(dir.path().join("app.log.20000101T000000.000Z-1.lz4"), "old").unwrap();
+
+  let policy = RotationPolicy::new().max_size(64 * 1024).keep(2);
+  let listener = FileListener::with_rotation(path.to_str().unwrap(), policy).unwrap();
+  write_events(&listener, 5_000);
+  listener.on_shutdown();
+
+  for name in unrelated {
+    assert!(dir.path().join(name).exists(), "{name} was pruned");
+  }
+  assert!(!dir
+    .path()
+    .join("app.log.20000101T000000.000Z-1.lz4")
+    .exists());
+  assert_eq!(rotated_segments(dir.path()).len(), 2 + unrelated.len());
+}
+
+#[test]
+fn file_listener_bounds_pending_lines_while_writes_fail() {
+  let dir = tempfile::tempdir().unwrap();
+  let logs = dir.path().join("logs");
+  let path = logs.join("app.log");
+
+  let policy = RotationPolicy::new().max_size(1024);
+  let listener = FileListener::with_rotation(path.to_str().unwrap(), policy).unwrap();
+  // Rotation renames the active file, which fails once its directory is gone.
+  fs::remove_dir_all(&logs).unwrap();
+  write_events(&listener, 50_000);
+
+  assert!(listener.dropped_lines() > 0);
+}
+
+#[test]
+fn file_listener_rotates_by_time() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("app.log");
+
+  let policy = RotationPolicy::new().every(Duration::from_millis(20));
+  let listener = FileListener::with_rotation(path.to_str().unwrap(), policy).unwrap();
+  write_events(&listener, 1);
+  std::thread::sleep(Duration::from_millis(30));
+  write_events(&listener, 1);
+  std::thread::sleep(Duration::from_millis(30));
+  write_events(&listener, 1);
+  listener.on_shutdown();
+
+  let segments = rotated_segments(dir.path());
+  assert!(!segments.is_empty());
+  let total: usize = segments
+    .iter()
+    .map(|s| {
+      fs::read_to_string(dir.path().join(s))
+        .unwrap()
+        .lines()
+        .count()
+    })
+    .sum::<usize>()
+    + fs::read_to_string(&path).unwrap().lines().count();
+  assert_eq!(total, 3);
+}
+
+#[test]
+fn file_listener_compresses_rotated_segments() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("app.log");
+
+  let policy = RotationPolicy::new()
+    .max_size(64 * 1024)
+    .compress(Compression::Lz4);
+  let listener = FileListener::with_rotation(path.to_str().unwrap(), policy).unwrap();
+  write_events(&listener, 2_000);
+  listener.on_shutdown();
+
+  let segments = rotated_segments(dir.path());
+  assert!(!segments.is_empty());
+  assert!(segments.iter().all(|s| s.ends_with(".lz4")));
+
+  let mut decoded = String::new();
+  lz4::Decoder::new(fs::File::open(dir.path().join(&segments[0])).unwrap())
+    .unwrap()
+    .read_to_string(&mut decoded)
+    .unwrap();
+  assert!(decoded
+    .lines()
+    .all(|line| serde_json::from_str::<serde_json::Value>(line).is_ok()));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn file_listener_gzips_rotated_segments() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("app.log");
+
+  let policy = RotationPolicy::new()
+    .max_size(64 * 1024)
+    .compress(Compression::Gzip);
+  let listener = FileListener::with_rotation(path.to_str().unwrap(), policy).unwrap();
+  write_events(&listener, 2_000);
+  listener.on_shutdown();
+
+  let segments = rotated_segments(dir.path());
+  assert!(!segments.is_empty());
+  assert!(segments.iter().all(|s| s.ends_with(".gz")));
+
+  let mut decoded = String::new();
+  flate2::read::GzDecoder::new(fs::File::open(dir.path().join(&segments[0])).unwrap())
+    .read_to_string(&mut decoded)
+    .unwrap();
+  assert!(decoded.lines().count() > 0);
+}
+
+#[test]
+fn file_listener_writes_typed_kv_values() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("app.log");
+  let listener = FileListener::new(path.to_str().unwrap()).unwrap();
+
+  let interner = Arc::new(StringInterner::new());
+  let builder = EventBuilder::new(interner.clone());
+  let event = builder.build_with_fields(
+    0,
+    LogLevel::INFO,
+    "typed",
+    "typed values",
+    &[
+      ("count".to_string(), FieldValue::U64(42)),
+      ("big".to_string(), FieldValue::U64(u64::MAX)),
+      ("ratio".to_string(), FieldValue::F64(0.25)),
+    ],
+  );
+  listener.handle(&event, &interner);
+  listener.on_shutdown();
+
+  let contents = fs::read_to_string(&path).unwrap();
+  let line: serde_json::Value = serde_json::from_str(contents.lines().next().unwrap()).unwrap();
+  assert_eq!(line["kv"]["count"], serde_json::json!(42));
+  assert_eq!(line["kv"]["big"], serde_json::json!(u64::MAX));
+  assert_eq!(line["kv"]["ratio"], serde_json::json!(0.25));
+  assert!(line["seq"].as_u64().unwrap() > 0);
+  assert_eq!(line["timestamp_ns"], serde_json::json!(0));
+}
+
+#[test]
+fn file_listener_writes_each_batch_immediately() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("app.log");
+  let listener = FileListener::new(path.to_str().unwrap()).unwrap();
+
+  let interner = Arc::new(StringInterner::new());
+  let builder = EventBuilder::new(interner.clone());
+  let events: Vec<_> = (0..5)
+    .map(|i| builder.build_fast(i, LogLevel::INFO, "batch", "batched line"))
+    .collect();
+  listener.handle_batch(&events, &interner);
+
+  // No shutdown: a batch does not wait for the buffer threshold.
+  let contents = fs::read_to_string(&path).unwrap();
+  let seqs: Vec<u64> = contents
+    .lines()
+    .map(|line| {
+      serde_json::from_str::<serde_json::Value>(line).unwrap()["seq"]
+        .as_u64()
+        .unwrap()
+    })
+    .collect();
+  assert_eq!(
+    seqs,
+    events.iter().map(|event| event.seq).collect::<Vec<_>>()
+  );
+}
@@ -0,0 +1,54 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crossbeam_channel::{bounded, unbounded};
+use ttlog::event::{LogEvent, LogLevel};
+use ttlog::lf_buffer::LockFreeRingBuffer;
+use ttlog::string_interner::{StringInterner, EXHAUSTED_MESSAGE, EXHAUSTED_PLACEHOLDER};
+use ttlog::trace::{EventBroadcast, ListenerMessage, Message, Trace};
+
+#[test]
+fn exhausted_interner_logs_one_warning_per_kind() {
+  let (msg_tx, _msg_rx) = bounded::<Message>(8);
+  let (listener_tx, _listener_rx) = bounded::<ListenerMessage>(8);
+  let (event_tx, _event_rx) = unbounded::<EventBroadcast>();
+
+  let trace = Trace::new(
+    msg_tx,
+    listener_tx,
+    event_tx,
+    Arc::new(StringInterner::with_capacity_limit(4)),
+    Arc::new(LockFreeRingBuffer::<LogEvent>::new(64)),
+  );
+  trace.level.store(LogLevel::INFO as u8, Ordering::Relaxed);
+
+  for i in 0..4 {
+    let message = format!("dynamic message {}", i);
+    let message_id = trace.interner.intern_message(&message);
+    trace.send_event_fast(
+      LogLevel::INFO as u8,
+      0,
+      std::num::NonZeroU32::new(message_id),
+      0,
+      0,
+      (0, 0),
+      None,
+    );
+  }
+
+  let messages: Vec<String> = trace
+    .snapshot_buffer
+    .take_snapshot()
+    .iter()
+    .filter_map(|event| event.message_id)
+    .filter_map(|id| trace.interner.get_message(id.get()))
+    .map(|message| message.to_string())
+    .collect();
+
+  assert_eq!(
+    messages.iter().filter(|m| *m == EXHAUSTED_MESSAGE).count(),
+    1
+  );
+  assert!(messages.iter().any(|m| m == EXHAUSTED_PLACEHOLDER));
+  assert!(trace.interner.exhausted() > 0);
+}
@@ -0,0 +1,64 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ttlog::event::{LogEvent, LogLevel};
+use ttlog::listener::{ListenerFilter, LogListener};
+use ttlog::string_interner::StringInterner;
+use ttlog::trace_builder::TraceBuilder;
+use ttlog::ttlog_macros::{info, warn};
+
+#[derive(Default)]
+struct BatchRecorder {
+  batches: Mutex<Vec<usize>>,
+  singles: Mutex<usize>,
+}
+
+impl LogListener for BatchRecorder {
+  fn handle(&self, _event: &LogEvent, _interner: &StringInterner) {
+    *self.singles.lock().unwrap() += 1;
+  }
+
+  fn handle_batch(&self, events: &[LogEvent], _interner: &StringInterner) {
+    self.batches.lock().unwrap().push(events.len());
+  }
+}
+
+#[test]
+fn listeners_receive_events_in_batches() {
+  let all = Arc::new(BatchRecorder::default());
+  let warnings = Arc::new(BatchRecorder::default());
+  let dir = tempfile::tempdir().unwrap();
+  let mut trace = TraceBuilder::new()
+    .service_name("batching")
+    .storage_path(dir.path().to_str().unwrap())
+    .snapshot_interval(None)
+    .level(LogLevel::INFO)
+    .listener_batching(16, Duration::from_millis(50))
+    .listener(all.clone())
+    .listener_with_filter(
+      warnings.clone(),
+      ListenerFilter::new().min_level(LogLevel::WARN),
+    )
+    .build_standalone()
+    .unwrap();
+
+  for i in 0..100 {
+    if i % 10 == 0 {
+      warn!(logger: &trace, "warning {}", i);
+    } else {
+      info!(logger: &trace, "event {}", i);
+    }
+  }
+  trace.flush_listeners();
+
+  let batches = all.batches.lock().unwrap().clone();
+  assert_eq!(batches.iter().sum::<usize>(), 100);
+  assert!(batches.iter().all(|&len| (1..=16).contains(&len)));
+  assert!(batches.len() < 100, "events were delivered one by one");
+  assert_eq!(*all.singles.lock().unwrap(), 0);
+
+  let filtered = warnings.batches.lock().unwrap().clone();
+  assert_eq!(filtered.iter().sum::<usize>(), 10);
+
+  trace.shutdown();
+}
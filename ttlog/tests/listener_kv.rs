@@ -0,0 +1,97 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ttlog::backpressure::{OverflowPolicy, DROP_REPORT_TARGET};
+use ttlog::event::{LogEvent, LogLevel};
+use ttlog::kv_codec;
+use ttlog::listener::{ListenerFilter, LogListener};
+use ttlog::listener_worker::WorkerOptions;
+use ttlog::string_interner::StringInterner;
+use ttlog::trace_builder::TraceBuilder;
+use ttlog::ttlog_macros::info;
+
+/// Holds its thread on the first event until the gate opens, then records
+/// the `i` field of every event, or `None` if its KV could not be resolved.
+struct GatedRecorder {
+  gate: Mutex<Option<std::sync::mpsc::Receiver<()>>>,
+  seen: Mutex<Vec<Option<u64>>>,
+}
+
+impl GatedRecorder {
+  fn new(gate: std::sync::mpsc::Receiver<()>) -> Self {
+    Self {
+      gate: Mutex::new(Some(gate)),
+      seen: Mutex::new(Vec::new()),
+    }
+  }
+}
+
+impl LogListener for GatedRecorder {
+  fn handle(&self, event: &LogEvent, interner: &StringInterner) {
+    if let Some(gate) = self.gate.lock().unwrap().take() {
+      let _ = gate.recv();
+    }
+    let i = event
+      .kv_id
+      .and_then(|id| interner.get_kv(id.get()))
+      .and_then(|bytes| kv_codec::decode(&bytes, interner))
+      .and_then(|kv| kv["i"].as_u64());
+    self.seen.lock().unwrap().push(i);
+  }
+}
+
+#[test]
+fn lagging_listeners_keep_the_kv_of_every_event() {
+  let (open_shared, shared_gate) = std::sync::mpsc::channel();
+  let (open_worker, worker_gate) = std::sync::mpsc::channel();
+  let shared = Arc::new(GatedRecorder::new(shared_gate));
+  let worker = Arc::new(GatedRecorder::new(worker_gate));
+  let no_reports = || ListenerFilter::new().deny_target(DROP_REPORT_TARGET);
+
+  let dir = tempfile::tempdir().unwrap();
+  let trace = TraceBuilder::new()
+    .service_name("listener-kv")
+    .storage_path(dir.path().to_str().unwrap())
+    .capacity(16)
+    .listener_queue_capacity(48)
+    .overflow_policy(OverflowPolicy::DropNewest)
+    .snapshot_interval(None)
+    .level(LogLevel::INFO)
+    .listener_on_worker(
+      worker.clone(),
+      no_reports(),
+      WorkerOptions::new()
+        .queue_capacity(48)
+        .overflow_policy(OverflowPolicy::DropNewest),
+    )
+    .listener_with_filter(shared.clone(), no_reports())
+    .build_standalone()
+    .unwrap();
+
+  // Both listeners stall on the first event while far more payloads than
+  // the arena has slots are stored behind it.
+  info!(logger: &trace, "first", i = 0u64);
+  std::thread::sleep(Duration::from_millis(50));
+  let logged = 100 * trace.interner.kv_slots() as u64;
+  for i in 1..logged {
+    info!(logger: &trace, "event", i = i);
+  }
+
+  // Stores that reached a slot pinned by a queued event went without KVs.
+  assert!(trace.dropped_events().kv_dropped > 0);
+
+  open_shared.send(()).unwrap();
+  open_worker.send(()).unwrap();
+  trace.flush_listeners();
+
+  for listener in [&shared, &worker] {
+    let seen = listener.seen.lock().unwrap();
+    assert!(seen.len() > 1);
+    assert!(
+      seen.iter().all(Option::is_some),
+      "{} of {} events lost their KV",
+      seen.iter().filter(|i| i.is_none()).count(),
+      seen.len()
+    );
+  }
+}
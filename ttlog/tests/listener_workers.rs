@@ -0,0 +1,203 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ttlog::backpressure::{OverflowPolicy, DROP_REPORT_TARGET};
+use ttlog::event::{LogEvent, LogLevel};
+use ttlog::listener::{ListenerFilter, LogListener};
+use ttlog::listener_worker::{ListenerStatus, WorkerOptions};
+use ttlog::string_interner::StringInterner;
+use ttlog::trace_builder::{TraceBuilder, TraceError};
+use ttlog::ttlog_macros::{info, warn};
+
+/// Blocks its thread on the first event until the gate opens.
+struct StuckListener {
+  gate: Mutex<Option<std::sync::mpsc::Receiver<()>>>,
+  handled: Mutex<usize>,
+}
+
+impl LogListener for StuckListener {
+  fn handle(&self, _event: &LogEvent, _interner: &StringInterner) {
+    if let Some(gate) = self.gate.lock().unwrap().take() {
+      let _ = gate.recv();
+    }
+    *self.handled.lock().unwrap() += 1;
+  }
+}
+
+#[derive(Default)]
+struct CountingListener {
+  handled: Mutex<usize>,
+}
+
+impl LogListener for CountingListener {
+  fn handle(&self, _event: &LogEvent, _interner: &StringInterner) {
+    *self.handled.lock().unwrap() += 1;
+  }
+}
+
+#[test]
+fn stuck_worker_does_not_stall_shared_listeners() {
+  let (open, gate) = std::sync::mpsc::channel();
+  let stuck = Arc::new(StuckListener {
+    gate: Mutex::new(Some(gate)),
+    handled: Mutex::new(0),
+  });
+  let fast = Arc::new(CountingListener::default());
+
+  let dir = tempfile::tempdir().unwrap();
+  let trace = TraceBuilder::new()
+    .service_name("listener-workers")
+    .storage_path(dir.path().to_str().unwrap())
+    .snapshot_interval(None)
+    .level(LogLevel::INFO)
+    .worker_queue_room(8)
+    .build_standalone()
+    .unwrap();
+  let stuck_id = trace
+    .add_listener_on_worker(
+      stuck.clone(),
+      // Keep the drop reports out of the counts below.
+      ListenerFilter::new().deny_target(DROP_REPORT_TARGET),
+      WorkerOptions::new()
+        .queue_capacity(8)
+        .overflow_policy(OverflowPolicy::DropNewest),
+    )
+    .unwrap();
+  let fast_id = trace.add_listener(fast.clone());
+
+  // The first event parks the worker; the rest fill its queue and overflow.
+  info!(logger: &trace, "first");
+  std::thread::sleep(Duration::from_millis(50));
+  for i in 0..100 {
+    info!(logger: &trace, "event {}", i);
+  }
+
+  // Wait for the shared listener only: the stuck worker must not hold it up.
+  let deadline = std::time::Instant::now() + Duration::from_secs(5);
+  while *fast.handled.lock().unwrap() < 101 {
+    assert!(
+      std::time::Instant::now() < deadline,
+      "shared listener stalled"
+    );
+    std::thread::sleep(Duration::from_millis(5));
+  }
+
+  let stuck_health = trace.listener_health(stuck_id).unwrap();
+  assert!(stuck_health.dedicated);
+  assert_eq!(stuck_health.status, ListenerStatus::Backlogged);
+  assert_eq!(stuck_health.queued, 8);
+  assert!(stuck_health.dropped.dropped_newest > 0);
+
+  let fast_health = trace.listener_health(fast_id).unwrap();
+  assert!(!fast_health.dedicated);
+  assert_eq!(fast_health.status, ListenerStatus::Healthy);
+  assert!(fast_health.delivered >= 101);
+
+  open.send(()).unwrap();
+  trace.flush_listeners();
+  assert_eq!(
+    *stuck.handled.lock().unwrap() as u64 + stuck_health.dropped.total(),
+    101
+  );
+}
+
+#[test]
+fn removing_a_worker_listener_stops_its_thread() {
+  let dir = tempfile::tempdir().unwrap();
+  let trace = TraceBuilder::new()
+    .service_name("listener-workers-remove")
+    .storage_path(dir.path().to_str().unwrap())
+    .snapshot_interval(None)
+    .level(LogLevel::INFO)
+    .worker_queue_room(16)
+    .build_standalone()
+    .unwrap();
+  let listener = Arc::new(CountingListener::default());
+  let id = trace
+    .add_listener_on_worker(
+      listener.clone(),
+      ListenerFilter::new(),
+      WorkerOptions::default(),
+    )
+    .unwrap();
+
+  for i in 0..10 {
+    info!(logger: &trace, "event {}", i);
+  }
+  trace.flush_listeners();
+  assert_eq!(*listener.handled.lock().unwrap(), 10);
+  assert_eq!(trace.listener_health(id).unwrap().delivered, 10);
+
+  assert!(trace.remove_listener(id));
+  assert!(trace.listener_health(id).is_none());
+}
+
+#[test]
+fn builder_attaches_worker_listeners() {
+  let listener = Arc::new(CountingListener::default());
+  let dir = tempfile::tempdir().unwrap();
+  let trace = TraceBuilder::new()
+    .service_name("listener-workers-builder")
+    .storage_path(dir.path().to_str().unwrap())
+    .snapshot_interval(None)
+    .level(LogLevel::INFO)
+    .listener_on_worker(
+      listener.clone(),
+      ListenerFilter::new().min_level(LogLevel::WARN),
+      WorkerOptions::default(),
+    )
+    .build_standalone()
+    .unwrap();
+
+  info!(logger: &trace, "filtered out");
+  warn!(logger: &trace, "kept");
+  trace.flush_listeners();
+  assert_eq!(*listener.handled.lock().unwrap(), 1);
+}
+
+#[test]
+fn runtime_workers_share_the_room_left_in_the_arena() {
+  let dir = tempfile::tempdir().unwrap();
+  let trace = TraceBuilder::new()
+    .service_name("listener-workers-room")
+    .storage_path(dir.path().to_str().unwrap())
+    .snapshot_interval(None)
+    .capacity(64)
+    .listener_queue_capacity(64)
+    .kv_arena_slots(256)
+    .build_standalone()
+    .unwrap();
+  let add = |capacity| {
+    trace.add_listener_on_worker(
+      Arc::new(CountingListener::default()),
+      ListenerFilter::new(),
+      WorkerOptions::new().queue_capacity(capacity),
+    )
+  };
+
+  // 128 slots are left beyond the ring and the shared queue.
+  let first = add(100).unwrap();
+  assert!(matches!(
+    add(100),
+    Err(TraceError::QueueExceedsArena {
+      queue_capacity: 100,
+      room: 28,
+    })
+  ));
+
+  // The default shrinks to what is left, and takes all of it.
+  trace
+    .add_listener_on_worker(
+      Arc::new(CountingListener::default()),
+      ListenerFilter::new(),
+      WorkerOptions::default(),
+    )
+    .unwrap();
+  assert!(matches!(
+    add(1),
+    Err(TraceError::QueueExceedsArena { room: 0, .. })
+  ));
+
+  assert!(trace.remove_listener(first));
+  add(100).unwrap();
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "log")]
+
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, Once};
+
+use crossbeam_channel::{bounded, unbounded};
+use log::LevelFilter;
+use ttlog::event::{LogEvent, LogLevel};
+use ttlog::lf_buffer::LockFreeRingBuffer;
+use ttlog::log_facade::TtlogLogger;
+use ttlog::string_interner::StringInterner;
+use ttlog::trace::{EventBroadcast, ListenerMessage, Message, Trace, GLOBAL_LOGGER};
+
+static TEST_LOCK: Mutex<()> = Mutex::new(());
+static INIT_LOG: Once = Once::new();
+
+fn lock_tests() -> std::sync::MutexGuard<'static, ()> {
+  TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn ensure_global_logger() -> &'static Trace {
+  INIT_LOG.call_once(|| {
+    TtlogLogger::new()
+      .with_module_level("noisy", LevelFilter::Warn)
+      .with_module_level("noisy::important", LevelFilter::Debug)
+      .init()
+      .expect("log facade should only be installed once");
+  });
+
+  if let Some(logger) = GLOBAL_LOGGER.get() {
+    return logger;
+  }
+
+  let (msg_tx, _msg_rx) = bounded::<Message>(8);
+  let (listener_tx, _listener_rx) = bounded::<ListenerMessage>(8);
+  let (event_tx, _event_rx) = unbounded::<EventBroadcast>();
+
+  let trace = Trace::new(
+    msg_tx,
+    listener_tx,
+    event_tx,
+    Arc::new(StringInterner::new()),
+    Arc::new(LockFreeRingBuffer::<LogEvent>::new(128)),
+  );
+
+  let _ = GLOBAL_LOGGER.set(trace);
+  GLOBAL_LOGGER.get().unwrap()
+}
+
+fn message_of(logger: &Trace, event: &LogEvent) -> String {
+  let template = logger
+    .interner
+    .get_message(event.message_id.unwrap().get())
+    .unwrap();
+  let kv = event
+    .kv_id
+    .and_then(|id| logger.interner.get_kv(id.get()))
+    .map(|bytes| ttlog::kv_codec::decode(&bytes, &logger.interner).unwrap())
+    .unwrap_or_else(|| serde_json::json!({}));
+  let (kv, args) = ttlog::format::split_args(kv);
+  ttlog::format::render(&template, &args, &kv).into_owned()
+}
+
+#[test]
+fn log_record_is_forwarded() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  log::info!(target: "app::http", status = 200, path = "/"; "served {} bytes", 512);
+
+  let events = logger.snapshot_buffer.take_snapshot();
+  assert_eq!(events.len(), 1);
+
+  let event = &events[0];
+  assert_eq!(event.level(), LogLevel::INFO);
+  assert_eq!(message_of(logger, event), "served 512 bytes");
+  assert_eq!(
+    logger
+      .interner
+      .get_target(event.target_id)
+      .unwrap()
+      .as_ref(),
+    "app::http"
+  );
+  assert!(event.position.0 > 0);
+
+  let kv_bytes = logger.interner.get_kv(event.kv_id.unwrap().get()).unwrap();
+  let kv = ttlog::kv_codec::decode(&kv_bytes, &logger.interner).unwrap();
+  assert_eq!(kv["status"], serde_json::json!(200));
+  assert_eq!(kv["path"], serde_json::json!("/"));
+}
+
+#[test]
+fn log_respects_trace_level() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::WARN as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  log::info!("dropped");
+  log::error!("kept");
+
+  let events = logger.snapshot_buffer.take_snapshot();
+  assert_eq!(events.len(), 1);
+  assert_eq!(message_of(logger, &events[0]), "kept");
+}
+
+#[test]
+fn log_respects_module_levels() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  log::info!(target: "noisy::conn", "capped");
+  log::warn!(target: "noisy::conn", "allowed");
+  log::debug!(target: "noisy::important", "more specific prefix");
+  log::debug!(target: "noisyneighbor", "not a submodule");
+
+  let messages: Vec<String> = logger
+    .snapshot_buffer
+    .take_snapshot()
+    .iter()
+    .map(|event| message_of(logger, event))
+    .collect();
+
+  assert_eq!(
+    messages,
+    vec!["allowed", "more specific prefix", "not a submodule"]
+  );
+}
+
+#[test]
+fn log_interns_only_static_messages() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let first = 0;
+  log::info!("static message");
+  log::info!("formatted {}", first);
+  let (_, messages_before, _) = logger.interner.stats();
+
+  for i in 1..100 {
+    log::info!("formatted {}", i);
+  }
+  log::info!("static message");
+
+  let (_, messages_after, _) = logger.interner.stats();
+  assert_eq!(messages_after, messages_before);
+
+  let messages: Vec<String> = logger
+    .snapshot_buffer
+    .take_snapshot()
+    .iter()
+    .map(|event| message_of(logger, event))
+    .collect();
+  assert_eq!(messages.len(), 102);
+  assert_eq!(messages[0], "static message");
+  assert_eq!(messages[100], "formatted 99");
+}
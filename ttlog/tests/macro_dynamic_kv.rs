@@ -63,8 +63,8 @@ fn trace_macro_captures_changing_kv_values() {
       .interner
       .get_kv(kv_id.get())
       .expect("expected kv bytes in interner");
-    let kv_json: serde_json::Value =
-      serde_json::from_slice(&kv_bytes).expect("kv bytes should be valid JSON");
+    let kv_json =
+      ttlog::kv_codec::decode(&kv_bytes, &logger.interner).expect("kv bytes should decode");
 
     let i_value = kv_json
       .get("i")
@@ -81,3 +81,25 @@ fn trace_macro_captures_changing_kv_values() {
     observed
   );
 }
+
+#[test]
+fn unique_kv_values_do_not_grow_the_interner() {
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+
+  trace!(
+    request_id = "warmup",
+    "unique_kv_values_do_not_grow_the_interner"
+  );
+  let (_, _, kvs_before) = logger.interner.stats();
+
+  for i in 0..10_000 {
+    let request_id = format!("req-{}", i);
+    trace!(
+      request_id = request_id,
+      "unique_kv_values_do_not_grow_the_interner"
+    );
+  }
+
+  assert_eq!(logger.interner.stats().2, kvs_before);
+}
@@ -2,6 +2,7 @@ use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 
 use crossbeam_channel::{bounded, unbounded};
+use ttlog::directive::Directives;
 use ttlog::event::{LogEvent, LogLevel};
 use ttlog::lf_buffer::LockFreeRingBuffer;
 use ttlog::string_interner::StringInterner;
@@ -158,8 +159,7 @@ fn macro_kv_only() {
   assert!(event.kv_id.is_some(), "kv-only call should have kv_id");
 
   let kv_bytes = logger.interner.get_kv(event.kv_id.unwrap().get()).unwrap();
-  let kv: serde_json::Value = serde_json::from_slice(&kv_bytes).unwrap();
-  // i32 uses fallback (plain number), not the string-wrapped path
+  let kv = ttlog::kv_codec::decode(&kv_bytes, &logger.interner).unwrap();
   assert_eq!(kv["answer"], serde_json::json!(42));
 }
 
@@ -184,9 +184,9 @@ fn macro_message_with_kv() {
   assert!(event.kv_id.is_some());
 
   let kv_bytes = logger.interner.get_kv(event.kv_id.unwrap().get()).unwrap();
-  let kv: serde_json::Value = serde_json::from_slice(&kv_bytes).unwrap();
+  let kv = ttlog::kv_codec::decode(&kv_bytes, &logger.interner).unwrap();
   assert_eq!(kv["user"], serde_json::json!("alice"));
-  assert_eq!(kv["count"], serde_json::json!("3"));
+  assert_eq!(kv["count"], serde_json::json!(3));
 }
 
 // ── Multiple KV pairs ───────────────────────────────────────────
@@ -210,8 +210,8 @@ fn macro_multiple_kv_pairs() {
     .interner
     .get_kv(events[0].kv_id.unwrap().get())
     .unwrap();
-  let kv: serde_json::Value = serde_json::from_slice(&kv_bytes).unwrap();
-  assert_eq!(kv["a"], serde_json::json!("1"));
+  let kv = ttlog::kv_codec::decode(&kv_bytes, &logger.interner).unwrap();
+  assert_eq!(kv["a"], serde_json::json!(1));
   assert!(kv.get("b").is_some());
   assert_eq!(kv["c"], serde_json::json!(true));
 }
@@ -288,9 +288,9 @@ fn macro_kv_with_expressions() {
     .interner
     .get_kv(events[0].kv_id.unwrap().get())
     .unwrap();
-  let kv: serde_json::Value = serde_json::from_slice(&kv_bytes).unwrap();
-  assert_eq!(kv["doubled"], serde_json::json!("20"));
-  assert_eq!(kv["sum"], serde_json::json!("15"));
+  let kv = ttlog::kv_codec::decode(&kv_bytes, &logger.interner).unwrap();
+  assert_eq!(kv["doubled"], serde_json::json!(20));
+  assert_eq!(kv["sum"], serde_json::json!(15));
 }
 
 // ── Target and file are interned ────────────────────────────────
@@ -392,7 +392,156 @@ fn macro_string_kv_values() {
     .interner
     .get_kv(events[0].kv_id.unwrap().get())
     .unwrap();
-  let kv: serde_json::Value = serde_json::from_slice(&kv_bytes).unwrap();
+  let kv = ttlog::kv_codec::decode(&kv_bytes, &logger.interner).unwrap();
   assert_eq!(kv["name"], serde_json::json!("bob"));
   assert_eq!(kv["msg"], serde_json::json!("hello world"));
 }
+
+// ── Format-string interpolation ─────────────────────────────────
+
+fn kv_json(logger: &Trace, event: &LogEvent) -> serde_json::Value {
+  let kv_bytes = logger.interner.get_kv(event.kv_id.unwrap().get()).unwrap();
+  ttlog::kv_codec::decode(&kv_bytes, &logger.interner).unwrap()
+}
+
+#[test]
+fn macro_format_positional_args() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let id = 7u64;
+  info!("user {} logged in from {}", id, "eu-west");
+
+  let events = collect_events_with_message(logger, "user {} logged in from {}");
+  assert_eq!(events.len(), 1, "template should be interned verbatim");
+
+  let (kv, args) = ttlog::format::split_args(kv_json(logger, &events[0]));
+  assert_eq!(kv, serde_json::json!({}));
+  assert_eq!(
+    args,
+    vec![serde_json::json!(7), serde_json::json!("eu-west")]
+  );
+  assert_eq!(
+    ttlog::format::render("user {} logged in from {}", &args, &kv),
+    "user 7 logged in from eu-west"
+  );
+}
+
+#[test]
+fn macro_format_args_with_kv() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let elapsed = 1.5f64;
+  warn!("request took {:.1}s", elapsed, route = "/health");
+
+  let events = collect_events_with_message(logger, "request took {:.1}s");
+  assert_eq!(events.len(), 1);
+
+  let (kv, args) = ttlog::format::split_args(kv_json(logger, &events[0]));
+  assert_eq!(kv["route"], serde_json::json!("/health"));
+  assert_eq!(
+    ttlog::format::render("request took {:.1}s", &args, &kv),
+    "request took 1.5s"
+  );
+}
+
+#[test]
+fn macro_format_named_placeholders() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let shard = "s1";
+  error!("shard {shard} failed with {code}", code = 503);
+
+  let events = collect_events_with_message(logger, "shard {shard} failed with {code}");
+  assert_eq!(events.len(), 1);
+
+  // `shard` is captured from scope and recorded as a KV field.
+  let kv = kv_json(logger, &events[0]);
+  assert_eq!(kv["shard"], serde_json::json!("s1"));
+  assert_eq!(kv["code"], serde_json::json!(503));
+  assert_eq!(
+    ttlog::format::render("shard {shard} failed with {code}", &[], &kv),
+    "shard s1 failed with 503"
+  );
+}
+
+#[test]
+fn macro_format_display_only_args() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  // Neither implements `Serialize`; both are recorded through `Display`.
+  let err = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+  let path = std::path::Path::new("/var/lib/app.db");
+  error!("open {} failed: {err}", path.display());
+
+  let events = collect_events_with_message(logger, "open {} failed: {err}");
+  assert_eq!(events.len(), 1);
+
+  let (kv, args) = ttlog::format::split_args(kv_json(logger, &events[0]));
+  assert_eq!(args, vec![serde_json::json!("/var/lib/app.db")]);
+  assert_eq!(kv["err"], serde_json::json!("no such file"));
+  assert_eq!(
+    ttlog::format::render("open {} failed: {err}", &args, &kv),
+    "open /var/lib/app.db failed: no such file"
+  );
+}
+
+#[test]
+fn macro_format_args_skipped_when_filtered() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::ERROR as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let evaluated = std::cell::Cell::new(false);
+  let expensive = || {
+    evaluated.set(true);
+    1
+  };
+  debug!("filtered {}", expensive());
+
+  assert!(
+    !evaluated.get(),
+    "arguments must not be evaluated below the level"
+  );
+  assert!(collect_events_with_message(logger, "filtered {}").is_empty());
+}
+
+#[test]
+fn macro_respects_target_directives() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let log_both = || {
+    info!("directive info");
+    error!("directive error");
+  };
+
+  logger.set_directives_str("macro_tests=error").unwrap();
+  log_both();
+  let after_first = logger.snapshot_buffer.take_snapshot().len();
+
+  // The same callsites pick up new directives on their next call.
+  logger
+    .set_directives_str("macro_tests::other=error")
+    .unwrap();
+  log_both();
+  let after_second = logger.snapshot_buffer.take_snapshot().len();
+
+  logger.set_directives(Directives::new());
+  assert_eq!(after_first, 1);
+  assert_eq!(after_second, 2);
+}
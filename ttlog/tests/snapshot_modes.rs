@@ -0,0 +1,103 @@
+use ttlog::config::TtlogConfig;
+use ttlog::event::LogLevel;
+use ttlog::reload::ConfigWatcher;
+use ttlog::snapshot::{SnapshotMode, SnapshotTrigger};
+use ttlog::trace_builder::TraceBuilder;
+use ttlog::ttlog_macros::info;
+
+fn snapshot_files(dir: &std::path::Path) -> Vec<String> {
+  let mut names: Vec<String> = std::fs::read_dir(dir)
+    .unwrap()
+    .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+    .collect();
+  names.sort();
+  names
+}
+
+#[test]
+fn peek_snapshot_keeps_history_for_later_snapshots() {
+  let dir = tempfile::tempdir().unwrap();
+  let mut trace = TraceBuilder::new()
+    .service_name("snapshot-modes")
+    .storage_path(dir.path().to_str().unwrap())
+    .snapshot_interval(None)
+    .level(LogLevel::INFO)
+    .snapshot_mode(SnapshotTrigger::Manual, SnapshotMode::Peek)
+    .build_standalone()
+    .unwrap();
+
+  for i in 0..3 {
+    info!(logger: &trace, "event {}", i);
+  }
+  trace.request_snapshot("debug");
+  assert_eq!(trace.snapshot_buffer.len(), 3);
+
+  // The final snapshot on shutdown drains, and still has every event.
+  trace.shutdown();
+  assert!(trace.snapshot_buffer.is_empty());
+
+  let files = snapshot_files(dir.path());
+  assert_eq!(files.len(), 2);
+  assert!(files.iter().any(|name| name.ends_with("-debug.bin")));
+  assert!(files
+    .iter()
+    .any(|name| name.ends_with("-flush_and_exit.bin")));
+}
+
+#[test]
+fn manual_snapshots_drain_by_default() {
+  let dir = tempfile::tempdir().unwrap();
+  let trace = TraceBuilder::new()
+    .service_name("snapshot-modes-drain")
+    .storage_path(dir.path().to_str().unwrap())
+    .snapshot_interval(None)
+    .level(LogLevel::INFO)
+    .build_standalone()
+    .unwrap();
+
+  info!(logger: &trace, "event");
+  trace.request_snapshot("debug");
+  assert!(trace.snapshot_buffer.is_empty());
+  assert_eq!(snapshot_files(dir.path()).len(), 1);
+}
+
+#[test]
+fn reload_switches_snapshot_modes() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("ttlog.json");
+  let write_config = |modes: &str| {
+    let config = format!(
+      r#"{{
+        "service_name": "snapshot-modes-reload",
+        "level": "info",
+        "storage": {{ "path": "{}" }},
+        "snapshots": {{ "interval_secs": 0, "panic": false, "modes": {} }}
+      }}"#,
+      dir.path().display(),
+      modes
+    );
+    std::fs::write(&path, config).unwrap();
+  };
+
+  write_config("{}");
+  let config = TtlogConfig::from_file(&path).unwrap();
+  let trace = config
+    .clone()
+    .into_builder()
+    .unwrap()
+    .build_standalone()
+    .unwrap();
+  let watcher = ConfigWatcher::new(&trace, &path, config, Vec::new());
+
+  info!(logger: &trace, "before");
+  trace.request_snapshot("drained");
+  assert!(trace.snapshot_buffer.is_empty());
+
+  write_config(r#"{ "manual": "peek" }"#);
+  watcher.reload().unwrap();
+  for i in 0..3 {
+    info!(logger: &trace, "event {}", i);
+  }
+  trace.request_snapshot("peeked");
+  assert!(trace.snapshot_buffer.len() >= 3);
+}
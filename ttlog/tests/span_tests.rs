@@ -0,0 +1,130 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+
+use crossbeam_channel::{bounded, unbounded};
+use ttlog::event::{LogEvent, LogLevel};
+use ttlog::lf_buffer::LockFreeRingBuffer;
+use ttlog::span;
+use ttlog::string_interner::StringInterner;
+use ttlog::trace::{EventBroadcast, ListenerMessage, Message, Trace, GLOBAL_LOGGER};
+use ttlog::ttlog_macros::{debug_span, info, info_span, span};
+
+static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+fn lock_tests() -> std::sync::MutexGuard<'static, ()> {
+  TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn ensure_global_logger() -> &'static Trace {
+  if let Some(logger) = GLOBAL_LOGGER.get() {
+    return logger;
+  }
+
+  let (msg_tx, _msg_rx) = bounded::<Message>(8);
+  let (listener_tx, _listener_rx) = bounded::<ListenerMessage>(8);
+  let (event_tx, _event_rx) = unbounded::<EventBroadcast>();
+
+  let trace = Trace::new(
+    msg_tx,
+    listener_tx,
+    event_tx,
+    Arc::new(StringInterner::new()),
+    Arc::new(LockFreeRingBuffer::<LogEvent>::new(128)),
+  );
+
+  let _ = GLOBAL_LOGGER.set(trace);
+  GLOBAL_LOGGER.get().unwrap()
+}
+
+fn kv_json(logger: &Trace, event: &LogEvent) -> serde_json::Value {
+  let kv_bytes = logger.interner.get_kv(event.kv_id.unwrap().get()).unwrap();
+  ttlog::kv_codec::decode(&kv_bytes, &logger.interner).unwrap()
+}
+
+fn message(logger: &Trace, event: &LogEvent) -> String {
+  logger
+    .interner
+    .get_message(event.message_id.unwrap().get())
+    .unwrap()
+    .to_string()
+}
+
+#[test]
+fn span_lifecycle_events() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let bytes = u64::MAX;
+  let request = info_span!("request", user = "alice", attempt = 2, bytes = bytes);
+  let request_id = request.id();
+  {
+    let _entered = request.enter();
+    info!("inside");
+  }
+  drop(request);
+
+  let events = logger.snapshot_buffer.take_snapshot();
+  assert_eq!(events.len(), 4);
+  assert!(events.iter().all(|e| e.span_id == request_id));
+
+  let enter = kv_json(logger, &events[0]);
+  assert_eq!(message(logger, &events[0]), "request");
+  assert_eq!(enter[span::EVENT_KEY], "enter");
+  assert_eq!(enter[span::PARENT_KEY], 0);
+  assert_eq!(enter["user"], "alice");
+  assert_eq!(enter["attempt"], 2);
+  assert_eq!(enter["bytes"], u64::MAX);
+
+  assert_eq!(message(logger, &events[1]), "inside");
+  assert_eq!(kv_json(logger, &events[2])[span::EVENT_KEY], "exit");
+
+  let close = kv_json(logger, &events[3]);
+  assert_eq!(close[span::EVENT_KEY], "close");
+  assert!(close[span::DURATION_KEY].as_u64().unwrap() >= close[span::BUSY_KEY].as_u64().unwrap());
+}
+
+#[test]
+fn nested_spans_record_parent() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let outer = span!(WARN, "outer");
+  let inner_id = outer.in_scope(|| {
+    let inner = debug_span!("inner");
+    inner.in_scope(|| info!("deep"));
+    inner.id()
+  });
+
+  let events = logger.snapshot_buffer.take_snapshot();
+  let inner_enter = events
+    .iter()
+    .find(|e| e.span_id == inner_id && message(logger, e) == "inner")
+    .unwrap();
+  assert_eq!(kv_json(logger, inner_enter)[span::PARENT_KEY], outer.id());
+  assert_eq!(inner_enter.level(), LogLevel::DEBUG);
+
+  let deep = events
+    .iter()
+    .find(|e| message(logger, e) == "deep")
+    .unwrap();
+  assert_eq!(deep.span_id, inner_id);
+}
+
+#[test]
+fn filtered_span_is_disabled() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::WARN as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let span = info_span!("quiet");
+  assert!(span.is_disabled());
+  span.in_scope(|| {});
+  drop(span);
+
+  assert!(logger.snapshot_buffer.take_snapshot().is_empty());
+}
@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use ttlog::event::{LogEvent, LogLevel};
+use ttlog::listener::LogListener;
+use ttlog::string_interner::StringInterner;
+use ttlog::trace::{Trace, GLOBAL_LOGGER};
+use ttlog::trace_builder::TraceBuilder;
+use ttlog::ttlog_macros::{debug, error, info, info_span, warn};
+
+#[derive(Default)]
+struct CountingListener {
+  handled: AtomicUsize,
+}
+
+impl LogListener for CountingListener {
+  fn handle(&self, _event: &LogEvent, _interner: &StringInterner) {
+    self.handled.fetch_add(1, Ordering::SeqCst);
+  }
+}
+
+fn standalone(name: &str, level: LogLevel) -> (Trace, Arc<CountingListener>) {
+  let listener = Arc::new(CountingListener::default());
+  let trace = TraceBuilder::new()
+    .service_name(name)
+    .snapshot_interval(None)
+    .level(level)
+    .listener(listener.clone())
+    .build_standalone()
+    .expect("standalone instances never conflict");
+  (trace, listener)
+}
+
+fn messages(trace: &Trace) -> Vec<String> {
+  trace
+    .snapshot_buffer
+    .take_snapshot()
+    .iter()
+    .filter_map(|event| event.message_id)
+    .filter_map(|id| trace.interner.get_message(id.get()))
+    .map(|message| message.to_string())
+    .collect()
+}
+
+#[test]
+fn standalone_instances_are_independent() {
+  let (mut first, first_events) = standalone("first", LogLevel::INFO);
+  let (mut second, second_events) = standalone("second", LogLevel::WARN);
+  assert!(GLOBAL_LOGGER.get().is_none());
+
+  for _ in 0..2 {
+    info!(logger: &first, "to first");
+    info!(logger: &second, "below second's level");
+    warn!(logger: &second, "to second {}", 42);
+  }
+  debug!("no logger in scope");
+
+  assert_eq!(messages(&first), ["to first", "to first"]);
+  assert_eq!(
+    messages(&second),
+    ["to second {}", "to second {}"],
+    "same callsites resolve against each logger's own interner",
+  );
+
+  first.set_level(LogLevel::ERROR);
+  assert_eq!(second.get_level(), LogLevel::WARN);
+
+  first.shutdown();
+  second.shutdown();
+  assert_eq!(first_events.handled.load(Ordering::SeqCst), 2);
+  assert_eq!(second_events.handled.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn scoped_logger_receives_macro_events() {
+  let (mut outer, _) = standalone("outer", LogLevel::INFO);
+  let (mut inner, _) = standalone("inner", LogLevel::INFO);
+
+  {
+    let _scoped = outer.scoped();
+    info!("outer event");
+
+    inner.in_scope(|| {
+      let span = info_span!("inner span");
+      span.in_scope(|| error!("inner event"));
+    });
+
+    error!(logger: &inner, "explicit wins over scope");
+  }
+  info!("after scope");
+
+  assert_eq!(messages(&outer), ["outer event"]);
+  assert_eq!(
+    messages(&inner),
+    [
+      "inner span",
+      "inner event",
+      "inner span",
+      "inner span",
+      "explicit wins over scope"
+    ],
+  );
+
+  outer.shutdown();
+  inner.shutdown();
+}
+
+#[test]
+fn events_are_stamped_with_sequence_and_monotonic_time() {
+  let (mut trace, _) = standalone("clock", LogLevel::INFO);
+
+  for i in 0..64 {
+    info!(logger: &trace, "tick {}", i);
+  }
+
+  let events = trace.snapshot_buffer.take_snapshot();
+  assert_eq!(events.len(), 64);
+  for pair in events.windows(2) {
+    assert!(pair[0].seq < pair[1].seq);
+    assert!(pair[0].mono_ns <= pair[1].mono_ns);
+  }
+  assert!(events.iter().all(|event| event.mono_ns > 0));
+
+  trace.shutdown();
+}
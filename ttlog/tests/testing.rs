@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use ttlog::event::LogLevel;
+use ttlog::testing::{capture, CaptureListener, EventMatcher};
+use ttlog::trace_builder::TraceBuilder;
+use ttlog::ttlog_macros::{debug, error, info, warn};
+
+fn charge(user_id: u32, amount: u64) {
+  debug!("charging", user_id = user_id);
+  if amount > 100 {
+    error!("payment failed", user_id = user_id, amount = amount);
+  } else {
+    info!("payment ok", user_id = user_id);
+  }
+}
+
+#[test]
+fn capture_collects_events_of_the_closure() {
+  let logs = capture(|| charge(5, 500));
+
+  assert_eq!(logs.len(), 2);
+  logs.assert_logged(
+    &EventMatcher::new()
+      .level(LogLevel::ERROR)
+      .message("payment failed")
+      .kv("user_id", 5),
+  );
+  logs.assert_not_logged(&EventMatcher::new().message("payment ok"));
+}
+
+#[test]
+fn captures_in_parallel_tests_stay_apart() {
+  let handles: Vec<_> = (0..8)
+    .map(|user_id| {
+      std::thread::spawn(move || {
+        let logs = capture(|| {
+          for _ in 0..10 {
+            charge(user_id, 10);
+          }
+        });
+        assert_eq!(logs.count(&EventMatcher::new().kv("user_id", user_id)), 20);
+        assert_eq!(logs.len(), 20);
+      })
+    })
+    .collect();
+
+  for handle in handles {
+    handle.join().unwrap();
+  }
+}
+
+#[test]
+fn capture_keeps_every_event_and_its_fields() {
+  let logs = capture(|| {
+    for user_id in 0..500u32 {
+      charge(user_id, 10);
+    }
+  });
+
+  assert_eq!(logs.len(), 1_000);
+  for user_id in 0..500u32 {
+    assert_eq!(
+      logs.count(&EventMatcher::new().kv("user_id", user_id)),
+      2,
+      "user {}",
+      user_id
+    );
+  }
+}
+
+#[test]
+fn capture_renders_messages() {
+  let logs = capture(|| {
+    let attempt = 3;
+    warn!("retry {attempt} of {}", 5);
+  });
+  assert_eq!(logs.messages(), ["retry 3 of 5"]);
+}
+
+#[test]
+fn capture_listener_works_on_a_running_logger() {
+  let listener = Arc::new(CaptureListener::new());
+  let mut trace = TraceBuilder::new()
+    .level(LogLevel::INFO)
+    .snapshot_interval(None)
+    .listener(listener.clone())
+    .build_standalone()
+    .unwrap();
+
+  info!(logger: &trace, "first");
+  trace.flush_listeners();
+  assert_eq!(listener.captured().messages(), ["first"]);
+
+  info!(logger: &trace, "second");
+  trace.flush_listeners();
+  assert_eq!(listener.take().messages(), ["first", "second"]);
+  assert!(listener.captured().is_empty());
+
+  trace.snapshot_buffer.take_snapshot();
+  trace.shutdown();
+}
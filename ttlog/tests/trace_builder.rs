@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use ttlog::event::{LogEvent, LogLevel};
+use ttlog::listener::{ListenerFilter, LogListener};
+use ttlog::snapshot::StorageLayout;
+use ttlog::string_interner::StringInterner;
+use ttlog::trace::GLOBAL_LOGGER;
+use ttlog::trace_builder::{TraceBuilder, TraceError};
+use ttlog::ttlog_macros::{debug, info, warn};
+
+#[derive(Default)]
+struct CountingListener {
+  handled: AtomicUsize,
+}
+
+impl LogListener for CountingListener {
+  fn handle(&self, _event: &LogEvent, _interner: &StringInterner) {
+    self.handled.fetch_add(1, Ordering::SeqCst);
+  }
+}
+
+// GLOBAL_LOGGER can only be set once per process, so the whole lifecycle is
+// exercised in a single test.
+#[test]
+fn builder_installs_global_logger_once() {
+  let dir = tempfile::tempdir().unwrap();
+  let all = Arc::new(CountingListener::default());
+  let warnings = Arc::new(CountingListener::default());
+
+  let mut trace = TraceBuilder::new()
+    .service_name("builder_test")
+    .capacity(64)
+    .storage_path(dir.path().to_str().unwrap())
+    .storage_layout(StorageLayout::PerService)
+    .snapshot_interval(None)
+    .level(LogLevel::INFO)
+    .panic_hook(false)
+    .listener(all.clone())
+    .listener_with_filter(
+      warnings.clone(),
+      ListenerFilter::new().min_level(LogLevel::WARN),
+    )
+    .build()
+    .expect("first build succeeds");
+
+  assert_eq!(trace.get_level(), LogLevel::INFO);
+  assert_eq!(GLOBAL_LOGGER.get().unwrap().get_level(), LogLevel::INFO);
+
+  let second = TraceBuilder::new().panic_hook(false).build();
+  assert!(matches!(second, Err(TraceError::AlreadyInitialized)));
+
+  debug!("filtered out");
+  info!("builder info");
+  warn!("builder warn");
+
+  trace.request_snapshot("manual");
+  trace.shutdown();
+
+  assert_eq!(all.handled.load(Ordering::SeqCst), 2);
+  assert_eq!(warnings.handled.load(Ordering::SeqCst), 1);
+
+  let service_dir = dir.path().join("builder_test");
+  let snapshots: Vec<_> = std::fs::read_dir(&service_dir).unwrap().collect();
+  assert_eq!(snapshots.len(), 1);
+}
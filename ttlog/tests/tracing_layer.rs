@@ -0,0 +1,177 @@
+#![cfg(feature = "tracing-layer")]
+
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+
+use crossbeam_channel::{bounded, unbounded};
+use tracing_subscriber::layer::SubscriberExt;
+use ttlog::event::{LogEvent, LogLevel};
+use ttlog::lf_buffer::LockFreeRingBuffer;
+use ttlog::string_interner::StringInterner;
+use ttlog::trace::{EventBroadcast, ListenerMessage, Message, Trace, GLOBAL_LOGGER};
+use ttlog::tracing_layer::TtlogLayer;
+
+static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+fn lock_tests() -> std::sync::MutexGuard<'static, ()> {
+  TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn ensure_global_logger() -> &'static Trace {
+  if let Some(logger) = GLOBAL_LOGGER.get() {
+    return logger;
+  }
+
+  let (msg_tx, _msg_rx) = bounded::<Message>(8);
+  let (listener_tx, _listener_rx) = bounded::<ListenerMessage>(8);
+  let (event_tx, _event_rx) = unbounded::<EventBroadcast>();
+
+  let trace = Trace::new(
+    msg_tx,
+    listener_tx,
+    event_tx,
+    Arc::new(StringInterner::new()),
+    Arc::new(LockFreeRingBuffer::<LogEvent>::new(128)),
+  );
+
+  let _ = GLOBAL_LOGGER.set(trace);
+  GLOBAL_LOGGER.get().unwrap()
+}
+
+fn kv_json(logger: &Trace, event: &LogEvent) -> serde_json::Value {
+  let kv_bytes = logger.interner.get_kv(event.kv_id.unwrap().get()).unwrap();
+  ttlog::kv_codec::decode(&kv_bytes, &logger.interner).unwrap()
+}
+
+fn rendered(logger: &Trace, event: &LogEvent) -> String {
+  let template = logger
+    .interner
+    .get_message(event.message_id.unwrap().get())
+    .unwrap();
+  let (kv, args) = ttlog::format::split_args(kv_json(logger, event));
+  ttlog::format::render(&template, &args, &kv).into_owned()
+}
+
+#[test]
+fn tracing_event_is_forwarded() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let subscriber = tracing_subscriber::registry().with(TtlogLayer::new());
+  tracing::subscriber::with_default(subscriber, || {
+    tracing::warn!(target: "app::db", user_id = 5u64, ok = true, "query slow");
+  });
+
+  let events = logger.snapshot_buffer.take_snapshot();
+  assert_eq!(events.len(), 1);
+
+  let event = &events[0];
+  assert_eq!(event.level(), LogLevel::WARN);
+  assert_eq!(
+    logger
+      .interner
+      .get_target(event.target_id)
+      .unwrap()
+      .as_ref(),
+    "app::db"
+  );
+  assert_eq!(rendered(logger, event), "query slow");
+  assert!(logger
+    .interner
+    .get_file(event.file_id)
+    .unwrap()
+    .contains("tracing_layer"));
+
+  let kv = kv_json(logger, event);
+  assert_eq!(kv["user_id"], serde_json::json!(5));
+  assert_eq!(kv["ok"], serde_json::json!(true));
+}
+
+#[test]
+fn tracing_span_fields_are_merged() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let subscriber = tracing_subscriber::registry().with(TtlogLayer::new());
+  tracing::subscriber::with_default(subscriber, || {
+    let outer = tracing::info_span!("request", request_id = 9u64, shard = "a");
+    let _outer = outer.enter();
+    let inner = tracing::info_span!("query", shard = "b");
+    let _inner = inner.enter();
+    tracing::info!(rows = 3u64, "done");
+  });
+
+  let events = logger.snapshot_buffer.take_snapshot();
+  assert_eq!(events.len(), 1);
+
+  let kv = kv_json(logger, &events[0]);
+  assert_eq!(kv["request_id"], serde_json::json!(9));
+  assert_eq!(kv["shard"], serde_json::json!("b"), "inner span wins");
+  assert_eq!(kv["rows"], serde_json::json!(3));
+}
+
+#[test]
+fn tracing_respects_trace_level() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::ERROR as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let subscriber = tracing_subscriber::registry().with(TtlogLayer::new());
+  tracing::subscriber::with_default(subscriber, || {
+    tracing::info!("dropped");
+    tracing::error!("kept");
+  });
+
+  let events = logger.snapshot_buffer.take_snapshot();
+  assert_eq!(events.len(), 1);
+  assert_eq!(events[0].level(), LogLevel::ERROR);
+}
+
+#[test]
+fn tracing_message_braces_are_escaped() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let subscriber = tracing_subscriber::registry().with(TtlogLayer::new());
+  tracing::subscriber::with_default(subscriber, || {
+    tracing::info!("payload {}", "{}");
+  });
+
+  let events = logger.snapshot_buffer.take_snapshot();
+  assert_eq!(rendered(logger, &events[0]), "payload {}");
+}
+
+#[test]
+fn tracing_messages_are_not_interned() {
+  let _lock = lock_tests();
+  let logger = ensure_global_logger();
+  logger.level.store(LogLevel::TRACE as u8, Ordering::Relaxed);
+  logger.snapshot_buffer.take_snapshot();
+
+  let subscriber = tracing_subscriber::registry().with(TtlogLayer::new());
+  tracing::subscriber::with_default(subscriber, || {
+    tracing::info!("warm up");
+  });
+  let (_, messages_before, _) = logger.interner.stats();
+
+  let subscriber = tracing_subscriber::registry().with(TtlogLayer::new());
+  tracing::subscriber::with_default(subscriber, || {
+    for i in 0..100 {
+      tracing::info!("request {} done", i);
+    }
+  });
+
+  let (_, messages_after, _) = logger.interner.stats();
+  assert_eq!(messages_after, messages_before);
+
+  let events = logger.snapshot_buffer.take_snapshot();
+  assert_eq!(events.len(), 101);
+  assert_eq!(rendered(logger, &events[100]), "request 99 done");
+}